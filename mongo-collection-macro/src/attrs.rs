@@ -0,0 +1,285 @@
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, DeriveInput, Field, LitInt, LitStr, Token};
+
+/// Options collected from the container-level `#[collection(...)]` attribute
+#[derive(Default)]
+pub(crate) struct CollectionArgs {
+    /// Custom collection name
+    pub name: Option<String>,
+    /// Database the collection lives in
+    pub db: Option<String>,
+    /// Field holding the tenant key of multi-tenant collections
+    pub tenant_field: Option<String>,
+    /// Path of a `fn(&NameContext) -> impl Into<Cow<'static, str>>` resolving dynamic names
+    pub name_resolver: Option<syn::Path>,
+    /// Largest page size `find_paginated` accepts for this type
+    pub max_page_size: Option<LitInt>,
+    /// Read preference mode, e.g. `"secondaryPreferred"`
+    pub read_preference: Option<LitStr>,
+    /// Read concern level, e.g. `"majority"`
+    pub read_concern: Option<LitStr>,
+    /// Write concern `w` value, e.g. `"majority"` or `"2"`
+    pub write_concern: Option<LitStr>,
+    /// Compound indexes declared with `index(keys = "...", ...)`
+    pub indexes: Vec<IndexArgs>,
+}
+
+/// A container-level `index(keys = "a, -b", unique, name = "...")` declaration
+pub(crate) struct IndexArgs {
+    /// Stored field names and their direction (`1` or `-1`)
+    pub keys: Vec<(String, i32)>,
+    pub unique: bool,
+    pub name: Option<String>,
+}
+
+impl IndexArgs {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let mut keys = None;
+        let mut unique = false;
+        let mut name = None;
+        meta.parse_nested_meta(|nested| {
+            if nested.path.is_ident("keys") {
+                let lit: LitStr = nested.value()?.parse()?;
+                keys = Some(parse_index_keys(&lit)?);
+            } else if nested.path.is_ident("unique") {
+                unique = true;
+            } else if nested.path.is_ident("name") {
+                name = Some(parse_str(&nested)?);
+            } else {
+                return Err(nested.error("unsupported index attribute"));
+            }
+            Ok(())
+        })?;
+
+        let keys = keys.ok_or_else(|| meta.error("index requires `keys = \"...\"`"))?;
+        Ok(IndexArgs { keys, unique, name })
+    }
+}
+
+/// Parses `"tenant_id, -created_at"` into `[("tenant_id", 1), ("created_at", -1)]`
+fn parse_index_keys(lit: &LitStr) -> syn::Result<Vec<(String, i32)>> {
+    let value = lit.value();
+    let mut keys = Vec::new();
+    for key in value.split(',').map(str::trim) {
+        let (field, direction) = match key.strip_prefix('-') {
+            Some(field) => (field.trim(), -1),
+            None => (key.strip_prefix('+').unwrap_or(key).trim(), 1),
+        };
+        if field.is_empty() {
+            return Err(syn::Error::new_spanned(lit, "index keys must not be empty"));
+        }
+        keys.push((field.to_string(), direction));
+    }
+    Ok(keys)
+}
+
+impl CollectionArgs {
+    /// Parses every `#[collection(...)]` attribute on the struct
+    pub fn from_input(input: &DeriveInput) -> syn::Result<Self> {
+        let mut args = CollectionArgs::default();
+
+        for attr in &input.attrs {
+            if !attr.path().is_ident("collection") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    args.name = Some(parse_str(&meta)?);
+                } else if meta.path.is_ident("db") {
+                    args.db = Some(parse_str(&meta)?);
+                } else if meta.path.is_ident("tenant_field") {
+                    args.tenant_field = Some(parse_str(&meta)?);
+                } else if meta.path.is_ident("name_resolver") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    args.name_resolver = Some(lit.parse()?);
+                } else if meta.path.is_ident("max_page_size") {
+                    let lit: LitInt = meta.value()?.parse()?;
+                    if lit.base10_parse::<u64>()? == 0 {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            "max_page_size must be at least 1",
+                        ));
+                    }
+                    args.max_page_size = Some(lit);
+                } else if meta.path.is_ident("read_preference") {
+                    args.read_preference = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("read_concern") {
+                    args.read_concern = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("write_concern") {
+                    args.write_concern = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("index") {
+                    args.indexes.push(IndexArgs::parse(&meta)?);
+                } else {
+                    return Err(meta.error("unsupported collection attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(args)
+    }
+}
+
+/// Options collected from a field-level `#[collection(...)]` attribute
+#[derive(Default)]
+pub(crate) struct FieldArgs {
+    /// Overrides the inferred BSON type used in the generated `$jsonSchema`
+    pub bson_type: Option<String>,
+    /// Leaves the field out of `Collection::export_columns`
+    pub skip_export: bool,
+    /// Lists the field in `Collection::unfilterable_fields`
+    pub skip_filter: bool,
+    /// Lists the field in `Collection::facet_fields`
+    pub facet: bool,
+    /// Embeds the field type's `DocumentSchema` in the generated `$jsonSchema`
+    pub nested: bool,
+    /// Declares an ascending single-field index
+    pub index: bool,
+    /// Declares a unique ascending single-field index
+    pub unique: bool,
+}
+
+impl FieldArgs {
+    pub fn from_field(field: &Field) -> syn::Result<Self> {
+        let mut args = FieldArgs::default();
+
+        for attr in &field.attrs {
+            if !attr.path().is_ident("collection") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bson_type") {
+                    args.bson_type = Some(parse_str(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("skip_export") {
+                    args.skip_export = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip_filter") {
+                    args.skip_filter = true;
+                    Ok(())
+                } else if meta.path.is_ident("facet") {
+                    args.facet = true;
+                    Ok(())
+                } else if meta.path.is_ident("nested") {
+                    args.nested = true;
+                    Ok(())
+                } else if meta.path.is_ident("index") {
+                    args.index = true;
+                    Ok(())
+                } else if meta.path.is_ident("unique") {
+                    args.unique = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported collection field attribute"))
+                }
+            })?;
+        }
+
+        Ok(args)
+    }
+}
+
+/// The subset of `#[serde(...)]` container attributes that affects the stored document
+#[derive(Default)]
+pub(crate) struct SerdeContainer {
+    pub rename_all: Option<String>,
+}
+
+impl SerdeContainer {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut serde = SerdeContainer::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("serde") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    serde.rename_all = parse_serialize_name(&meta)?.or(serde.rename_all.take());
+                    Ok(())
+                } else {
+                    skip_meta(&meta)
+                }
+            })?;
+        }
+
+        Ok(serde)
+    }
+}
+
+/// The subset of `#[serde(...)]` field attributes that affects the stored document
+#[derive(Default)]
+pub(crate) struct SerdeField {
+    pub rename: Option<String>,
+    /// `skip` / `skip_serializing`: the field never reaches the database
+    pub skip: bool,
+    /// `skip_serializing_if`: the field may be absent
+    pub maybe_absent: bool,
+    pub flatten: bool,
+}
+
+impl SerdeField {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut serde = SerdeField::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("serde") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    serde.rename = parse_serialize_name(&meta)?.or(serde.rename.take());
+                    Ok(())
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                    serde.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip_serializing_if") {
+                    serde.maybe_absent = true;
+                    skip_meta(&meta)
+                } else if meta.path.is_ident("flatten") {
+                    serde.flatten = true;
+                    Ok(())
+                } else {
+                    skip_meta(&meta)
+                }
+            })?;
+        }
+
+        Ok(serde)
+    }
+}
+
+/// Parses `key = "value"`
+pub(crate) fn parse_str(meta: &ParseNestedMeta) -> syn::Result<String> {
+    let lit: LitStr = meta.value()?.parse()?;
+    Ok(lit.value())
+}
+
+/// Parses either `key = "value"` or `key(serialize = "value", deserialize = "...")`,
+/// returning the name used when serializing
+fn parse_serialize_name(meta: &ParseNestedMeta) -> syn::Result<Option<String>> {
+    if meta.input.peek(Token![=]) {
+        return parse_str(meta).map(Some);
+    }
+
+    let mut name = None;
+    meta.parse_nested_meta(|nested| {
+        if nested.path.is_ident("serialize") {
+            name = Some(parse_str(&nested)?);
+        } else {
+            let _ = parse_str(&nested)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Consumes the value of an attribute we don't care about
+fn skip_meta(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        let _: syn::Expr = meta.value()?.parse()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_meta(&nested))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::attrs::CollectionArgs;
+use crate::schema::FieldSchema;
+
+/// Generates `Collection::indexes`, or nothing when no index is declared
+///
+/// Single-field indexes come first in declaration order, followed by the
+/// container-level compound indexes.
+pub(crate) fn indexes(args: &CollectionArgs, fields: &[FieldSchema]) -> TokenStream2 {
+    let single = fields.iter().filter_map(|field| {
+        let unique = field.index?;
+        Some(index_model(&[(field.name.clone(), 1)], unique, None))
+    });
+    let compound = args
+        .indexes
+        .iter()
+        .map(|index| index_model(&index.keys, index.unique, index.name.as_deref()));
+    let models: Vec<_> = single.chain(compound).collect();
+    if models.is_empty() {
+        return quote! {};
+    }
+
+    quote! {
+        fn indexes() -> ::std::vec::Vec<::mongo_collection::__private::mongodb::IndexModel> {
+            ::std::vec![#(#models),*]
+        }
+    }
+}
+
+fn index_model(keys: &[(String, i32)], unique: bool, name: Option<&str>) -> TokenStream2 {
+    let fields = keys.iter().map(|(field, _)| field);
+    let directions = keys.iter().map(|(_, direction)| direction);
+    let mut setters = Vec::new();
+    if unique {
+        setters.push(quote! { .unique(true) });
+    }
+    if let Some(name) = name {
+        setters.push(quote! { .name(::std::string::String::from(#name)) });
+    }
+    let options = if setters.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            .options(
+                ::mongo_collection::__private::mongodb::options::IndexOptions::builder()
+                    #(#setters)*
+                    .build()
+            )
+        }
+    };
+
+    quote! {
+        ::mongo_collection::__private::mongodb::IndexModel::builder()
+            .keys(::mongo_collection::__private::mongodb::bson::doc! { #(#fields: #directions),* })
+            #options
+            .build()
+    }
+}
@@ -1,7 +1,13 @@
+mod attrs;
+mod indexes;
+mod options;
+mod schema;
+
+use attrs::CollectionArgs;
 use inflector::Inflector;
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Lit, parse_macro_input};
+use syn::{Data, DeriveInput, parse_macro_input};
 
 /// Automatically derives the Collection trait implementation
 ///
@@ -27,6 +33,172 @@ use syn::{Data, DeriveInput, Lit, parse_macro_input};
 /// }
 /// // Collection name will be "users"
 /// ```
+///
+/// ## Binding the model to a database
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// #[collection(db = "billing")]
+/// struct Invoice {
+///     amount: i64,
+/// }
+/// assert_eq!(Invoice::database_name(), Some("billing"));
+/// ```
+///
+/// ## Multi-tenant collections
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// #[collection(tenant_field = "tenant_id")]
+/// struct Order {
+///     tenant_id: String,
+///     status: String,
+/// }
+/// assert_eq!(Order::tenant_field(), Some("tenant_id"));
+/// ```
+///
+/// ## Dynamic collection names
+/// ```ignore
+/// fn monthly(context: &NameContext) -> String {
+///     match context.period {
+///         Some(ref period) => format!("orders_{period}"),
+///         None => "orders".to_string(),
+///     }
+/// }
+///
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// #[collection(name_resolver = "monthly")]
+/// struct Order {
+///     total: i64,
+/// }
+/// assert_eq!(Order::resolve_name(&NameContext::new().period("2026_10")), "orders_2026_10");
+/// ```
+///
+/// ## Page size limit
+///
+/// `max_page_size` can only tighten the global `PageSizeLimits` maximum; raise the global
+/// limit to allow larger pages.
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// #[collection(max_page_size = 20)]
+/// struct LogEntry {
+///     message: String,
+/// }
+/// assert_eq!(LogEntry::max_page_size(), Some(20));
+/// ```
+///
+/// ## Read preference, read concern and write concern
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// #[collection(read_preference = "secondaryPreferred", read_concern = "majority", write_concern = "majority")]
+/// struct Invoice {
+///     amount: i64,
+/// }
+/// // Invoice::collection(&db) uses these options instead of the database defaults
+/// ```
+///
+/// ## Export columns
+///
+/// `Collection::export_columns()` lists the stored field names in declaration order and
+/// is used as the CSV header by `CollectionRepository::export`. Fields can be left out:
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// struct User {
+///     name: String,
+///     #[collection(skip_export)]
+///     password_hash: String,
+/// }
+/// assert_eq!(User::export_columns(), ["name"]);
+/// ```
+///
+/// ## Filterable fields
+///
+/// `q=` filter expressions (`FilterLanguage::for_collection`) may reference every stored
+/// field except those marked `skip_filter`, which are listed by
+/// `Collection::unfilterable_fields()`:
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// struct User {
+///     name: String,
+///     #[collection(skip_filter, skip_export)]
+///     password_hash: String,
+/// }
+/// assert_eq!(User::unfilterable_fields(), ["password_hash"]);
+/// ```
+///
+/// ## Facet fields
+///
+/// `facets=` value counts are only computed for fields marked `facet`, listed by
+/// `Collection::facet_fields()`:
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// struct Post {
+///     #[collection(facet)]
+///     status: String,
+///     title: String,
+/// }
+/// assert_eq!(Post::facet_fields(), ["status"]);
+/// ```
+///
+/// ## Indexes
+///
+/// `Collection::indexes()` lists the declared indexes. Fields marked `index` or `unique`
+/// get an ascending single-field index; compound indexes are declared on the struct, with
+/// `-` marking a descending key:
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// #[collection(index(keys = "tenant_id, -created_at"))]
+/// #[collection(index(keys = "tenant_id, slug", unique, name = "tenant_slug"))]
+/// struct Article {
+///     tenant_id: String,
+///     slug: String,
+///     #[collection(unique)]
+///     external_id: String,
+///     #[collection(index)]
+///     created_at: i64,
+/// }
+/// assert_eq!(Article::indexes().len(), 4);
+/// ```
+///
+/// ## Model registry
+///
+/// Every derived model is registered in a global inventory, so tooling can enumerate
+/// the models linked into the binary at startup:
+/// ```ignore
+/// for model in mongo_collection::registry::all() {
+///     println!("{:?}.{} {:?}", model.database_name(), model.name(), model.fields());
+/// }
+/// ```
+///
+/// ## `$jsonSchema` generation
+///
+/// `Collection::json_schema()` is generated from the field types: `Option<T>` fields
+/// are not required, `Vec<T>` becomes an array, maps become objects, and
+/// `#[serde(rename)]`, `#[serde(rename_all)]` and `#[serde(skip)]` are honored.
+/// Types the macro can't see into (nested structs, enums, newtypes, `uuid::Uuid`,
+/// `chrono::NaiveDate`, ...) are left unconstrained, because their serde attributes
+/// decide what they serialize to. The BSON type can be set per field:
+/// ```ignore
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// struct User {
+///     name: String,
+///     #[collection(bson_type = "string")]
+///     status: Status,
+/// }
+/// ```
+///
+/// Nested structs that derive `DocumentSchema` are embedded with `nested`, which also
+/// works through `Option`, `Vec` and `Box`:
+/// ```ignore
+/// #[derive(DocumentSchema, Serialize, Deserialize, Debug, Clone)]
+/// struct Address {
+///     city: String,
+/// }
+///
+/// #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+/// struct User {
+///     #[collection(nested)]
+///     address: Option<Address>, // { "bsonType": ["object", "null"], "properties": { "city": ... } }
+/// }
+/// ```
 #[proc_macro_derive(Collection, attributes(collection))]
 pub fn derive_collection(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -34,9 +206,15 @@ pub fn derive_collection(input: TokenStream) -> TokenStream {
     // Get the struct name
     let name = &input.ident;
 
-    // Look for #[collection(name = "...")] attribute
-    let collection_name =
-        get_collection_name(&input).unwrap_or_else(|| to_plural_snake_case(&name.to_string()));
+    // Parse #[collection(...)] attributes
+    let args = match CollectionArgs::from_input(&input) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let collection_name = args
+        .name
+        .clone()
+        .unwrap_or_else(|| to_plural_snake_case(&name.to_string()));
 
     // Ensure it's a struct
     match input.data {
@@ -48,45 +226,97 @@ pub fn derive_collection(input: TokenStream) -> TokenStream {
         }
     }
 
+    let fields = match schema::collect_fields(&input) {
+        Ok(fields) => fields,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let json_schema = schema::json_schema_body(&fields);
+    let export_columns = schema::export_columns_body(&fields);
+    let unfilterable_fields = schema::unfilterable_fields_body(&fields);
+    let facet_fields = schema::facet_fields_body(&fields);
+    let indexes = indexes::indexes(&args, &fields);
+    let field_infos = schema::fields_body(&fields);
+    let database_name = args.db.as_ref().map(|db| {
+        quote! {
+            fn database_name() -> Option<&'static str> {
+                Some(#db)
+            }
+        }
+    });
+    let tenant_field = args.tenant_field.as_ref().map(|field| {
+        quote! {
+            fn tenant_field() -> Option<&'static str> {
+                Some(#field)
+            }
+        }
+    });
+    let max_page_size = args.max_page_size.as_ref().map(|max| {
+        quote! {
+            fn max_page_size() -> Option<u64> {
+                Some(#max)
+            }
+        }
+    });
+    let resolve_name = args.name_resolver.as_ref().map(|resolver| {
+        quote! {
+            fn resolve_name(
+                context: &::mongo_collection::NameContext,
+            ) -> ::std::borrow::Cow<'static, str> {
+                #resolver(context).into()
+            }
+        }
+    });
+    let collection_options = match options::collection_options(&args) {
+        Ok(tokens) => tokens,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     // Generate implementation
     let expanded = quote! {
         impl Collection for #name {
             fn name() -> &'static str {
                 #collection_name
             }
-        }
-    };
 
-    TokenStream::from(expanded)
-}
+            fn json_schema() -> ::mongo_collection::__private::mongodb::bson::Document {
+                #json_schema
+            }
+
+            fn export_columns() -> &'static [&'static str] {
+                #export_columns
+            }
+
+            fn unfilterable_fields() -> &'static [&'static str] {
+                #unfilterable_fields
+            }
+
+            fn facet_fields() -> &'static [&'static str] {
+                #facet_fields
+            }
 
-/// Extracts the collection name from attributes
-fn get_collection_name(input: &DeriveInput) -> Option<String> {
-    for attr in &input.attrs {
-        if attr.path().is_ident("collection") {
-            if let Ok(meta_list) = attr.meta.require_list() {
-                // Store parsing result
-                let mut name = None;
-
-                // Parse name = "value" format
-                let _ = meta_list.parse_nested_meta(|meta| {
-                    if meta.path.is_ident("name") {
-                        let value = meta.value()?;
-                        let lit: Lit = value.parse()?;
-                        if let Lit::Str(lit_str) = lit {
-                            name = Some(lit_str.value());
-                        }
-                    }
-                    Ok(())
-                });
-
-                if name.is_some() {
-                    return name;
-                }
+            fn fields() -> &'static [::mongo_collection::FieldInfo] {
+                #field_infos
             }
+
+            #database_name
+
+            #tenant_field
+
+            #max_page_size
+
+            #indexes
+
+            #resolve_name
+
+            #collection_options
         }
-    }
-    None
+
+        ::mongo_collection::__private::inventory::submit! {
+            ::mongo_collection::ModelInfo::of::<#name>()
+        }
+    };
+
+    TokenStream::from(expanded)
 }
 
 /// Converts CamelCase to plural snake_case
@@ -172,6 +402,58 @@ pub fn derive_collection_repository(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Derives `DocumentSchema` for a struct embedded in a model
+///
+/// The schema is inferred exactly like `Collection::json_schema()`, so serde attributes
+/// and field-level `#[collection(bson_type = "...")]`/`#[collection(nested)]` are honored.
+/// Models reference it with `#[collection(nested)]`:
+/// ```ignore
+/// #[derive(DocumentSchema, Serialize, Deserialize, Debug, Clone)]
+/// struct Address {
+///     city: String,
+///     zip: Option<String>,
+/// }
+/// assert_eq!(
+///     Address::document_schema(),
+///     doc! {
+///         "bsonType": "object",
+///         "required": ["city"],
+///         "properties": {
+///             "city": { "bsonType": "string" },
+///             "zip": { "bsonType": ["string", "null"] },
+///         },
+///     }
+/// );
+/// ```
+#[proc_macro_derive(DocumentSchema, attributes(collection))]
+pub fn derive_document_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    if !matches!(input.data, Data::Struct(_)) {
+        return syn::Error::new_spanned(name, "DocumentSchema can only be derived for structs")
+            .to_compile_error()
+            .into();
+    }
+
+    let fields = match schema::collect_fields(&input) {
+        Ok(fields) => fields,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let json_schema = schema::json_schema_body(&fields);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let expanded = quote! {
+        impl #impl_generics ::mongo_collection::DocumentSchema for #name #ty_generics #where_clause {
+            fn document_schema() -> ::mongo_collection::__private::mongodb::bson::Document {
+                #json_schema
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::LitStr;
+
+use crate::attrs::CollectionArgs;
+
+/// Generates `Collection::collection_options`, or nothing when no option is declared
+pub(crate) fn collection_options(args: &CollectionArgs) -> syn::Result<TokenStream2> {
+    if args.read_preference.is_none() && args.read_concern.is_none() && args.write_concern.is_none()
+    {
+        return Ok(quote! {});
+    }
+
+    let mut setters = Vec::new();
+    if let Some(ref lit) = args.read_preference {
+        let read_preference = read_preference(lit)?;
+        setters.push(quote! {
+            .selection_criteria(
+                ::mongo_collection::__private::mongodb::options::SelectionCriteria::ReadPreference(
+                    #read_preference
+                )
+            )
+        });
+    }
+    if let Some(ref lit) = args.read_concern {
+        let read_concern = read_concern(lit);
+        setters.push(quote! { .read_concern(#read_concern) });
+    }
+    if let Some(ref lit) = args.write_concern {
+        let write_concern = write_concern(lit);
+        setters.push(quote! { .write_concern(#write_concern) });
+    }
+
+    Ok(quote! {
+        fn collection_options() -> ::mongo_collection::__private::mongodb::options::CollectionOptions {
+            ::mongo_collection::__private::mongodb::options::CollectionOptions::builder()
+                #(#setters)*
+                .build()
+        }
+    })
+}
+
+fn read_preference(lit: &LitStr) -> syn::Result<TokenStream2> {
+    let mode = match lit.value().as_str() {
+        "primary" => {
+            return Ok(quote! {
+                ::mongo_collection::__private::mongodb::options::ReadPreference::Primary
+            });
+        }
+        "primaryPreferred" => quote! { PrimaryPreferred },
+        "secondary" => quote! { Secondary },
+        "secondaryPreferred" => quote! { SecondaryPreferred },
+        "nearest" => quote! { Nearest },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "read_preference must be one of \"primary\", \"primaryPreferred\", \
+                 \"secondary\", \"secondaryPreferred\" or \"nearest\"",
+            ));
+        }
+    };
+    Ok(quote! {
+        ::mongo_collection::__private::mongodb::options::ReadPreference::#mode { options: None }
+    })
+}
+
+fn read_concern(lit: &LitStr) -> TokenStream2 {
+    let level = lit.value();
+    let constructor = match level.as_str() {
+        "local" => quote! { local() },
+        "majority" => quote! { majority() },
+        "linearizable" => quote! { linearizable() },
+        "available" => quote! { available() },
+        "snapshot" => quote! { snapshot() },
+        _ => quote! { custom(#level) },
+    };
+    quote! { ::mongo_collection::__private::mongodb::options::ReadConcern::#constructor }
+}
+
+fn write_concern(lit: &LitStr) -> TokenStream2 {
+    let w = lit.value();
+    let constructor = if w == "majority" {
+        quote! { majority() }
+    } else if let Ok(nodes) = w.parse::<u32>() {
+        quote! { nodes(#nodes) }
+    } else {
+        // Custom write concern tag set name
+        quote! { custom(#w) }
+    };
+    quote! { ::mongo_collection::__private::mongodb::options::WriteConcern::#constructor }
+}
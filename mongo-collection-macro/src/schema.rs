@@ -0,0 +1,417 @@
+use inflector::Inflector;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
+
+use crate::attrs::{FieldArgs, SerdeContainer, SerdeField};
+
+/// A `$jsonSchema` node inferred from a Rust type
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SchemaNode {
+    /// No constraint (e.g. `Bson`)
+    Any,
+    /// One or more allowed BSON types, plus the element schema for arrays
+    Typed {
+        bson_types: Vec<String>,
+        items: Option<Box<SchemaNode>>,
+    },
+    /// An embedded document described by the type's `DocumentSchema`
+    Nested { ty: Box<Type>, nullable: bool },
+}
+
+impl SchemaNode {
+    fn of(bson_type: &str) -> Self {
+        SchemaNode::Typed {
+            bson_types: vec![bson_type.to_string()],
+            items: None,
+        }
+    }
+
+    fn array(items: SchemaNode) -> Self {
+        SchemaNode::Typed {
+            bson_types: vec!["array".to_string()],
+            items: Some(Box::new(items)),
+        }
+    }
+
+    /// Allows `null` in addition to the current types
+    fn nullable(self) -> Self {
+        self.or_type("null")
+    }
+
+    /// Allows `bson_type` in addition to the current types
+    fn or_type(self, bson_type: &str) -> Self {
+        match self {
+            SchemaNode::Any => SchemaNode::Any,
+            SchemaNode::Nested { ty, nullable } => SchemaNode::Nested {
+                ty,
+                nullable: nullable || bson_type == "null",
+            },
+            SchemaNode::Typed {
+                mut bson_types,
+                items,
+            } => {
+                if !bson_types.iter().any(|t| t == bson_type) {
+                    bson_types.push(bson_type.to_string());
+                }
+                SchemaNode::Typed { bson_types, items }
+            }
+        }
+    }
+
+    /// BSON types allowed by the node; empty when unconstrained
+    fn bson_types(&self) -> Vec<String> {
+        match self {
+            SchemaNode::Any => Vec::new(),
+            SchemaNode::Typed { bson_types, .. } => bson_types.clone(),
+            SchemaNode::Nested { nullable, .. } => {
+                let mut bson_types = vec!["object".to_string()];
+                if *nullable {
+                    bson_types.push("null".to_string());
+                }
+                bson_types
+            }
+        }
+    }
+
+    /// Renders the node as the body of a `doc!` literal
+    pub fn to_tokens(&self) -> TokenStream2 {
+        match self {
+            SchemaNode::Any => quote! { {} },
+            SchemaNode::Nested { ty, nullable } => {
+                let method = if *nullable {
+                    quote! { nullable_document_schema }
+                } else {
+                    quote! { document_schema }
+                };
+                quote! { (<#ty as ::mongo_collection::DocumentSchema>::#method()) }
+            }
+            SchemaNode::Typed { bson_types, items } => {
+                let bson_type = if let [single] = bson_types.as_slice() {
+                    quote! { #single }
+                } else {
+                    quote! { [#(#bson_types),*] }
+                };
+                match items {
+                    Some(items) => {
+                        let items = items.to_tokens();
+                        quote! { { "bsonType": #bson_type, "items": #items } }
+                    }
+                    None => quote! { { "bsonType": #bson_type } },
+                }
+            }
+        }
+    }
+}
+
+/// Inferred schema of a single field
+pub(crate) struct FieldSchema {
+    /// Name of the field in the stored document
+    pub name: String,
+    pub node: SchemaNode,
+    pub required: bool,
+    /// Whether the field is listed in `Collection::export_columns`
+    pub export: bool,
+    /// Whether `q=` filter expressions may reference the field
+    pub filter: bool,
+    /// Whether `facets=` may request value counts for the field
+    pub facet: bool,
+    /// Single-field index declared with `#[collection(index)]` or `#[collection(unique)]`;
+    /// `Some(true)` for a unique index
+    pub index: Option<bool>,
+}
+
+/// Collects the schema of every serialized field of the struct
+pub(crate) fn collect_fields(input: &DeriveInput) -> syn::Result<Vec<FieldSchema>> {
+    let Data::Struct(data) = &input.data else {
+        return Ok(Vec::new());
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Ok(Vec::new());
+    };
+
+    let container = SerdeContainer::from_attrs(&input.attrs)?;
+    let mut schemas = Vec::new();
+
+    for field in &fields.named {
+        let serde = SerdeField::from_attrs(&field.attrs)?;
+        let args = FieldArgs::from_field(field)?;
+        // Flattened fields are merged into the parent and can't be described here
+        if serde.skip || serde.flatten {
+            if args.index || args.unique {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "skipped and flattened fields can't be indexed",
+                ));
+            }
+            continue;
+        }
+
+        let ident = field.ident.as_ref().expect("named field").to_string();
+        let ident = ident.strip_prefix("r#").unwrap_or(&ident).to_string();
+        let name = match (serde.rename, &container.rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(rule)) => apply_rename_rule(rule, &ident),
+            (None, None) => ident,
+        };
+
+        if args.nested && args.bson_type.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "nested and bson_type can't be combined",
+            ));
+        }
+        let (mut node, optional) = infer(&field.ty, args.nested);
+        if let Some(bson_type) = args.bson_type {
+            node = SchemaNode::of(&bson_type);
+            if optional {
+                node = node.nullable();
+            }
+        }
+        // A document inserted without `_id` gets an `ObjectId` from the driver
+        if name == "_id" && (optional || serde.maybe_absent) {
+            node = node.or_type("objectId");
+        }
+
+        schemas.push(FieldSchema {
+            name,
+            node,
+            required: !optional && !serde.maybe_absent,
+            export: !args.skip_export,
+            filter: !args.skip_filter,
+            facet: args.facet,
+            index: (args.index || args.unique).then_some(args.unique),
+        });
+    }
+
+    Ok(schemas)
+}
+
+/// Generates the body of `Collection::json_schema`
+pub(crate) fn json_schema_body(fields: &[FieldSchema]) -> TokenStream2 {
+    let names = fields.iter().map(|f| &f.name);
+    let nodes = fields.iter().map(|f| f.node.to_tokens());
+    let required: Vec<_> = fields
+        .iter()
+        .filter(|f| f.required)
+        .map(|f| &f.name)
+        .collect();
+
+    // `required` must be a non-empty array
+    let required = if required.is_empty() {
+        quote! {}
+    } else {
+        quote! { "required": [#(#required),*], }
+    };
+
+    quote! {
+        ::mongo_collection::__private::mongodb::bson::doc! {
+            "bsonType": "object",
+            #required
+            "properties": { #(#names: #nodes),* },
+        }
+    }
+}
+
+/// Generates the body of `Collection::export_columns`
+pub(crate) fn export_columns_body(fields: &[FieldSchema]) -> TokenStream2 {
+    let names = fields.iter().filter(|f| f.export).map(|f| &f.name);
+    quote! { &[#(#names),*] }
+}
+
+/// Generates the body of `Collection::unfilterable_fields`
+pub(crate) fn unfilterable_fields_body(fields: &[FieldSchema]) -> TokenStream2 {
+    let names = fields.iter().filter(|f| !f.filter).map(|f| &f.name);
+    quote! { &[#(#names),*] }
+}
+
+/// Generates the body of `Collection::facet_fields`
+pub(crate) fn facet_fields_body(fields: &[FieldSchema]) -> TokenStream2 {
+    let names = fields.iter().filter(|f| f.facet).map(|f| &f.name);
+    quote! { &[#(#names),*] }
+}
+
+/// Generates the body of `Collection::fields`
+pub(crate) fn fields_body(fields: &[FieldSchema]) -> TokenStream2 {
+    let infos = fields.iter().map(|field| {
+        let name = &field.name;
+        let bson_types = field.node.bson_types();
+        let required = field.required;
+        quote! {
+            ::mongo_collection::FieldInfo {
+                name: #name,
+                bson_types: &[#(#bson_types),*],
+                required: #required,
+            }
+        }
+    });
+    quote! { &[#(#infos),*] }
+}
+
+/// Infers the schema of a Rust type, returning whether it is an `Option`
+///
+/// With `nested`, the innermost non-container type is an embedded document described
+/// by its `DocumentSchema`
+pub(crate) fn infer(ty: &Type, nested: bool) -> (SchemaNode, bool) {
+    let recurse = |ty| infer(ty, nested);
+    match ty {
+        Type::Reference(reference) => recurse(&reference.elem),
+        Type::Paren(paren) => recurse(&paren.elem),
+        Type::Group(group) => recurse(&group.elem),
+        Type::Array(array) => (SchemaNode::array(recurse(&array.elem).0), false),
+        Type::Slice(slice) => (SchemaNode::array(recurse(&slice.elem).0), false),
+        Type::Tuple(tuple) if tuple.elems.is_empty() => (SchemaNode::of("null"), false),
+        Type::Tuple(_) => (SchemaNode::of("array"), false),
+        Type::Path(path) => {
+            let Some(segment) = path.path.segments.last() else {
+                return (SchemaNode::Any, false);
+            };
+            let ident = segment.ident.to_string();
+            let generic = first_generic(&segment.arguments);
+
+            match (ident.as_str(), generic) {
+                ("Option", Some(inner)) => (recurse(inner).0.nullable(), true),
+                ("Box" | "Arc" | "Rc" | "Cow", Some(inner)) => recurse(inner),
+                ("Vec" | "VecDeque" | "HashSet" | "BTreeSet" | "IndexSet", Some(inner)) => {
+                    (SchemaNode::array(recurse(inner).0), false)
+                }
+                _ if nested => (
+                    SchemaNode::Nested {
+                        ty: Box::new(ty.clone()),
+                        nullable: false,
+                    },
+                    false,
+                ),
+                _ => (infer_scalar(&ident, generic.is_some()), false),
+            }
+        }
+        _ => (SchemaNode::Any, false),
+    }
+}
+
+/// Maps a non-container type name to its BSON type
+fn infer_scalar(ident: &str, has_generics: bool) -> SchemaNode {
+    match ident {
+        "String" | "str" | "char" => SchemaNode::of("string"),
+        "bool" => SchemaNode::of("bool"),
+        "i8" | "i16" | "i32" | "u8" | "u16" => SchemaNode::of("int"),
+        // 64-bit integers are stored as `long`, but `int` values deserialize fine
+        "i64" | "u32" | "u64" | "isize" | "usize" => SchemaNode::Typed {
+            bson_types: vec!["long".to_string(), "int".to_string()],
+            items: None,
+        },
+        "f32" | "f64" => SchemaNode::of("double"),
+        "ObjectId" => SchemaNode::of("objectId"),
+        // `chrono::DateTime<Tz>` serializes as an RFC 3339 string by default
+        "DateTime" if has_generics => SchemaNode::of("string"),
+        "DateTime" => SchemaNode::of("date"),
+        "Decimal128" => SchemaNode::of("decimal"),
+        "Binary" => SchemaNode::of("binData"),
+        "Timestamp" => SchemaNode::of("timestamp"),
+        "Document" | "HashMap" | "BTreeMap" | "IndexMap" | "Map" => SchemaNode::of("object"),
+        // Anything else (nested structs, enums, newtypes, `uuid::Uuid` which serializes as a
+        // string while `bson::Uuid` is binary, `chrono::NaiveDate`, ...) can serialize to any
+        // BSON type depending on its serde attributes, so it is left unconstrained; use
+        // `#[collection(bson_type = "...")]` to pin it down, or `#[collection(nested)]` for
+        // structs deriving `DocumentSchema`
+        _ => SchemaNode::Any,
+    }
+}
+
+fn first_generic(arguments: &PathArguments) -> Option<&Type> {
+    let PathArguments::AngleBracketed(args) = arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+/// Applies a serde `rename_all` rule to a snake_case field name
+fn apply_rename_rule(rule: &str, field: &str) -> String {
+    match rule {
+        "lowercase" => field.to_lowercase(),
+        "UPPERCASE" => field.to_uppercase(),
+        "PascalCase" => field.to_pascal_case(),
+        "camelCase" => field.to_camel_case(),
+        "snake_case" => field.to_snake_case(),
+        "SCREAMING_SNAKE_CASE" => field.to_screaming_snake_case(),
+        "kebab-case" => field.to_kebab_case(),
+        "SCREAMING-KEBAB-CASE" => field.to_kebab_case().to_uppercase(),
+        _ => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infer_str(ty: &str) -> (SchemaNode, bool) {
+        infer(&syn::parse_str::<Type>(ty).unwrap(), false)
+    }
+
+    #[test]
+    fn test_infer_scalars_and_containers() {
+        assert_eq!(infer_str("String"), (SchemaNode::of("string"), false));
+        assert_eq!(
+            infer_str("Option<bool>").0,
+            SchemaNode::Typed {
+                bson_types: vec!["bool".to_string(), "null".to_string()],
+                items: None,
+            }
+        );
+        assert!(infer_str("Option<bool>").1);
+        assert_eq!(
+            infer_str("Vec<ObjectId>").0,
+            SchemaNode::array(SchemaNode::of("objectId"))
+        );
+        assert_eq!(infer_str("Address").0, SchemaNode::Any);
+        assert_eq!(infer_str("Option<Status>").0, SchemaNode::Any);
+        assert_eq!(infer_str("uuid::Uuid").0, SchemaNode::Any);
+        assert_eq!(infer_str("NaiveDate").0, SchemaNode::Any);
+        assert_eq!(
+            infer_str("HashMap<String, i32>").0,
+            SchemaNode::of("object")
+        );
+        assert_eq!(
+            infer_str("chrono::DateTime<Utc>").0,
+            SchemaNode::of("string")
+        );
+        assert_eq!(infer_str("bson::DateTime").0, SchemaNode::of("date"));
+    }
+
+    #[test]
+    fn test_infer_nested() {
+        let nested = |ty: &str| infer(&syn::parse_str::<Type>(ty).unwrap(), true);
+        let address = || Box::new(syn::parse_str::<Type>("Address").unwrap());
+        assert_eq!(
+            nested("Option<Address>"),
+            (
+                SchemaNode::Nested {
+                    ty: address(),
+                    nullable: true,
+                },
+                true
+            )
+        );
+        assert_eq!(
+            nested("Vec<Address>").0,
+            SchemaNode::array(SchemaNode::Nested {
+                ty: address(),
+                nullable: false,
+            })
+        );
+        assert_eq!(nested("Vec<Address>").0.bson_types(), ["array"]);
+    }
+
+    #[test]
+    fn test_apply_rename_rule() {
+        assert_eq!(apply_rename_rule("camelCase", "created_at"), "createdAt");
+        assert_eq!(apply_rename_rule("PascalCase", "created_at"), "CreatedAt");
+        assert_eq!(
+            apply_rename_rule("SCREAMING-KEBAB-CASE", "created_at"),
+            "CREATED-AT"
+        );
+    }
+}
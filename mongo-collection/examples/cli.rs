@@ -0,0 +1,52 @@
+/// 命令行工具示例：在应用中注册模型与迁移，构建自己的 `mongo-collection` 命令
+///
+/// 运行：
+/// cargo run --example cli --features cli -- --db test_db list
+/// cargo run --example cli --features cli -- --db test_db diff
+/// cargo run --example cli --features cli -- --db test_db apply
+/// cargo run --example cli --features cli -- --db test_db migrate up
+/// cargo run --example cli --features cli -- --db test_db seed fixtures/
+use mongo_collection::Collection;
+use mongo_collection::cli::Cli;
+use mongo_collection::migration::Migration;
+use mongodb::bson::{Document, doc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+struct User {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[collection(unique)]
+    email: String,
+    name: String,
+}
+
+#[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+#[collection(index(keys = "user_id, -created_at"))]
+struct Order {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    user_id: String,
+    status: String,
+    created_at: i64,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), mongodb::error::Error> {
+    // 注册所有派生了 `Collection` 的模型，也可以用 `.model::<User>()` 逐个注册
+    Cli::new()
+        .registered()
+        .migration(
+            Migration::new(1, "backfill_order_status").up(|db| async move {
+                db.collection::<Document>("orders")
+                    .update_many(
+                        doc! { "status": { "$exists": false } },
+                        doc! { "$set": { "status": "open" } },
+                    )
+                    .await?;
+                Ok(())
+            }),
+        )
+        .run()
+        .await
+}
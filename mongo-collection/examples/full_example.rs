@@ -106,13 +106,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!();
 
     // 7. 更新
-    if let Some(first_user) = all_users.first() {
-        if let Some(ref id) = first_user.id {
-            println!("✏️  7. 更新用户");
-            let update = doc! { "$set": { "age": 26 } };
-            let updated = User::update_by_id(&db, id, update).await?;
-            println!("   更新成功: {}\n", updated);
-        }
+    if let Some(first_user) = all_users.first()
+        && let Some(ref id) = first_user.id
+    {
+        println!("✏️  7. 更新用户");
+        let update = doc! { "$set": { "age": 26 } };
+        let updated = User::update_by_id(&db, id, update).await?;
+        println!("   更新成功: {}\n", updated);
     }
 
     // 8. 检查存在
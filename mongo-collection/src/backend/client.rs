@@ -0,0 +1,380 @@
+use super::{Backend, CollectionTarget, ResolvedTarget};
+use crate::utils::invalid_argument;
+use async_trait::async_trait;
+use mongodb::bson::Document;
+use mongodb::options::FindOptions;
+use mongodb::{Client, Database};
+use std::fmt;
+use std::sync::Arc;
+
+type DatabaseResolver = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// 基于 `mongodb::Client` 的后端
+///
+/// 根据模型的 `#[collection(db = "...")]` 自行选择数据库，
+/// 未声明数据库的模型使用 [`default_database`](Self::default_database)。
+/// 数据库名可以通过 [`database_prefix`](Self::database_prefix) 或
+/// [`database_resolver`](Self::database_resolver) 按环境改写。
+///
+/// `ClientBackend::from(client)` 创建不带默认数据库和改写规则的后端，此时模型必须声明数据库。
+///
+/// # 示例
+///
+/// ```ignore
+/// let backend = ClientBackend::new(client)
+///     .default_database("app")
+///     .database_prefix("staging_");
+///
+/// // Invoice 声明了 #[collection(db = "billing")]，实际访问 staging_billing
+/// let invoice = Invoice::find_by_id(&backend, id).await?;
+/// ```
+#[derive(Clone)]
+pub struct ClientBackend {
+    client: Client,
+    default_database: Option<String>,
+    resolver: Option<DatabaseResolver>,
+}
+
+impl ClientBackend {
+    /// 创建客户端后端
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            default_database: None,
+            resolver: None,
+        }
+    }
+
+    /// 设置未声明数据库的模型所使用的数据库
+    pub fn default_database(mut self, name: impl Into<String>) -> Self {
+        self.default_database = Some(name.into());
+        self
+    }
+
+    /// 为所有数据库名添加前缀（如 `"staging_"`）
+    pub fn database_prefix(self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        self.database_resolver(move |name| format!("{prefix}{name}"))
+    }
+
+    /// 设置数据库名改写规则
+    pub fn database_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// 返回底层客户端
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// 解析目标集合所在的数据库名
+    pub fn database_name(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<String, mongodb::error::Error> {
+        let name = target
+            .database
+            .as_deref()
+            .or(self.default_database.as_deref())
+            .ok_or_else(|| {
+                invalid_argument(format!(
+                    "collection {} declares no database and no default database is configured",
+                    target.name
+                ))
+            })?;
+        Ok(match self.resolver {
+            Some(ref resolver) => resolver(name),
+            None => name.to_string(),
+        })
+    }
+
+    /// 解析目标集合所在的数据库
+    pub fn database(&self, target: &CollectionTarget) -> Result<Database, mongodb::error::Error> {
+        Ok(self.client.database(&self.database_name(target)?))
+    }
+}
+
+impl fmt::Debug for ClientBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBackend")
+            .field("default_database", &self.default_database)
+            .field("resolver", &self.resolver.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<Client> for ClientBackend {
+    fn from(client: Client) -> Self {
+        Self::new(client)
+    }
+}
+
+#[async_trait]
+impl Backend for ClientBackend {
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error> {
+        self.database(target)?.insert_one(target, document).await
+    }
+
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error> {
+        self.database(target)?.insert_many(target, documents).await
+    }
+
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        Backend::find_one(&self.database(target)?, target, filter).await
+    }
+
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        self.database(target)?.find(target, filter, options).await
+    }
+
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.database(target)?.count_documents(target, filter).await
+    }
+
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.database(target)?
+            .update_one(target, filter, update)
+            .await
+    }
+
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.database(target)?
+            .update_many(target, filter, update)
+            .await
+    }
+
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.database(target)?
+            .find_one_and_update(target, filter, update)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.database(target)?.delete_one(target, filter).await
+    }
+
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.database(target)?.delete_many(target, filter).await
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.database(target)?
+            .find_one_and_delete(target, filter)
+            .await
+    }
+
+    async fn estimated_document_count(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.database(target)?
+            .estimated_document_count(target)
+            .await
+    }
+
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        self.database(target)?
+            .explain(target, filter, options)
+            .await
+    }
+
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        self.database(target)?
+            .upsert_one(target, filter, update)
+            .await
+    }
+
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        Backend::aggregate(&self.database(target)?, target, pipeline).await
+    }
+
+    async fn insert_batch(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+        ordered: bool,
+    ) -> Result<Vec<(usize, String)>, mongodb::error::Error> {
+        self.database(target)?
+            .insert_batch(target, documents, ordered)
+            .await
+    }
+
+    fn resolve(&self, target: &CollectionTarget) -> Result<ResolvedTarget, mongodb::error::Error> {
+        Ok(ResolvedTarget::new(self.database(target)?, target.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BackendExt;
+    use crate::{Collection, MemoryBackend, NameContext};
+    use mongodb::bson::{Bson, doc};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[collection(db = "billing")]
+    struct Invoice {
+        amount: i64,
+    }
+
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    struct Note {
+        text: String,
+    }
+
+    fn ledger_collection(context: &NameContext) -> String {
+        match context.period {
+            Some(ref period) => format!("ledger_{period}"),
+            None => "ledger".to_string(),
+        }
+    }
+
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[collection(
+        db = "billing",
+        tenant_field = "tenant_id",
+        name_resolver = "ledger_collection"
+    )]
+    struct Ledger {
+        tenant_id: String,
+    }
+
+    #[tokio::test]
+    async fn test_database_name_resolution() {
+        // 创建客户端不会建立连接
+        let client = Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+
+        let backend = ClientBackend::new(client.clone());
+        assert_eq!(
+            backend
+                .database_name(&CollectionTarget::of::<Invoice>())
+                .unwrap(),
+            "billing"
+        );
+        assert!(
+            backend
+                .database_name(&CollectionTarget::of::<Note>())
+                .is_err()
+        );
+
+        let backend = ClientBackend::new(client)
+            .default_database("app")
+            .database_prefix("staging_");
+        assert_eq!(
+            backend
+                .database_name(&CollectionTarget::of::<Invoice>())
+                .unwrap(),
+            "staging_billing"
+        );
+        assert_eq!(
+            backend
+                .database_name(&CollectionTarget::of::<Note>())
+                .unwrap(),
+            "staging_app"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_through_decorators() {
+        let client = Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        let backend = ClientBackend::new(client)
+            .default_database("app")
+            .database_prefix("staging_")
+            .with_name_context(NameContext::new().period("2026_10"))
+            .for_tenant("acme");
+
+        let resolved = backend.resolve(&CollectionTarget::of::<Ledger>()).unwrap();
+        assert_eq!(resolved.database.name(), "staging_billing");
+        assert_eq!(resolved.name(), "ledger_2026_10");
+        assert_eq!(resolved.tenant, Some(Bson::from("acme")));
+        assert_eq!(resolved.scope(doc! {}), doc! { "tenant_id": "acme" });
+        assert!(
+            resolved
+                .check_update(&doc! { "$set": { "tenant_id": "globex" } })
+                .is_err()
+        );
+
+        // 未声明租户字段的模型不受租户限定
+        let resolved = backend.resolve(&CollectionTarget::of::<Note>()).unwrap();
+        assert_eq!(resolved.database.name(), "staging_app");
+        assert_eq!(resolved.name(), "notes");
+        assert!(resolved.tenant.is_none());
+
+        assert!(
+            MemoryBackend::new()
+                .resolve(&CollectionTarget::of::<Note>())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,551 @@
+use super::query::{aggregate, apply_update, compare_documents, matches, upsert_document};
+use super::{Backend, CollectionTarget};
+use crate::utils::invalid_argument;
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{Bson, Document, doc};
+use mongodb::options::FindOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// 内存后端
+///
+/// 在进程内保存文档，用于在没有 MongoDB 服务器的情况下测试业务逻辑。
+/// 支持常用的查询操作符（`$eq`、`$ne`、`$gt`、`$gte`、`$lt`、`$lte`、`$in`、`$nin`、
+/// `$exists`、`$regex`、`$not`、`$size`、`$all`、`$and`、`$or`、`$nor`）、
+/// 更新操作符（`$set`、`$unset`、`$inc`、`$push`、`$addToSet`、`$pull`）
+/// 排序、跳过和限制，以及聚合阶段（`$match`、`$sort`、`$skip`、`$limit`、`$count`、
+/// `$unwind`、`$group` 的 `$sum` 与 `$facet`）；遇到其它操作符，或投影、排序规则等
+/// 会改变查询结果的 `FindOptions` 时返回错误。
+///
+/// 克隆得到的实例共享同一份数据，与 `mongodb::Database` 的语义一致。
+///
+/// # 示例
+///
+/// ```ignore
+/// let db = MemoryBackend::new();
+/// User::create(&db, &user).await?;
+/// let page = User::find_paginated(&db, doc! { "age": { "$gt": 18 } }, &query).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    collections: Arc<Mutex<HashMap<String, Vec<Document>>>>,
+}
+
+impl MemoryBackend {
+    /// 创建空的内存后端
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 返回集合中的全部文档（按插入顺序）
+    pub fn documents(&self, collection: &str) -> Vec<Document> {
+        self.lock().get(collection).cloned().unwrap_or_default()
+    }
+
+    /// 清空所有集合
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<Document>>> {
+        self.collections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 返回第一个匹配文档的位置
+    fn position(
+        documents: &[Document],
+        filter: &Document,
+    ) -> Result<Option<usize>, mongodb::error::Error> {
+        for (index, document) in documents.iter().enumerate() {
+            if matches(document, filter)? {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 插入前补全 `_id` 并检查唯一性
+    fn prepare_insert(
+        existing: &[Document],
+        mut document: Document,
+    ) -> Result<Document, mongodb::error::Error> {
+        let id = match document.get("_id") {
+            Some(id) => id.clone(),
+            None => {
+                let id = Bson::ObjectId(ObjectId::new());
+                let mut with_id = Document::new();
+                with_id.insert("_id", id.clone());
+                with_id.extend(document);
+                document = with_id;
+                id
+            }
+        };
+        if existing.iter().any(|d| d.get("_id") == Some(&id)) {
+            return Err(invalid_argument(format!("duplicate key: _id {id}")));
+        }
+        Ok(document)
+    }
+}
+
+#[async_trait]
+impl Backend for MemoryBackend {
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error> {
+        let mut collections = self.lock();
+        let documents = collections.entry(target.name.to_string()).or_default();
+        let document = Self::prepare_insert(documents, document)?;
+        documents.push(document);
+        Ok(())
+    }
+
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error> {
+        let mut collections = self.lock();
+        let existing = collections.entry(target.name.to_string()).or_default();
+        for document in documents {
+            let document = Self::prepare_insert(existing, document)?;
+            existing.push(document);
+        }
+        Ok(())
+    }
+
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        let collections = self.lock();
+        let documents = collections
+            .get(target.name.as_ref())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        Ok(Self::position(documents, &filter)?.map(|index| documents[index].clone()))
+    }
+
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        let options = options.unwrap_or_default();
+        check_find_options(&options)?;
+
+        let mut results = Vec::new();
+        {
+            let collections = self.lock();
+            for document in collections.get(target.name.as_ref()).into_iter().flatten() {
+                if matches(document, &filter)? {
+                    results.push(document.clone());
+                }
+            }
+        }
+
+        if let Some(ref sort) = options.sort {
+            results.sort_by(|a, b| compare_documents(a, b, sort));
+        }
+        let skip = options.skip.unwrap_or(0) as usize;
+        let limit = match options.limit {
+            Some(limit) if limit != 0 => limit.unsigned_abs() as usize,
+            _ => usize::MAX,
+        };
+        Ok(results.into_iter().skip(skip).take(limit).collect())
+    }
+
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let collections = self.lock();
+        let mut count = 0;
+        for document in collections.get(target.name.as_ref()).into_iter().flatten() {
+            if matches(document, &filter)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let mut collections = self.lock();
+        let Some(documents) = collections.get_mut(target.name.as_ref()) else {
+            return Ok(0);
+        };
+        match Self::position(documents, &filter)? {
+            Some(index) => Ok(apply_update(&mut documents[index], &update)? as u64),
+            None => Ok(0),
+        }
+    }
+
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let mut collections = self.lock();
+        let Some(documents) = collections.get_mut(target.name.as_ref()) else {
+            return Ok(0);
+        };
+        // 先更新副本，任一文档出错时集合保持不变
+        let mut updated = documents.clone();
+        let mut modified = 0;
+        for document in &mut updated {
+            if matches(document, &filter)? && apply_update(document, &update)? {
+                modified += 1;
+            }
+        }
+        *documents = updated;
+        Ok(modified)
+    }
+
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        let mut collections = self.lock();
+        let Some(documents) = collections.get_mut(target.name.as_ref()) else {
+            return Ok(None);
+        };
+        match Self::position(documents, &filter)? {
+            // 与 MongoDB 默认行为一致，返回更新前的文档
+            Some(index) => {
+                let before = documents[index].clone();
+                apply_update(&mut documents[index], &update)?;
+                Ok(Some(before))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        Ok(self.find_one_and_delete(target, filter).await?.is_some() as u64)
+    }
+
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let mut collections = self.lock();
+        let Some(documents) = collections.get_mut(target.name.as_ref()) else {
+            return Ok(0);
+        };
+        let mut matched = Vec::with_capacity(documents.len());
+        for document in documents.iter() {
+            matched.push(matches(document, &filter)?);
+        }
+        let mut flags = matched.into_iter();
+        let before = documents.len();
+        documents.retain(|_| !flags.next().unwrap_or(false));
+        Ok((before - documents.len()) as u64)
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        let mut collections = self.lock();
+        let Some(documents) = collections.get_mut(target.name.as_ref()) else {
+            return Ok(None);
+        };
+        Ok(Self::position(documents, &filter)?.map(|index| documents.remove(index)))
+    }
+
+    /// 内存后端没有索引，执行计划总是全集合扫描
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        let mut plan = doc! { "stage": "COLLSCAN", "filter": filter, "direction": "forward" };
+        if let Some(sort) = options.and_then(|options| options.sort) {
+            plan = doc! { "stage": "SORT", "sortPattern": sort, "inputStage": plan };
+        }
+        Ok(doc! {
+            "queryPlanner": {
+                "namespace": target.name.as_ref(),
+                "winningPlan": plan,
+                "rejectedPlans": [],
+            }
+        })
+    }
+
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        let mut collections = self.lock();
+        let documents = collections.entry(target.name.to_string()).or_default();
+        match Self::position(documents, &filter)? {
+            Some(index) => {
+                apply_update(&mut documents[index], &update)?;
+                Ok(false)
+            }
+            None => {
+                let document = upsert_document(&filter, &update)?;
+                let document = Self::prepare_insert(documents, document)?;
+                documents.push(document);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        aggregate(self.documents(&target.name), &pipeline)
+    }
+}
+
+/// 拒绝内存后端无法实现、但会改变查询结果的选项
+fn check_find_options(options: &FindOptions) -> Result<(), mongodb::error::Error> {
+    let unsupported = [
+        ("projection", options.projection.is_some()),
+        ("collation", options.collation.is_some()),
+        ("min", options.min.is_some()),
+        ("max", options.max.is_some()),
+        ("maxScan", options.max_scan.is_some()),
+        ("returnKey", options.return_key == Some(true)),
+        ("showRecordId", options.show_record_id == Some(true)),
+    ];
+    match unsupported.iter().find(|(_, set)| *set) {
+        Some((option, _)) => Err(invalid_argument(format!(
+            "unsupported find option for the memory backend: {option}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collection, CollectionRepository, PaginatedQuery, QueryError, SortOrder};
+    use mongodb::bson::doc;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct User {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        id: Option<ObjectId>,
+        name: String,
+        #[collection(facet)]
+        age: i32,
+    }
+
+    fn user(name: &str, age: i32) -> User {
+        User {
+            id: None,
+            name: name.to_string(),
+            age,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repository_on_memory_backend() {
+        let db = MemoryBackend::new();
+        User::create_many(
+            &db,
+            vec![user("alice", 30), user("bob", 17), user("carol", 25)],
+        )
+        .await
+        .unwrap();
+
+        let query = PaginatedQuery {
+            page: 1,
+            page_size: 2,
+            sort_by: Some("age".to_string()),
+            sort_order: SortOrder::Asc,
+            ..Default::default()
+        };
+        let page = User::find_paginated(&db, doc! { "age": { "$gte": 18 } }, &query)
+            .await
+            .unwrap();
+        assert_eq!(page.total_count, 2);
+        assert_eq!(page.items[0].name, "carol");
+        assert_eq!(page.items[1].name, "alice");
+
+        let bob = User::find_one(&db, doc! { "name": "bob" })
+            .await
+            .unwrap()
+            .unwrap();
+        let id = bob.id.unwrap().to_hex();
+        assert!(
+            User::update_by_id(&db, &id, doc! { "$inc": { "age": 1 } })
+                .await
+                .unwrap()
+        );
+        assert_eq!(User::find_by_id(&db, &id).await.unwrap().unwrap().age, 18);
+
+        let deleted = User::delete_many(&db, doc! { "name": { "$regex": "^[ab]" } })
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+        assert_eq!(User::count(&db, doc! {}).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_paginated_applies_model_page_limit() {
+        #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+        #[collection(max_page_size = 2)]
+        struct Tag {
+            name: String,
+        }
+
+        let db = MemoryBackend::new();
+        let query = PaginatedQuery {
+            page_size: 3,
+            ..Default::default()
+        };
+        let error = Tag::find_paginated(&db, doc! {}, &query).await.unwrap_err();
+        assert!(matches!(
+            error.get_custom::<QueryError>(),
+            Some(QueryError::OutOfRange { max: 2, .. })
+        ));
+
+        // 模型限制不能放宽全局限制
+        #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+        #[collection(max_page_size = 500)]
+        struct Log {
+            message: String,
+        }
+
+        let query = PaginatedQuery {
+            page_size: 300,
+            ..Default::default()
+        };
+        let error = Log::find_paginated(&db, doc! {}, &query).await.unwrap_err();
+        assert!(matches!(
+            error.get_custom::<QueryError>(),
+            Some(QueryError::OutOfRange { max: 100, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_find_paginated_with_facets() {
+        let db = MemoryBackend::new();
+        User::create_many(
+            &db,
+            vec![user("alice", 30), user("bob", 17), user("carol", 30)],
+        )
+        .await
+        .unwrap();
+
+        let query = PaginatedQuery::from_query_str("page_size=1&facets=age").unwrap();
+        let page = User::find_paginated(&db, doc! { "name": { "$ne": "bob" } }, &query)
+            .await
+            .unwrap();
+        assert_eq!((page.total_count, page.items.len()), (2, 1));
+        assert_eq!(page.facets["age"].len(), 1);
+        assert_eq!(page.facets["age"][0].value, Bson::Int32(30));
+        assert_eq!(page.facets["age"][0].count, 2);
+
+        let query = PaginatedQuery {
+            facets: vec!["email".to_string()],
+            ..Default::default()
+        };
+        let error = User::find_paginated(&db, doc! {}, &query)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.get_custom::<QueryError>(),
+            Some(QueryError::UnknownField { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_facets_require_opt_in() {
+        #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+        struct Account {
+            #[collection(facet)]
+            role: String,
+            password_hash: String,
+        }
+
+        let db = MemoryBackend::new();
+        let account = Account {
+            role: "admin".to_string(),
+            password_hash: "secret".to_string(),
+        };
+        Account::create(&db, &account).await.unwrap();
+
+        let query = PaginatedQuery::from_query_str("facets=role").unwrap();
+        let page = Account::find_paginated(&db, doc! {}, &query).await.unwrap();
+        assert_eq!(page.facets["role"][0].count, 1);
+
+        // schema 中存在但未标记 facet 的字段不能统计取值
+        let query = PaginatedQuery::from_query_str("facets=password_hash").unwrap();
+        let error = Account::find_paginated(&db, doc! {}, &query)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.get_custom::<QueryError>(),
+            Some(QueryError::UnknownField { field }) if field == "password_hash"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_find_options_are_rejected() {
+        let db = MemoryBackend::new();
+        User::create(&db, &user("alice", 30)).await.unwrap();
+
+        let options = FindOptions::builder()
+            .projection(doc! { "name": 1 })
+            .build();
+        assert!(User::find_many(&db, doc! {}, Some(options)).await.is_err());
+
+        let options = FindOptions::builder().limit(1).batch_size(10).build();
+        let users = User::find_many(&db, doc! {}, Some(options)).await.unwrap();
+        assert_eq!(users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_update_leaves_documents_unchanged() {
+        let db = MemoryBackend::new();
+        User::create_many(&db, vec![user("alice", 30), user("bob", 17)])
+            .await
+            .unwrap();
+        let before = db.documents("users");
+
+        let update = doc! { "$set": { "age": 1 }, "$push": { "name": "x" } };
+        assert!(User::update_many(&db, doc! {}, update).await.is_err());
+        assert_eq!(db.documents("users"), before);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_id_is_rejected() {
+        let db = MemoryBackend::new();
+        let mut alice = user("alice", 30);
+        alice.id = Some(ObjectId::new());
+        User::create(&db, &alice).await.unwrap();
+        assert!(User::create(&db, &alice).await.is_err());
+        assert_eq!(db.documents("users").len(), 1);
+    }
+}
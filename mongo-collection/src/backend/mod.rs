@@ -0,0 +1,393 @@
+//! 仓储存储后端
+//!
+//! `CollectionRepository` 的所有方法都通过 [`Backend`] 访问数据，
+//! 因此既可以传入 `mongodb::Database`，也可以传入用于测试的 [`MemoryBackend`]。
+
+mod client;
+mod memory;
+mod mongo;
+mod naming;
+mod options;
+pub(crate) mod query;
+mod slow_query;
+mod tenant;
+
+pub use client::ClientBackend;
+pub use memory::MemoryBackend;
+pub use naming::WithNameContext;
+pub use options::WithCollectionOptions;
+pub use slow_query::{SlowQuery, SlowQueryDetector, SlowQueryOptions};
+pub use tenant::TenantScoped;
+pub(crate) use tenant::ensure_scoped;
+
+use crate::{Collection, NameContext};
+use async_trait::async_trait;
+use mongodb::Database;
+use mongodb::bson::{Bson, Document};
+use mongodb::options::{CollectionOptions, FindOptions};
+use std::borrow::Cow;
+
+/// 仓储操作的目标集合
+///
+/// 由 `Collection` 的元数据生成，后端装饰器（如
+/// [`WithCollectionOptions`]）可以在转发前修改它
+#[derive(Debug, Clone)]
+pub struct CollectionTarget {
+    /// 集合名
+    pub name: Cow<'static, str>,
+    /// 模型声明的数据库名（仅在后端自行选择数据库时使用，如 [`ClientBackend`]）
+    pub database: Option<Cow<'static, str>>,
+    /// 租户字段名（见 [`TenantScoped`]）
+    pub tenant_field: Option<Cow<'static, str>>,
+    /// 动态集合名解析函数（见 [`WithNameContext`]）
+    pub name_resolver: Option<fn(&NameContext) -> Cow<'static, str>>,
+    /// 读偏好、读关注与写关注
+    pub options: CollectionOptions,
+}
+
+impl CollectionTarget {
+    /// 根据模型元数据创建目标集合
+    pub fn of<T: Collection>() -> Self {
+        Self {
+            name: Cow::Borrowed(T::name()),
+            database: T::database_name().map(Cow::Borrowed),
+            tenant_field: T::tenant_field().map(Cow::Borrowed),
+            name_resolver: Some(T::resolve_name),
+            options: T::collection_options(),
+        }
+    }
+
+    /// 创建使用默认选项的目标集合
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            database: None,
+            tenant_field: None,
+            name_resolver: None,
+            options: CollectionOptions::default(),
+        }
+    }
+}
+
+/// 后端解析出的 MongoDB 集合
+///
+/// 由 [`Backend::resolve`] 返回：`target` 已应用装饰器的改写（如动态集合名与集合选项），
+/// `tenant` 为 [`TenantScoped`] 限定的租户
+#[derive(Debug, Clone)]
+pub struct ResolvedTarget {
+    /// 集合所在的数据库
+    pub database: Database,
+    /// 解析后的目标集合
+    pub target: CollectionTarget,
+    /// 当前租户（仅对声明了租户字段的模型设置）
+    pub tenant: Option<Bson>,
+}
+
+impl ResolvedTarget {
+    /// 创建未限定租户的解析结果
+    pub fn new(database: Database, target: CollectionTarget) -> Self {
+        Self {
+            database,
+            target,
+            tenant: None,
+        }
+    }
+
+    /// 集合名
+    pub fn name(&self) -> &str {
+        &self.target.name
+    }
+
+    /// 按解析后的名称与选项打开集合
+    pub fn collection<T: Send + Sync>(&self) -> mongodb::Collection<T> {
+        self.database
+            .collection_with_options(&self.target.name, self.target.options.clone())
+    }
+
+    /// 为过滤条件加上租户条件
+    pub fn scope(&self, filter: Document) -> Document {
+        match self.tenant {
+            Some(ref tenant) => tenant::scope(&self.target, tenant, filter),
+            None => filter,
+        }
+    }
+
+    /// 为待插入文档写入租户字段，拒绝属于其它租户的文档
+    pub fn stamp(&self, document: Document) -> Result<Document, mongodb::error::Error> {
+        match self.tenant {
+            Some(ref tenant) => tenant::stamp(&self.target, tenant, document),
+            None => Ok(document),
+        }
+    }
+
+    /// 拒绝修改租户字段的更新
+    pub fn check_update(&self, update: &Document) -> Result<(), mongodb::error::Error> {
+        match self.tenant {
+            Some(_) => tenant::check_update(&self.target, update),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 仓储存储后端
+///
+/// 以目标集合和原始 BSON 文档为单位描述仓储需要的全部操作，
+/// 序列化与反序列化由 `CollectionRepository` 负责
+/// 注意：所有错误使用 MongoDB 原生错误类型 `mongodb::error::Error`
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// 插入单个文档
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error>;
+
+    /// 批量插入文档
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error>;
+
+    /// 根据条件查找单个文档
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error>;
+
+    /// 根据条件查找多个文档
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error>;
+
+    /// 统计文档数量
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error>;
+
+    /// 更新单个文档，返回被修改的文档数
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error>;
+
+    /// 更新多个文档，返回被修改的文档数
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error>;
+
+    /// 查找并更新单个文档
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error>;
+
+    /// 删除单个文档，返回被删除的文档数
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error>;
+
+    /// 删除多个文档，返回被删除的文档数
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error>;
+
+    /// 查找并删除单个文档
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error>;
+
+    /// 统计集合的大致文档数（基于集合元数据，不扫描文档）
+    async fn estimated_document_count(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.count_documents(target, Document::new()).await
+    }
+
+    /// 返回查询的执行计划（`explain` 命令在 `queryPlanner` 级别的输出）
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        let _ = (filter, options);
+        Err(crate::utils::invalid_argument(format!(
+            "explain is not supported by this backend (collection {})",
+            target.name
+        )))
+    }
+
+    /// 批量插入文档，返回插入失败的文档下标与错误信息
+    ///
+    /// `ordered` 为 `true` 时在第一个失败的文档处停止，否则继续插入其余文档；
+    /// 默认实现逐个调用 [`insert_one`](Self::insert_one)
+    async fn insert_batch(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+        ordered: bool,
+    ) -> Result<Vec<(usize, String)>, mongodb::error::Error> {
+        let mut failures = Vec::new();
+        for (index, document) in documents.into_iter().enumerate() {
+            if let Err(error) = self.insert_one(target, document).await {
+                failures.push((index, error.to_string()));
+                if ordered {
+                    break;
+                }
+            }
+        }
+        Ok(failures)
+    }
+
+    /// 原子地更新第一个匹配 `filter` 的文档，没有匹配时插入新文档，返回是否插入了新文档
+    ///
+    /// 新文档由 `filter` 中的等值条件与 `update`（包括 `$setOnInsert`）构成，
+    /// 与 MongoDB 的 `upsert: true` 相同
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        let _ = (filter, update);
+        Err(crate::utils::invalid_argument(format!(
+            "upsert is not supported by this backend (collection {})",
+            target.name
+        )))
+    }
+
+    /// 执行聚合管道，返回全部结果文档
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        let _ = pipeline;
+        Err(crate::utils::invalid_argument(format!(
+            "aggregate is not supported by this backend (collection {})",
+            target.name
+        )))
+    }
+
+    /// 后端是否已限定到单个租户
+    ///
+    /// 装饰器应转发内层后端的值；仅 [`TenantScoped`] 返回 `true`
+    fn is_tenant_scoped(&self) -> bool {
+        false
+    }
+
+    /// 解析目标集合实际所在的数据库、集合名与租户
+    ///
+    /// 供需要直接使用驱动的操作（变更流、集合管理、事务）使用，
+    /// 使它们与 CRUD 方法访问同一个集合；装饰器应在修改目标后转发给内层后端。
+    /// 不基于 MongoDB 的后端（如 [`MemoryBackend`]）返回错误
+    fn resolve(&self, target: &CollectionTarget) -> Result<ResolvedTarget, mongodb::error::Error> {
+        Err(crate::utils::invalid_argument(format!(
+            "collection {} is not backed by a MongoDB database",
+            target.name
+        )))
+    }
+}
+
+/// 后端扩展方法
+///
+/// 为所有 [`Backend`] 提供装饰器构造方法
+pub trait BackendExt: Backend {
+    /// 覆盖本次调用的读偏好、读关注与写关注
+    ///
+    /// 仅覆盖 `options` 中已设置的字段，其余沿用模型声明的选项
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let options = CollectionOptions::builder()
+    ///     .selection_criteria(SelectionCriteria::ReadPreference(ReadPreference::Primary))
+    ///     .build();
+    /// let report = Report::find_one(&db.with_collection_options(options), filter).await?;
+    /// ```
+    fn with_collection_options(&self, options: CollectionOptions) -> WithCollectionOptions<Self>
+    where
+        Self: Clone + Sized,
+    {
+        WithCollectionOptions::new(self.clone(), options)
+    }
+
+    /// 限定到单个租户
+    ///
+    /// 对声明了 `tenant_field` 的模型，所有过滤条件都会加上租户条件，
+    /// 插入的文档会写入租户字段；未声明的模型不受影响
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let acme = db.for_tenant("acme");
+    /// let orders = Order::find_all(&acme).await?;
+    /// ```
+    fn for_tenant(&self, tenant: impl Into<Bson>) -> TenantScoped<Self>
+    where
+        Self: Clone + Sized,
+    {
+        TenantScoped::new(self.clone(), tenant)
+    }
+
+    /// 按上下文解析动态集合名
+    ///
+    /// 对声明了 `name_resolver` 的模型，集合名由解析函数根据 `context` 生成；
+    /// 其余模型仍使用静态集合名
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let october = db.with_name_context(NameContext::new().period("2026_10"));
+    /// // 访问 orders_2026_10
+    /// let orders = Order::find_all(&october).await?;
+    /// ```
+    fn with_name_context(&self, context: NameContext) -> WithNameContext<Self>
+    where
+        Self: Clone + Sized,
+    {
+        WithNameContext::new(self.clone(), context)
+    }
+
+    /// 启用慢查询检测
+    ///
+    /// `find`、`count_documents` 与 `aggregate` 耗时超过阈值时报告；
+    /// 开启 [`SlowQueryOptions::explain`] 后附带执行计划，并标记大集合上的全集合扫描
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let db = db.with_slow_query_detection(SlowQueryOptions::new(Duration::from_millis(200)));
+    /// let page = User::find_paginated(&db, filter, &query).await?;
+    /// ```
+    fn with_slow_query_detection(&self, options: SlowQueryOptions) -> SlowQueryDetector<Self>
+    where
+        Self: Clone + Sized,
+    {
+        SlowQueryDetector::new(self.clone(), options)
+    }
+}
+
+impl<B: Backend + ?Sized> BackendExt for B {}
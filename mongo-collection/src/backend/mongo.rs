@@ -0,0 +1,220 @@
+use super::{Backend, CollectionTarget, ResolvedTarget};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::Database;
+use mongodb::bson::{Document, doc};
+use mongodb::error::ErrorKind;
+use mongodb::options::{FindOptions, Hint, InsertManyOptions, UpdateOptions};
+
+/// 按目标集合的名称与选项获取原始文档集合
+fn collection(db: &Database, target: &CollectionTarget) -> mongodb::Collection<Document> {
+    db.collection_with_options(&target.name, target.options.clone())
+}
+
+/// 基于 MongoDB 服务器的后端
+#[async_trait]
+impl Backend for Database {
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error> {
+        collection(self, target).insert_one(document).await?;
+        Ok(())
+    }
+
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error> {
+        collection(self, target).insert_many(documents).await?;
+        Ok(())
+    }
+
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        collection(self, target).find_one(filter).await
+    }
+
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        let cursor = collection(self, target)
+            .find(filter)
+            .with_options(options)
+            .await?;
+        cursor.try_collect().await
+    }
+
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        collection(self, target).count_documents(filter).await
+    }
+
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let result = collection(self, target).update_one(filter, update).await?;
+        Ok(result.modified_count)
+    }
+
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let result = collection(self, target).update_many(filter, update).await?;
+        Ok(result.modified_count)
+    }
+
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        collection(self, target)
+            .find_one_and_update(filter, update)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let result = collection(self, target).delete_one(filter).await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let result = collection(self, target).delete_many(filter).await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        collection(self, target).find_one_and_delete(filter).await
+    }
+
+    async fn estimated_document_count(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<u64, mongodb::error::Error> {
+        collection(self, target).estimated_document_count().await
+    }
+
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        let mut find = doc! { "find": target.name.as_ref(), "filter": filter };
+        if let Some(options) = options {
+            if let Some(sort) = options.sort {
+                find.insert("sort", sort);
+            }
+            if let Some(projection) = options.projection {
+                find.insert("projection", projection);
+            }
+            if let Some(skip) = options.skip {
+                find.insert("skip", skip as i64);
+            }
+            if let Some(limit) = options.limit {
+                find.insert("limit", limit);
+            }
+            match options.hint {
+                Some(Hint::Keys(keys)) => {
+                    find.insert("hint", keys);
+                }
+                Some(Hint::Name(name)) => {
+                    find.insert("hint", name);
+                }
+                _ => {}
+            }
+        }
+
+        let command = doc! { "explain": find, "verbosity": "queryPlanner" };
+        match target.options.selection_criteria.clone() {
+            Some(criteria) => self.run_command(command).selection_criteria(criteria).await,
+            None => self.run_command(command).await,
+        }
+    }
+
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        let options = UpdateOptions::builder().upsert(true).build();
+        let result = collection(self, target)
+            .update_one(filter, update)
+            .with_options(options)
+            .await?;
+        Ok(result.upserted_id.is_some())
+    }
+
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        let cursor = collection(self, target).aggregate(pipeline).await?;
+        cursor.try_collect().await
+    }
+
+    async fn insert_batch(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+        ordered: bool,
+    ) -> Result<Vec<(usize, String)>, mongodb::error::Error> {
+        let options = InsertManyOptions::builder().ordered(ordered).build();
+        match collection(self, target)
+            .insert_many(documents)
+            .with_options(options)
+            .await
+        {
+            Ok(_) => Ok(Vec::new()),
+            // 写关注错误不针对单个文档，整体返回
+            Err(error) => match *error.kind {
+                ErrorKind::InsertMany(ref failure) if failure.write_concern_error.is_none() => {
+                    Ok(failure
+                        .write_errors
+                        .iter()
+                        .flatten()
+                        .map(|write| (write.index, write.message.clone()))
+                        .collect())
+                }
+                _ => Err(error),
+            },
+        }
+    }
+
+    fn resolve(&self, target: &CollectionTarget) -> Result<ResolvedTarget, mongodb::error::Error> {
+        Ok(ResolvedTarget::new(self.clone(), target.clone()))
+    }
+}
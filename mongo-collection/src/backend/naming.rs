@@ -0,0 +1,267 @@
+use super::{Backend, CollectionTarget, ResolvedTarget};
+use crate::NameContext;
+use async_trait::async_trait;
+use mongodb::bson::Document;
+use mongodb::options::FindOptions;
+
+/// 按上下文解析动态集合名的后端装饰器
+///
+/// 通过 [`BackendExt::with_name_context`](super::BackendExt::with_name_context) 或
+/// [`WithNameContext::new`] 创建，可以直接交给 [`MongoRepository`](crate::MongoRepository) 持有
+#[derive(Debug, Clone)]
+pub struct WithNameContext<B> {
+    inner: B,
+    context: NameContext,
+}
+
+impl<B> WithNameContext<B> {
+    /// 创建带命名上下文的后端
+    pub fn new(inner: B, context: NameContext) -> Self {
+        Self { inner, context }
+    }
+
+    /// 返回命名上下文
+    pub fn context(&self) -> &NameContext {
+        &self.context
+    }
+
+    /// 返回内层后端
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// 使用模型的解析函数替换集合名
+    fn target(&self, target: &CollectionTarget) -> CollectionTarget {
+        let mut target = target.clone();
+        if let Some(resolver) = target.name_resolver {
+            target.name = resolver(&self.context);
+        }
+        target
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Backend for WithNameContext<B> {
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error> {
+        self.inner.insert_one(&self.target(target), document).await
+    }
+
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error> {
+        self.inner
+            .insert_many(&self.target(target), documents)
+            .await
+    }
+
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner.find_one(&self.target(target), filter).await
+    }
+
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        self.inner.find(&self.target(target), filter, options).await
+    }
+
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .count_documents(&self.target(target), filter)
+            .await
+    }
+
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .update_one(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .update_many(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner
+            .find_one_and_update(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.delete_one(&self.target(target), filter).await
+    }
+
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.delete_many(&self.target(target), filter).await
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner
+            .find_one_and_delete(&self.target(target), filter)
+            .await
+    }
+
+    async fn estimated_document_count(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .estimated_document_count(&self.target(target))
+            .await
+    }
+
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        self.inner
+            .explain(&self.target(target), filter, options)
+            .await
+    }
+
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        self.inner
+            .upsert_one(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        self.inner.aggregate(&self.target(target), pipeline).await
+    }
+
+    async fn insert_batch(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+        ordered: bool,
+    ) -> Result<Vec<(usize, String)>, mongodb::error::Error> {
+        self.inner
+            .insert_batch(&self.target(target), documents, ordered)
+            .await
+    }
+
+    fn is_tenant_scoped(&self) -> bool {
+        self.inner.is_tenant_scoped()
+    }
+
+    fn resolve(&self, target: &CollectionTarget) -> Result<ResolvedTarget, mongodb::error::Error> {
+        self.inner.resolve(&self.target(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::BackendExt;
+    use crate::{Collection, CollectionRepository, MemoryBackend, NameContext};
+    use serde::{Deserialize, Serialize};
+
+    fn order_collection(context: &NameContext) -> String {
+        let mut name = String::new();
+        if let Some(ref tenant) = context.tenant {
+            name.push_str(&format!("tenant_{tenant}_"));
+        }
+        name.push_str("orders");
+        if let Some(ref period) = context.period {
+            name.push_str(&format!("_{period}"));
+        }
+        name
+    }
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    #[collection(name_resolver = "order_collection")]
+    struct Order {
+        total: i64,
+    }
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Customer {
+        name: String,
+    }
+
+    #[test]
+    fn test_resolve_name() {
+        assert_eq!(Order::resolve_name(&NameContext::new()), "orders");
+        assert_eq!(
+            Order::resolve_name(&NameContext::new().tenant("acme").period("2026_10")),
+            "tenant_acme_orders_2026_10"
+        );
+        // 未声明解析函数的模型始终使用静态集合名
+        assert_eq!(
+            Customer::resolve_name(&NameContext::new().tenant("acme")),
+            "customers"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_routes_operations_to_resolved_collection() {
+        let backend = MemoryBackend::new();
+        let october = backend.with_name_context(NameContext::new().period("2026_10"));
+
+        Order::create(&october, &Order { total: 42 }).await.unwrap();
+        Customer::create(&october, &Customer { name: "a".into() })
+            .await
+            .unwrap();
+
+        assert_eq!(backend.documents("orders_2026_10").len(), 1);
+        assert!(backend.documents("orders").is_empty());
+        assert_eq!(backend.documents("customers").len(), 1);
+        assert_eq!(Order::find_all(&october).await.unwrap().len(), 1);
+        assert!(Order::find_all(&backend).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+use super::{Backend, CollectionTarget, ResolvedTarget};
+use async_trait::async_trait;
+use mongodb::bson::Document;
+use mongodb::options::{CollectionOptions, FindOptions};
+
+/// 覆盖集合选项的后端装饰器
+///
+/// 通过 [`BackendExt::with_collection_options`](super::BackendExt::with_collection_options) 或
+/// [`WithCollectionOptions::new`] 创建，可以直接交给 [`MongoRepository`](crate::MongoRepository) 持有
+#[derive(Debug, Clone)]
+pub struct WithCollectionOptions<B> {
+    inner: B,
+    options: CollectionOptions,
+}
+
+impl<B> WithCollectionOptions<B> {
+    /// 创建覆盖集合选项的后端
+    pub fn new(inner: B, options: CollectionOptions) -> Self {
+        Self { inner, options }
+    }
+
+    /// 返回覆盖的集合选项
+    pub fn options(&self) -> &CollectionOptions {
+        &self.options
+    }
+
+    /// 返回内层后端
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// 将覆盖选项合并到目标集合
+    fn target(&self, target: &CollectionTarget) -> CollectionTarget {
+        let mut target = target.clone();
+        if let Some(ref selection_criteria) = self.options.selection_criteria {
+            target.options.selection_criteria = Some(selection_criteria.clone());
+        }
+        if let Some(ref read_concern) = self.options.read_concern {
+            target.options.read_concern = Some(read_concern.clone());
+        }
+        if let Some(ref write_concern) = self.options.write_concern {
+            target.options.write_concern = Some(write_concern.clone());
+        }
+        target
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Backend for WithCollectionOptions<B> {
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error> {
+        self.inner.insert_one(&self.target(target), document).await
+    }
+
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error> {
+        self.inner
+            .insert_many(&self.target(target), documents)
+            .await
+    }
+
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner.find_one(&self.target(target), filter).await
+    }
+
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        self.inner.find(&self.target(target), filter, options).await
+    }
+
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .count_documents(&self.target(target), filter)
+            .await
+    }
+
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .update_one(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .update_many(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner
+            .find_one_and_update(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.delete_one(&self.target(target), filter).await
+    }
+
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.delete_many(&self.target(target), filter).await
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner
+            .find_one_and_delete(&self.target(target), filter)
+            .await
+    }
+
+    async fn estimated_document_count(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .estimated_document_count(&self.target(target))
+            .await
+    }
+
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        self.inner
+            .explain(&self.target(target), filter, options)
+            .await
+    }
+
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        self.inner
+            .upsert_one(&self.target(target), filter, update)
+            .await
+    }
+
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        self.inner.aggregate(&self.target(target), pipeline).await
+    }
+
+    async fn insert_batch(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+        ordered: bool,
+    ) -> Result<Vec<(usize, String)>, mongodb::error::Error> {
+        self.inner
+            .insert_batch(&self.target(target), documents, ordered)
+            .await
+    }
+
+    fn is_tenant_scoped(&self) -> bool {
+        self.inner.is_tenant_scoped()
+    }
+
+    fn resolve(&self, target: &CollectionTarget) -> Result<ResolvedTarget, mongodb::error::Error> {
+        self.inner.resolve(&self.target(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BackendExt;
+    use crate::{Collection, MemoryBackend};
+    use mongodb::options::{ReadConcern, ReadPreference, SelectionCriteria, WriteConcern};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[collection(
+        read_preference = "secondaryPreferred",
+        read_concern = "majority",
+        write_concern = "2"
+    )]
+    struct Invoice {
+        amount: i64,
+    }
+
+    #[test]
+    fn test_derived_collection_options() {
+        let target = CollectionTarget::of::<Invoice>();
+        assert!(matches!(
+            target.options.selection_criteria,
+            Some(SelectionCriteria::ReadPreference(
+                ReadPreference::SecondaryPreferred { options: None }
+            ))
+        ));
+        assert_eq!(target.options.read_concern, Some(ReadConcern::majority()));
+        assert_eq!(target.options.write_concern, Some(WriteConcern::nodes(2)));
+    }
+
+    #[test]
+    fn test_overrides_only_set_options() {
+        let mut target = CollectionTarget::new("reports");
+        target.options.read_concern = Some(ReadConcern::majority());
+
+        let backend = MemoryBackend::new();
+        let overrides = CollectionOptions::builder()
+            .write_concern(WriteConcern::majority())
+            .build();
+        let merged = backend.with_collection_options(overrides).target(&target);
+
+        assert_eq!(merged.options.read_concern, Some(ReadConcern::majority()));
+        assert_eq!(merged.options.write_concern, Some(WriteConcern::majority()));
+        assert!(merged.options.selection_criteria.is_none());
+    }
+}
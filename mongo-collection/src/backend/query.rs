@@ -0,0 +1,855 @@
+//! 内存后端使用的查询、更新与排序求值
+//!
+//! 仅实现常用的操作符子集，遇到不支持的操作符时返回错误而不是静默忽略
+
+use crate::utils::invalid_argument;
+use mongodb::bson::{Bson, Document, doc};
+use regex::RegexBuilder;
+use std::cmp::Ordering;
+
+type Result<T> = std::result::Result<T, mongodb::error::Error>;
+
+// ========== 查询 ==========
+
+/// 判断文档是否匹配查询条件
+pub(crate) fn matches(document: &Document, filter: &Document) -> Result<bool> {
+    for (key, condition) in filter {
+        let matched = match key.as_str() {
+            "$and" => {
+                let mut all = true;
+                for operand in logical_operands(key, condition)? {
+                    all &= matches(document, operand)?;
+                }
+                all
+            }
+            "$or" => {
+                let mut any = false;
+                for operand in logical_operands(key, condition)? {
+                    any |= matches(document, operand)?;
+                }
+                any
+            }
+            "$nor" => {
+                let mut any = false;
+                for operand in logical_operands(key, condition)? {
+                    any |= matches(document, operand)?;
+                }
+                !any
+            }
+            op if op.starts_with('$') => {
+                return Err(invalid_argument(format!(
+                    "unsupported query operator: {op}"
+                )));
+            }
+            path => matches_field(&lookup(document, path), condition)?,
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn logical_operands<'a>(op: &str, condition: &'a Bson) -> Result<Vec<&'a Document>> {
+    let Bson::Array(items) = condition else {
+        return Err(invalid_argument(format!("{op} requires an array")));
+    };
+    items
+        .iter()
+        .map(|item| match item {
+            Bson::Document(doc) => Ok(doc),
+            _ => Err(invalid_argument(format!("{op} entries must be documents"))),
+        })
+        .collect()
+}
+
+/// 按点号路径取值，路径经过数组时展开数组中的每个文档
+pub(crate) fn lookup<'a>(document: &'a Document, path: &str) -> Vec<&'a Bson> {
+    let segments: Vec<&str> = path.split('.').collect();
+    let mut values = Vec::new();
+    if let Some(value) = document.get(segments[0]) {
+        lookup_value(value, &segments[1..], &mut values);
+    }
+    values
+}
+
+fn lookup_value<'a>(value: &'a Bson, segments: &[&str], values: &mut Vec<&'a Bson>) {
+    let Some((head, rest)) = segments.split_first() else {
+        values.push(value);
+        return;
+    };
+    match value {
+        Bson::Document(doc) => {
+            if let Some(next) = doc.get(*head) {
+                lookup_value(next, rest, values);
+            }
+        }
+        Bson::Array(items) => {
+            if let Ok(index) = head.parse::<usize>() {
+                if let Some(next) = items.get(index) {
+                    lookup_value(next, rest, values);
+                }
+            } else {
+                for item in items
+                    .iter()
+                    .filter(|item| matches!(item, Bson::Document(_)))
+                {
+                    lookup_value(item, segments, values);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 判断某个字段的取值是否满足条件
+fn matches_field(values: &[&Bson], condition: &Bson) -> Result<bool> {
+    match condition {
+        Bson::Document(ops) if is_operator_document(ops) => {
+            for (op, argument) in ops {
+                if !matches_operator(values, op, argument, ops)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        Bson::RegularExpression(regex) => {
+            matches_regex(values, regex.pattern.as_str(), regex.options.as_str())
+        }
+        _ => Ok(equals_any(values, condition)),
+    }
+}
+
+fn is_operator_document(doc: &Document) -> bool {
+    doc.keys().next().is_some_and(|key| key.starts_with('$'))
+}
+
+fn matches_operator(values: &[&Bson], op: &str, argument: &Bson, ops: &Document) -> Result<bool> {
+    let matched = match op {
+        "$eq" => equals_any(values, argument),
+        "$ne" => !equals_any(values, argument),
+        "$gt" => compares_any(values, argument, |o| o == Ordering::Greater),
+        "$gte" => compares_any(values, argument, |o| o != Ordering::Less),
+        "$lt" => compares_any(values, argument, |o| o == Ordering::Less),
+        "$lte" => compares_any(values, argument, |o| o != Ordering::Greater),
+        "$in" => in_array(values, op, argument)?,
+        "$nin" => !in_array(values, op, argument)?,
+        "$exists" => values.is_empty() != is_truthy(argument),
+        "$regex" => {
+            let options = ops.get_str("$options").unwrap_or_default();
+            match argument {
+                Bson::String(pattern) => matches_regex(values, pattern, options)?,
+                Bson::RegularExpression(regex) => {
+                    let options = if options.is_empty() {
+                        regex.options.as_str()
+                    } else {
+                        options
+                    };
+                    matches_regex(values, regex.pattern.as_str(), options)?
+                }
+                _ => return Err(invalid_argument("$regex requires a string")),
+            }
+        }
+        // 与 $regex 一起处理
+        "$options" => true,
+        "$not" => !matches_field(values, argument)?,
+        "$size" => values.iter().any(|value| match value {
+            Bson::Array(items) => as_f64(argument) == Some(items.len() as f64),
+            _ => false,
+        }),
+        "$all" => {
+            let Bson::Array(required) = argument else {
+                return Err(invalid_argument("$all requires an array"));
+            };
+            required.iter().all(|item| equals_any(values, item))
+        }
+        _ => {
+            return Err(invalid_argument(format!(
+                "unsupported query operator: {op}"
+            )));
+        }
+    };
+    Ok(matched)
+}
+
+/// 字段值等于目标值，或字段为数组且包含目标值
+fn equals_any(values: &[&Bson], target: &Bson) -> bool {
+    if values.is_empty() {
+        return matches!(target, Bson::Null);
+    }
+    values.iter().any(|value| {
+        bson_equals(value, target)
+            || matches!(value, Bson::Array(items) if items.iter().any(|item| bson_equals(item, target)))
+    })
+}
+
+fn compares_any(values: &[&Bson], target: &Bson, predicate: impl Fn(Ordering) -> bool) -> bool {
+    let check = |value: &Bson| query_compare(value, target).is_some_and(&predicate);
+    values.iter().any(|value| match value {
+        Bson::Array(items) => items.iter().any(check),
+        other => check(other),
+    })
+}
+
+fn in_array(values: &[&Bson], op: &str, argument: &Bson) -> Result<bool> {
+    let Bson::Array(candidates) = argument else {
+        return Err(invalid_argument(format!("{op} requires an array")));
+    };
+    for candidate in candidates {
+        let matched = match candidate {
+            Bson::RegularExpression(regex) => {
+                matches_regex(values, regex.pattern.as_str(), regex.options.as_str())?
+            }
+            _ => equals_any(values, candidate),
+        };
+        if matched {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn matches_regex(values: &[&Bson], pattern: &str, options: &str) -> Result<bool> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(options.contains('i'))
+        .multi_line(options.contains('m'))
+        .dot_matches_new_line(options.contains('s'))
+        .ignore_whitespace(options.contains('x'))
+        .build()
+        .map_err(|e| invalid_argument(format!("invalid $regex: {e}")))?;
+    let is_match = |value: &Bson| matches!(value, Bson::String(s) if regex.is_match(s));
+    Ok(values.iter().any(|value| match value {
+        Bson::Array(items) => items.iter().any(is_match),
+        other => is_match(other),
+    }))
+}
+
+fn is_truthy(value: &Bson) -> bool {
+    match value {
+        Bson::Boolean(b) => *b,
+        Bson::Null | Bson::Undefined => false,
+        other => as_f64(other).is_none_or(|n| n != 0.0),
+    }
+}
+
+// ========== 比较 ==========
+
+fn as_f64(value: &Bson) -> Option<f64> {
+    match value {
+        Bson::Int32(n) => Some(*n as f64),
+        Bson::Int64(n) => Some(*n as f64),
+        Bson::Double(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// BSON 类型的比较顺序
+fn type_rank(value: &Bson) -> u8 {
+    match value {
+        Bson::MinKey => 0,
+        Bson::Null | Bson::Undefined => 1,
+        Bson::Int32(_) | Bson::Int64(_) | Bson::Double(_) | Bson::Decimal128(_) => 2,
+        Bson::String(_) | Bson::Symbol(_) => 3,
+        Bson::Document(_) => 4,
+        Bson::Array(_) => 5,
+        Bson::Binary(_) => 6,
+        Bson::ObjectId(_) => 7,
+        Bson::Boolean(_) => 8,
+        Bson::DateTime(_) => 9,
+        Bson::Timestamp(_) => 10,
+        Bson::RegularExpression(_) => 11,
+        Bson::MaxKey => 13,
+        _ => 12,
+    }
+}
+
+/// 按 MongoDB 的类型顺序比较两个值（用于排序）
+pub(crate) fn compare_values(a: &Bson, b: &Bson) -> Ordering {
+    let rank = type_rank(a).cmp(&type_rank(b));
+    if rank != Ordering::Equal {
+        return rank;
+    }
+    match (a, b) {
+        (Bson::String(x) | Bson::Symbol(x), Bson::String(y) | Bson::Symbol(y)) => x.cmp(y),
+        (Bson::ObjectId(x), Bson::ObjectId(y)) => x.bytes().cmp(&y.bytes()),
+        (Bson::Boolean(x), Bson::Boolean(y)) => x.cmp(y),
+        (Bson::DateTime(x), Bson::DateTime(y)) => x.cmp(y),
+        (Bson::Timestamp(x), Bson::Timestamp(y)) => {
+            (x.time, x.increment).cmp(&(y.time, y.increment))
+        }
+        (Bson::Binary(x), Bson::Binary(y)) => x.bytes.cmp(&y.bytes),
+        (Bson::Array(x), Bson::Array(y)) => x
+            .iter()
+            .zip(y.iter())
+            .map(|(x, y)| compare_values(x, y))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Bson::Document(x), Bson::Document(y)) => x
+            .iter()
+            .zip(y.iter())
+            .map(|((kx, vx), (ky, vy))| kx.cmp(ky).then_with(|| compare_values(vx, vy)))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        _ => match (as_f64(a), as_f64(b)) {
+            (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            _ => Ordering::Equal,
+        },
+    }
+}
+
+/// 查询比较：只有同类型的值才可比较
+fn query_compare(a: &Bson, b: &Bson) -> Option<Ordering> {
+    (type_rank(a) == type_rank(b)).then(|| compare_values(a, b))
+}
+
+fn bson_equals(a: &Bson, b: &Bson) -> bool {
+    a == b || (type_rank(a) == 2 && query_compare(a, b) == Some(Ordering::Equal))
+}
+
+/// 按排序文档比较两个文档
+pub(crate) fn compare_documents(a: &Document, b: &Document, sort: &Document) -> Ordering {
+    for (path, direction) in sort {
+        let left = lookup(a, path)
+            .first()
+            .copied()
+            .cloned()
+            .unwrap_or(Bson::Null);
+        let right = lookup(b, path)
+            .first()
+            .copied()
+            .cloned()
+            .unwrap_or(Bson::Null);
+        let mut ordering = compare_values(&left, &right);
+        if as_f64(direction).is_some_and(|d| d < 0.0) {
+            ordering = ordering.reverse();
+        }
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+// ========== 更新 ==========
+
+/// 对文档应用更新操作符，返回文档是否被修改
+///
+/// 更新先作用于副本，全部成功后才写回；出错时 `document` 保持不变
+pub(crate) fn apply_update(document: &mut Document, update: &Document) -> Result<bool> {
+    if !is_operator_document(update) {
+        return Err(invalid_argument(
+            "update document requires atomic operators",
+        ));
+    }
+
+    let mut updated = document.clone();
+    update_in_place(&mut updated, update, false)?;
+    if updated == *document {
+        return Ok(false);
+    }
+    *document = updated;
+    Ok(true)
+}
+
+/// 构造 upsert 插入的新文档：`filter` 中的等值条件加上 `update`（包括 `$setOnInsert`）
+pub(crate) fn upsert_document(filter: &Document, update: &Document) -> Result<Document> {
+    if !is_operator_document(update) {
+        return Err(invalid_argument(
+            "update document requires atomic operators",
+        ));
+    }
+
+    let mut document = Document::new();
+    seed_equalities(&mut document, filter)?;
+    update_in_place(&mut document, update, true)?;
+    Ok(document)
+}
+
+/// 把过滤条件（包括 `$and` 的各个分支）中的等值条件写入文档
+fn seed_equalities(document: &mut Document, filter: &Document) -> Result<()> {
+    for (key, condition) in filter {
+        if key == "$and" {
+            for operand in logical_operands(key, condition)? {
+                seed_equalities(document, operand)?;
+            }
+            continue;
+        }
+        if key.starts_with('$') {
+            continue;
+        }
+        let value = match condition {
+            Bson::RegularExpression(_) => continue,
+            Bson::Document(operators) if is_operator_document(operators) => {
+                match operators.get("$eq") {
+                    Some(value) => value.clone(),
+                    None => continue,
+                }
+            }
+            value => value.clone(),
+        };
+        if lookup(document, key)
+            .first()
+            .is_some_and(|seeded| **seeded != value)
+        {
+            return Err(invalid_argument(format!(
+                "cannot infer upsert fields: {key} is matched twice"
+            )));
+        }
+        set_path(document, key, value)?;
+    }
+    Ok(())
+}
+
+/// `inserting` 为 `true` 时（upsert 插入新文档）`$setOnInsert` 生效
+fn update_in_place(document: &mut Document, update: &Document, inserting: bool) -> Result<()> {
+    for (op, fields) in update {
+        let Bson::Document(fields) = fields else {
+            return Err(invalid_argument(format!("{op} requires a document")));
+        };
+        for (path, value) in fields {
+            match op.as_str() {
+                "$set" => set_path(document, path, value.clone())?,
+                "$unset" => {
+                    unset_path(document, path);
+                }
+                "$inc" => {
+                    let current = lookup(document, path).first().copied().cloned();
+                    let next = match current {
+                        None | Some(Bson::Null) => value.clone(),
+                        Some(current) => add_numbers(&current, value)
+                            .ok_or_else(|| invalid_argument(format!("cannot $inc {path}")))?,
+                    };
+                    set_path(document, path, next)?;
+                }
+                "$push" | "$addToSet" => {
+                    let items = match value {
+                        Bson::Document(each) if each.contains_key("$each") => {
+                            match each.get("$each") {
+                                Some(Bson::Array(items)) => items.clone(),
+                                _ => return Err(invalid_argument("$each requires an array")),
+                            }
+                        }
+                        other => vec![other.clone()],
+                    };
+                    let mut array = match lookup(document, path).first() {
+                        None => Vec::new(),
+                        Some(Bson::Array(existing)) => existing.clone(),
+                        Some(_) => return Err(invalid_argument(format!("{path} is not an array"))),
+                    };
+                    for item in items {
+                        if op == "$push" || !array.contains(&item) {
+                            array.push(item);
+                        }
+                    }
+                    set_path(document, path, Bson::Array(array))?;
+                }
+                "$pull" => {
+                    if let Some(Bson::Array(existing)) = lookup(document, path).first() {
+                        let mut kept = Vec::new();
+                        for item in existing.iter() {
+                            if !matches_field(&[item], value)? {
+                                kept.push(item.clone());
+                            }
+                        }
+                        set_path(document, path, Bson::Array(kept))?;
+                    }
+                }
+                "$setOnInsert" => {
+                    if inserting {
+                        set_path(document, path, value.clone())?;
+                    }
+                }
+                _ => {
+                    return Err(invalid_argument(format!(
+                        "unsupported update operator: {op}"
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 数值相加；整数按整数相加，`Int32` 溢出时提升为 `Int64`，`Int64` 溢出时返回 `None`
+fn add_numbers(a: &Bson, b: &Bson) -> Option<Bson> {
+    let integer = |value: &Bson| match value {
+        Bson::Int32(n) => Some(i64::from(*n)),
+        Bson::Int64(n) => Some(*n),
+        _ => None,
+    };
+    Some(match (a, b) {
+        (Bson::Int32(x), Bson::Int32(y)) => x
+            .checked_add(*y)
+            .map(Bson::Int32)
+            .unwrap_or(Bson::Int64(i64::from(*x) + i64::from(*y))),
+        (Bson::Int32(_) | Bson::Int64(_), Bson::Int32(_) | Bson::Int64(_)) => {
+            Bson::Int64(integer(a)?.checked_add(integer(b)?)?)
+        }
+        _ => Bson::Double(as_f64(a)? + as_f64(b)?),
+    })
+}
+
+fn set_path(document: &mut Document, path: &str, value: Bson) -> Result<()> {
+    match path.split_once('.') {
+        None => {
+            document.insert(path, value);
+            Ok(())
+        }
+        Some((head, rest)) => {
+            let child = document
+                .entry(head.to_string())
+                .or_insert_with(|| Bson::Document(Document::new()));
+            match child {
+                Bson::Document(child) => set_path(child, rest, value),
+                _ => Err(invalid_argument(format!("cannot set {path}"))),
+            }
+        }
+    }
+}
+
+fn unset_path(document: &mut Document, path: &str) -> Option<Bson> {
+    match path.split_once('.') {
+        None => document.remove(path),
+        Some((head, rest)) => match document.get_mut(head) {
+            Some(Bson::Document(child)) => unset_path(child, rest),
+            _ => None,
+        },
+    }
+}
+
+// ========== 聚合 ==========
+
+/// 依次执行聚合管道的各个阶段
+///
+/// 支持 `$match`、`$sort`、`$skip`、`$limit`、`$count`、`$unwind`、`$group`
+/// （`$sum` 累加器）与 `$facet`
+pub(crate) fn aggregate(
+    mut documents: Vec<Document>,
+    pipeline: &[Document],
+) -> Result<Vec<Document>> {
+    for stage in pipeline {
+        let mut entries = stage.iter();
+        let (Some((name, argument)), None) = (entries.next(), entries.next()) else {
+            return Err(invalid_argument(
+                "aggregation stages must have exactly one field",
+            ));
+        };
+        documents = match (name.as_str(), argument) {
+            ("$match", Bson::Document(filter)) => {
+                let mut matched = Vec::new();
+                for document in documents {
+                    if matches(&document, filter)? {
+                        matched.push(document);
+                    }
+                }
+                matched
+            }
+            ("$sort", Bson::Document(sort)) => {
+                documents.sort_by(|a, b| compare_documents(a, b, sort));
+                documents
+            }
+            ("$skip", count) => {
+                let count = stage_count(name, count)?;
+                documents.into_iter().skip(count).collect()
+            }
+            ("$limit", count) => {
+                let count = stage_count(name, count)?;
+                documents.into_iter().take(count).collect()
+            }
+            ("$count", Bson::String(field)) => match documents.len() {
+                0 => Vec::new(),
+                count => vec![doc! { field: count as i64 }],
+            },
+            ("$unwind", argument) => unwind(documents, argument)?,
+            ("$group", Bson::Document(group)) => group_documents(documents, group)?,
+            ("$facet", Bson::Document(facets)) => {
+                let mut output = Document::new();
+                for (facet, pipeline) in facets {
+                    let Bson::Array(pipeline) = pipeline else {
+                        return Err(invalid_argument("$facet pipelines must be arrays"));
+                    };
+                    let pipeline: Vec<Document> = pipeline
+                        .iter()
+                        .map(|stage| match stage {
+                            Bson::Document(stage) => Ok(stage.clone()),
+                            _ => Err(invalid_argument("aggregation stages must be documents")),
+                        })
+                        .collect::<Result<_>>()?;
+                    let results = aggregate(documents.clone(), &pipeline)?;
+                    output.insert(facet, results);
+                }
+                vec![output]
+            }
+            (name, _) => {
+                return Err(invalid_argument(format!(
+                    "unsupported aggregation stage for the memory backend: {name}"
+                )));
+            }
+        };
+    }
+    Ok(documents)
+}
+
+fn stage_count(stage: &str, count: &Bson) -> Result<usize> {
+    match as_f64(count) {
+        Some(count) if count >= 0.0 => Ok(count as usize),
+        _ => Err(invalid_argument(format!(
+            "{stage} requires a non-negative number"
+        ))),
+    }
+}
+
+/// 字段路径表达式 `"$a.b"` 的路径部分
+fn field_path(expression: &Bson) -> Option<&str> {
+    expression.as_str()?.strip_prefix('$')
+}
+
+/// 按点号路径取值，不展开数组
+fn get_path<'a>(document: &'a Document, path: &str) -> Option<&'a Bson> {
+    match path.split_once('.') {
+        None => document.get(path),
+        Some((head, rest)) => match document.get(head)? {
+            Bson::Document(child) => get_path(child, rest),
+            _ => None,
+        },
+    }
+}
+
+fn unwind(documents: Vec<Document>, argument: &Bson) -> Result<Vec<Document>> {
+    let (path, preserve) = match argument {
+        Bson::Document(options) => (
+            options.get("path").and_then(field_path),
+            options
+                .get_bool("preserveNullAndEmptyArrays")
+                .unwrap_or(false),
+        ),
+        path => (field_path(path), false),
+    };
+    let Some(path) = path else {
+        return Err(invalid_argument(
+            "$unwind requires a field path such as \"$tags\"",
+        ));
+    };
+
+    let mut output = Vec::new();
+    for document in documents {
+        match get_path(&document, path).cloned() {
+            Some(Bson::Array(items)) if !items.is_empty() => {
+                for item in items {
+                    let mut unwound = document.clone();
+                    set_path(&mut unwound, path, item)?;
+                    output.push(unwound);
+                }
+            }
+            // 与 MongoDB 一致，保留的空数组文档不再包含该字段
+            Some(Bson::Array(_)) if preserve => {
+                let mut document = document;
+                unset_path(&mut document, path);
+                output.push(document);
+            }
+            Some(Bson::Array(_)) | Some(Bson::Null) | None => {
+                if preserve {
+                    output.push(document);
+                }
+            }
+            Some(_) => output.push(document),
+        }
+    }
+    Ok(output)
+}
+
+/// `$group`：`_id` 为字段路径或常量，累加器仅支持 `$sum`
+fn group_documents(documents: Vec<Document>, group: &Document) -> Result<Vec<Document>> {
+    let key_expression = group
+        .get("_id")
+        .ok_or_else(|| invalid_argument("$group requires an _id"))?;
+    let evaluate = |document: &Document, expression: &Bson| match field_path(expression) {
+        Some(path) => get_path(document, path).cloned().unwrap_or(Bson::Null),
+        None => expression.clone(),
+    };
+
+    let mut groups: Vec<Document> = Vec::new();
+    for document in &documents {
+        let key = evaluate(document, key_expression);
+        let index = match groups
+            .iter()
+            .position(|group| group.get("_id").is_some_and(|id| bson_equals(id, &key)))
+        {
+            Some(index) => index,
+            None => {
+                groups.push(doc! { "_id": key });
+                groups.len() - 1
+            }
+        };
+
+        for (field, accumulator) in group.iter().filter(|(field, _)| *field != "_id") {
+            let Some(operand) = accumulator.as_document().and_then(|a| a.get("$sum")) else {
+                return Err(invalid_argument(format!(
+                    "unsupported accumulator for the memory backend in $group.{field}"
+                )));
+            };
+            let value = match evaluate(document, operand) {
+                value @ (Bson::Int32(_) | Bson::Int64(_) | Bson::Double(_)) => value,
+                _ => Bson::Int32(0),
+            };
+            let total = groups[index].get(field).cloned().unwrap_or(Bson::Int32(0));
+            // 与 MongoDB 一致，`Int64` 溢出时退化为 `Double`
+            let total = add_numbers(&total, &value)
+                .or_else(|| Some(Bson::Double(as_f64(&total)? + as_f64(&value)?)))
+                .unwrap_or(total);
+            groups[index].insert(field, total);
+        }
+    }
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    fn sample() -> Document {
+        doc! {
+            "name": "alice",
+            "age": 30,
+            "tags": ["admin", "staff"],
+            "profile": { "city": "Paris", "score": 4.5 },
+        }
+    }
+
+    #[test]
+    fn test_matches_operators() {
+        let document = sample();
+        assert!(matches(&document, &doc! { "name": "alice" }).unwrap());
+        assert!(matches(&document, &doc! { "age": { "$gt": 18, "$lte": 30 } }).unwrap());
+        assert!(matches(&document, &doc! { "age": { "$eq": 30.0 } }).unwrap());
+        assert!(matches(&document, &doc! { "tags": "admin" }).unwrap());
+        assert!(matches(&document, &doc! { "tags": { "$in": ["guest", "staff"] } }).unwrap());
+        assert!(
+            matches(
+                &document,
+                &doc! { "profile.city": { "$regex": "^par", "$options": "i" } }
+            )
+            .unwrap()
+        );
+        assert!(matches(&document, &doc! { "missing": { "$exists": false } }).unwrap());
+        assert!(!matches(&document, &doc! { "age": { "$gt": "18" } }).unwrap());
+        assert!(
+            matches(
+                &document,
+                &doc! { "$or": [{ "name": "bob" }, { "$and": [{ "age": 30 }, { "tags": { "$nin": ["guest"] } }] }] }
+            )
+            .unwrap()
+        );
+        assert!(matches(&document, &doc! { "age": { "$where": "x" } }).is_err());
+    }
+
+    #[test]
+    fn test_apply_update() {
+        let mut document = sample();
+        let modified = apply_update(
+            &mut document,
+            &doc! {
+                "$set": { "profile.city": "Lyon" },
+                "$inc": { "age": 1 },
+                "$push": { "tags": "owner" },
+                "$unset": { "name": "" },
+            },
+        )
+        .unwrap();
+        assert!(modified);
+        assert_eq!(
+            document,
+            doc! {
+                "age": 31,
+                "tags": ["admin", "staff", "owner"],
+                "profile": { "city": "Lyon", "score": 4.5 },
+            }
+        );
+        assert!(!apply_update(&mut document, &doc! { "$set": { "age": 31 } }).unwrap());
+        assert!(apply_update(&mut document, &doc! { "age": 1 }).is_err());
+
+        let mut document = doc! { "big": i64::MAX - 1, "count": 1, "name": "alice" };
+        let original = document.clone();
+        assert!(
+            apply_update(
+                &mut document,
+                &doc! { "$set": { "name": "bob" }, "$inc": { "big": 2 } },
+            )
+            .is_err()
+        );
+        assert_eq!(document, original);
+        assert!(apply_update(&mut document, &doc! { "$inc": { "big": 1 } }).unwrap());
+        assert_eq!(document.get_i64("big").unwrap(), i64::MAX);
+        assert!(apply_update(&mut document, &doc! { "$inc": { "count": i32::MAX } }).unwrap());
+        assert_eq!(document.get_i64("count").unwrap(), i64::from(i32::MAX) + 1);
+    }
+
+    #[test]
+    fn test_upsert_document() {
+        let update = doc! { "$set": { "age": 30 }, "$setOnInsert": { "joined": 2024 } };
+        let filter = doc! {
+            "$and": [{ "tenant": "acme" }, { "profile.email": { "$eq": "a@x.io" } }],
+            "name": { "$regex": "^a" },
+            "age": { "$gt": 18 },
+        };
+        assert_eq!(
+            upsert_document(&filter, &update).unwrap(),
+            doc! { "tenant": "acme", "profile": { "email": "a@x.io" }, "age": 30, "joined": 2024 }
+        );
+
+        // 更新已有文档时 `$setOnInsert` 不生效
+        let mut document = doc! { "age": 20 };
+        assert!(apply_update(&mut document, &update).unwrap());
+        assert_eq!(document, doc! { "age": 30 });
+
+        let conflicting = doc! { "$and": [{ "tenant": "acme" }, { "tenant": "globex" }] };
+        assert!(upsert_document(&conflicting, &update).is_err());
+        assert!(upsert_document(&doc! {}, &doc! { "age": 1 }).is_err());
+    }
+
+    #[test]
+    fn test_compare_documents() {
+        let a = doc! { "status": "active", "age": 20 };
+        let b = doc! { "status": "active", "age": 30 };
+        let sort = doc! { "status": 1, "age": -1 };
+        assert_eq!(compare_documents(&a, &b, &sort), Ordering::Greater);
+        assert_eq!(
+            compare_documents(&doc! {}, &a, &doc! { "status": 1 }),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let documents = vec![
+            doc! { "team": "a", "score": 3 },
+            doc! { "team": "b", "score": 5 },
+            doc! { "team": "a", "score": 4.5 },
+            doc! { "score": 1 },
+        ];
+        let pipeline = [
+            doc! { "$match": { "score": { "$gt": 2 } } },
+            doc! { "$group": { "_id": "$team", "total": { "$sum": "$score" }, "n": { "$sum": 1 } } },
+            doc! { "$sort": { "total": -1 } },
+        ];
+        assert_eq!(
+            aggregate(documents.clone(), &pipeline).unwrap(),
+            vec![
+                doc! { "_id": "a", "total": 7.5, "n": 2 },
+                doc! { "_id": "b", "total": 5, "n": 1 },
+            ]
+        );
+
+        let pipeline = [
+            doc! { "$skip": 1 },
+            doc! { "$limit": 2 },
+            doc! { "$count": "n" },
+        ];
+        assert_eq!(
+            aggregate(documents.clone(), &pipeline).unwrap(),
+            vec![doc! { "n": 2_i64 }]
+        );
+        assert!(aggregate(documents, &[doc! { "$lookup": {} }]).is_err());
+    }
+}
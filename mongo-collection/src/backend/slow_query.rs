@@ -0,0 +1,464 @@
+use super::{Backend, CollectionTarget, ResolvedTarget};
+use crate::explain::QueryPlan;
+use async_trait::async_trait;
+use mongodb::bson::Document;
+use mongodb::options::FindOptions;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+type SlowQueryCallback = Arc<dyn Fn(&SlowQuery) + Send + Sync>;
+
+/// 一次慢查询的报告
+#[derive(Debug, Clone)]
+pub struct SlowQuery {
+    /// 集合名
+    pub collection: String,
+    /// 后端操作：`find`、`count_documents` 或 `aggregate`
+    pub operation: &'static str,
+    /// 过滤条件（含实际值，记录日志时请自行脱敏）；`aggregate` 为空
+    pub filter: Document,
+    /// 聚合管道，仅 `aggregate` 非空
+    pub pipeline: Vec<Document>,
+    /// 排序
+    pub sort: Option<Document>,
+    /// 耗时
+    pub duration: Duration,
+    /// 执行计划；未启用 [`SlowQueryOptions::explain`]、操作为 `aggregate`
+    /// 或后端不支持 `explain` 时为 `None`
+    pub plan: Option<QueryPlan>,
+    /// 集合的大致文档数，仅在计划为全集合扫描时统计
+    pub collection_size: Option<u64>,
+    /// 是否为大集合上的全集合扫描
+    pub large_collscan: bool,
+}
+
+/// 慢查询检测配置
+///
+/// # 示例
+///
+/// ```ignore
+/// let options = SlowQueryOptions::new(Duration::from_millis(200))
+///     .explain(true)
+///     .large_collection(50_000)
+///     .on_slow_query(|query| alert(query));
+/// let db = db.with_slow_query_detection(options);
+/// ```
+#[derive(Clone)]
+pub struct SlowQueryOptions {
+    threshold: Duration,
+    large_collection: u64,
+    explain: bool,
+    callback: Option<SlowQueryCallback>,
+}
+
+impl SlowQueryOptions {
+    /// 耗时达到 `threshold` 的查询视为慢查询
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            large_collection: 10_000,
+            explain: false,
+            callback: None,
+        }
+    }
+
+    /// 是否为慢查询获取执行计划，默认关闭
+    ///
+    /// 开启后，报告前会额外执行一次 `explain`，计划为全集合扫描时再统计集合大小；
+    /// 这两次请求在原操作返回前完成，会增加慢查询本身的耗时
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// 文档数达到该值的集合视为大集合，默认 10 000，仅在开启 [`explain`](Self::explain) 时生效
+    pub fn large_collection(mut self, documents: u64) -> Self {
+        self.large_collection = documents;
+        self
+    }
+
+    /// 设置慢查询回调
+    ///
+    /// 未设置时，启用 `tracing` feature 则输出 `warn` 级别事件，否则不报告
+    pub fn on_slow_query<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SlowQuery) + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl fmt::Debug for SlowQueryOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlowQueryOptions")
+            .field("threshold", &self.threshold)
+            .field("large_collection", &self.large_collection)
+            .field("explain", &self.explain)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+/// 慢查询检测装饰器
+///
+/// 对 `find`、`count_documents` 与 `aggregate`（即 `find_many`、`find_paginated`、`count`
+/// 等方法）计时，超过阈值时报告，可选地附带执行计划；其余操作原样转发。
+/// 通过 [`BackendExt::with_slow_query_detection`](super::BackendExt::with_slow_query_detection)
+/// 或 [`SlowQueryDetector::new`] 创建
+#[derive(Debug, Clone)]
+pub struct SlowQueryDetector<B> {
+    inner: B,
+    options: SlowQueryOptions,
+}
+
+impl<B: Backend> SlowQueryDetector<B> {
+    /// 创建慢查询检测后端
+    pub fn new(inner: B, options: SlowQueryOptions) -> Self {
+        Self { inner, options }
+    }
+
+    /// 返回内层后端
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// 是否有接收报告的地方
+    fn reports(&self) -> bool {
+        self.options.callback.is_some() || cfg!(feature = "tracing")
+    }
+
+    /// 生成并发出慢查询报告
+    async fn report(
+        &self,
+        target: &CollectionTarget,
+        operation: &'static str,
+        filter: Document,
+        pipeline: Vec<Document>,
+        options: Option<FindOptions>,
+        duration: Duration,
+    ) {
+        if !self.reports() {
+            return;
+        }
+        let sort = options.as_ref().and_then(|options| options.sort.clone());
+        let plan = if self.options.explain && pipeline.is_empty() {
+            // 慢查询报告不能影响原操作的结果，获取计划失败时忽略
+            match self.inner.explain(target, filter.clone(), options).await {
+                Ok(explain) => QueryPlan::from_explain(&explain).ok(),
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+        let collection_size = match plan {
+            Some(ref plan) if plan.is_collscan() => {
+                self.inner.estimated_document_count(target).await.ok()
+            }
+            _ => None,
+        };
+        let large_collscan =
+            collection_size.is_some_and(|size| size >= self.options.large_collection);
+
+        let query = SlowQuery {
+            collection: target.name.to_string(),
+            operation,
+            filter,
+            pipeline,
+            sort,
+            duration,
+            plan,
+            collection_size,
+            large_collscan,
+        };
+        match self.options.callback {
+            Some(ref callback) => callback(&query),
+            None => log(&query),
+        }
+    }
+}
+
+/// 未设置回调时的默认输出，过滤条件只记录结构
+#[cfg(feature = "tracing")]
+fn log(query: &SlowQuery) {
+    let filter = crate::instrument::filter_shape(&query.filter);
+    let plan = query
+        .plan
+        .as_ref()
+        .map(QueryPlan::summary)
+        .unwrap_or_default();
+
+    tracing::warn!(
+        db.collection.name = %query.collection,
+        db.operation.name = query.operation,
+        db.query.text = %filter,
+        duration_ms = query.duration.as_millis() as u64,
+        plan = %plan,
+        large_collscan = query.large_collscan,
+        "slow query"
+    );
+}
+
+/// 未启用 `tracing` 且未设置回调时不报告
+#[cfg(not(feature = "tracing"))]
+fn log(_query: &SlowQuery) {}
+
+#[async_trait]
+impl<B: Backend> Backend for SlowQueryDetector<B> {
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error> {
+        self.inner.insert_one(target, document).await
+    }
+
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error> {
+        self.inner.insert_many(target, documents).await
+    }
+
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner.find_one(target, filter).await
+    }
+
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .find(target, filter.clone(), options.clone())
+            .await;
+        let duration = start.elapsed();
+        if duration >= self.options.threshold {
+            self.report(target, "find", filter, Vec::new(), options, duration)
+                .await;
+        }
+        result
+    }
+
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let start = Instant::now();
+        let result = self.inner.count_documents(target, filter.clone()).await;
+        let duration = start.elapsed();
+        if duration >= self.options.threshold {
+            self.report(
+                target,
+                "count_documents",
+                filter,
+                Vec::new(),
+                None,
+                duration,
+            )
+            .await;
+        }
+        result
+    }
+
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.update_one(target, filter, update).await
+    }
+
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.update_many(target, filter, update).await
+    }
+
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner.find_one_and_update(target, filter, update).await
+    }
+
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.delete_one(target, filter).await
+    }
+
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.delete_many(target, filter).await
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner.find_one_and_delete(target, filter).await
+    }
+
+    async fn estimated_document_count(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner.estimated_document_count(target).await
+    }
+
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        self.inner.explain(target, filter, options).await
+    }
+
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        self.inner.upsert_one(target, filter, update).await
+    }
+
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        let start = Instant::now();
+        let result = self.inner.aggregate(target, pipeline.clone()).await;
+        let duration = start.elapsed();
+        if duration >= self.options.threshold {
+            self.report(
+                target,
+                "aggregate",
+                Document::new(),
+                pipeline,
+                None,
+                duration,
+            )
+            .await;
+        }
+        result
+    }
+
+    async fn insert_batch(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+        ordered: bool,
+    ) -> Result<Vec<(usize, String)>, mongodb::error::Error> {
+        self.inner.insert_batch(target, documents, ordered).await
+    }
+
+    fn is_tenant_scoped(&self) -> bool {
+        self.inner.is_tenant_scoped()
+    }
+
+    fn resolve(&self, target: &CollectionTarget) -> Result<ResolvedTarget, mongodb::error::Error> {
+        self.inner.resolve(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BackendExt;
+    use crate::{Collection, CollectionRepository, MemoryBackend, PaginatedQuery};
+    use mongodb::bson::doc;
+    use serde::{Deserialize, Serialize};
+    use std::sync::Mutex;
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Visit {
+        path: String,
+    }
+
+    #[tokio::test]
+    async fn test_reports_slow_queries_with_plan() {
+        let backend = MemoryBackend::new();
+        let visits = (0..3)
+            .map(|i| Visit {
+                path: format!("/{i}"),
+            })
+            .collect();
+        Visit::create_many(&backend, visits).await.unwrap();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let options = SlowQueryOptions::new(Duration::ZERO)
+            .explain(true)
+            .large_collection(3)
+            .on_slow_query(move |query| sink.lock().unwrap().push(query.clone()));
+        let db = backend.with_slow_query_detection(options);
+
+        let page = Visit::find_paginated(&db, doc! { "path": "/1" }, &PaginatedQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+
+        let reports = reports.lock().unwrap();
+        let operations: Vec<_> = reports.iter().map(|query| query.operation).collect();
+        assert_eq!(operations, vec!["count_documents", "find"]);
+
+        let find = &reports[1];
+        assert_eq!(find.collection, "visits");
+        assert_eq!(find.sort, Some(doc! { "_id": -1 }));
+        assert_eq!(find.plan.as_ref().unwrap().summary(), "SORT > COLLSCAN");
+        assert_eq!(find.collection_size, Some(3));
+        assert!(find.large_collscan);
+    }
+
+    #[tokio::test]
+    async fn test_reports_aggregate_without_explain_by_default() {
+        let backend = MemoryBackend::new();
+        Visit::create(&backend, &Visit { path: "/".into() })
+            .await
+            .unwrap();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let options = SlowQueryOptions::new(Duration::ZERO)
+            .on_slow_query(move |query| sink.lock().unwrap().push(query.clone()));
+        let db = backend.with_slow_query_detection(options);
+
+        let pipeline = vec![doc! { "$match": { "path": "/" } }, doc! { "$count": "n" }];
+        let target = CollectionTarget::of::<Visit>();
+        db.aggregate(&target, pipeline.clone()).await.unwrap();
+        Visit::count(&db, doc! {}).await.unwrap();
+
+        let reports = reports.lock().unwrap();
+        let operations: Vec<_> = reports.iter().map(|query| query.operation).collect();
+        assert_eq!(operations, vec!["aggregate", "count_documents"]);
+        assert_eq!(reports[0].pipeline, pipeline);
+        assert!(reports.iter().all(|query| query.plan.is_none()));
+        assert!(reports.iter().all(|query| query.collection_size.is_none()));
+    }
+}
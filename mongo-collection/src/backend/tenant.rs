@@ -0,0 +1,576 @@
+use super::{Backend, CollectionTarget, ResolvedTarget};
+use crate::utils::invalid_argument;
+use async_trait::async_trait;
+use mongodb::bson::{Bson, Document, doc};
+use mongodb::options::FindOptions;
+
+/// 限定到单个租户的后端装饰器
+///
+/// 对声明了 `#[collection(tenant_field = "...")]` 的模型：
+/// - 所有过滤条件都会加上 `{ tenant_field: tenant }`
+/// - 插入的文档会写入租户字段，已属于其它租户的文档会被拒绝
+/// - 修改租户字段的更新会被拒绝
+///
+/// 未声明租户字段的模型原样转发。
+/// 通过 [`BackendExt::for_tenant`](super::BackendExt::for_tenant) 或 [`TenantScoped::new`] 创建，
+/// 可以直接交给 [`MongoRepository`](crate::MongoRepository) 持有
+#[derive(Debug, Clone)]
+pub struct TenantScoped<B> {
+    inner: B,
+    tenant: Bson,
+}
+
+impl<B> TenantScoped<B> {
+    /// 创建租户限定的后端
+    pub fn new(inner: B, tenant: impl Into<Bson>) -> Self {
+        Self {
+            inner,
+            tenant: tenant.into(),
+        }
+    }
+
+    /// 返回当前租户
+    pub fn tenant(&self) -> &Bson {
+        &self.tenant
+    }
+
+    /// 返回内层后端
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// 为过滤条件加上租户条件
+    fn scope(&self, target: &CollectionTarget, filter: Document) -> Document {
+        scope(target, &self.tenant, filter)
+    }
+
+    /// 为待插入文档写入租户字段
+    fn stamp(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<Document, mongodb::error::Error> {
+        stamp(target, &self.tenant, document)
+    }
+
+    /// 拒绝修改租户字段的更新
+    fn check_update(
+        &self,
+        target: &CollectionTarget,
+        update: &Document,
+    ) -> Result<(), mongodb::error::Error> {
+        check_update(target, update)
+    }
+}
+
+/// 为过滤条件加上租户条件
+pub(crate) fn scope(target: &CollectionTarget, tenant: &Bson, mut filter: Document) -> Document {
+    let Some(field) = target.tenant_field.as_deref() else {
+        return filter;
+    };
+    if filter.contains_key(field) {
+        // 不能覆盖调用方的条件，两者必须同时满足
+        doc! { "$and": [{ field: tenant.clone() }, filter] }
+    } else {
+        filter.insert(field, tenant.clone());
+        filter
+    }
+}
+
+/// 为待插入文档写入租户字段
+pub(crate) fn stamp(
+    target: &CollectionTarget,
+    tenant: &Bson,
+    mut document: Document,
+) -> Result<Document, mongodb::error::Error> {
+    let Some(field) = target.tenant_field.as_deref() else {
+        return Ok(document);
+    };
+    match document.get(field) {
+        Some(value) if value != tenant => Err(invalid_argument(format!(
+            "document for collection {} belongs to tenant {value}, expected {tenant}",
+            target.name
+        ))),
+        Some(_) => Ok(document),
+        None => {
+            document.insert(field, tenant.clone());
+            Ok(document)
+        }
+    }
+}
+
+/// 拒绝修改租户字段的更新，包括把其它字段 `$rename` 为租户字段
+pub(crate) fn check_update(
+    target: &CollectionTarget,
+    update: &Document,
+) -> Result<(), mongodb::error::Error> {
+    let Some(field) = target.tenant_field.as_deref() else {
+        return Ok(());
+    };
+    let nested = format!("{field}.");
+    let touches = |path: &str| path == field || path.starts_with(&nested);
+    let touches_tenant = update.iter().any(|(op, fields)| {
+        fields.as_document().is_some_and(|fields| {
+            fields.iter().any(|(path, value)| {
+                touches(path) || (op == "$rename" && value.as_str().is_some_and(touches))
+            })
+        })
+    });
+    if touches_tenant {
+        return Err(invalid_argument(format!(
+            "update on collection {} must not modify tenant field {field}",
+            target.name
+        )));
+    }
+    Ok(())
+}
+
+/// 去掉 `$set`/`$setOnInsert` 中写入当前租户的租户字段，拒绝其它对租户字段的修改
+///
+/// 用于 upsert：按模型序列化的整个文档通常带有租户字段
+fn without_own_tenant(
+    target: &CollectionTarget,
+    tenant: &Bson,
+    mut update: Document,
+) -> Result<Document, mongodb::error::Error> {
+    if let Some(field) = target.tenant_field.as_deref() {
+        for op in ["$set", "$setOnInsert"] {
+            if let Ok(fields) = update.get_document_mut(op)
+                && fields.get(field) == Some(tenant)
+            {
+                fields.remove(field);
+            }
+        }
+    }
+    check_update(target, &update)?;
+    Ok(update)
+}
+
+/// 拒绝未限定租户的批量操作
+///
+/// 后端已限定租户，或过滤条件对租户字段做等值匹配（标量值或 `$eq`）时放行；
+/// `$in`、`$ne`、正则等可能匹配多个租户的条件仍被拒绝
+pub(crate) fn ensure_scoped<B: Backend + ?Sized>(
+    db: &B,
+    target: &CollectionTarget,
+    filter: &Document,
+    operation: &str,
+) -> Result<(), mongodb::error::Error> {
+    match target.tenant_field.as_deref() {
+        Some(field) if !db.is_tenant_scoped() && !filter.get(field).is_some_and(is_equality) => {
+            Err(invalid_argument(format!(
+                "refusing unscoped {operation} on tenant collection {}: \
+                 use a tenant-scoped backend or filter by {field}",
+                target.name
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 条件是否只匹配单个值
+fn is_equality(condition: &Bson) -> bool {
+    match condition {
+        Bson::RegularExpression(_) => false,
+        Bson::Document(operators) if operators.keys().any(|key| key.starts_with('$')) => {
+            operators.len() == 1
+                && operators
+                    .get("$eq")
+                    .is_some_and(|value| !matches!(value, Bson::RegularExpression(_)))
+        }
+        _ => true,
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Backend for TenantScoped<B> {
+    async fn insert_one(
+        &self,
+        target: &CollectionTarget,
+        document: Document,
+    ) -> Result<(), mongodb::error::Error> {
+        let document = self.stamp(target, document)?;
+        self.inner.insert_one(target, document).await
+    }
+
+    async fn insert_many(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+    ) -> Result<(), mongodb::error::Error> {
+        let documents = documents
+            .into_iter()
+            .map(|document| self.stamp(target, document))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.inner.insert_many(target, documents).await
+    }
+
+    async fn find_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner
+            .find_one(target, self.scope(target, filter))
+            .await
+    }
+
+    async fn find(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        self.inner
+            .find(target, self.scope(target, filter), options)
+            .await
+    }
+
+    async fn count_documents(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .count_documents(target, self.scope(target, filter))
+            .await
+    }
+
+    async fn update_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.check_update(target, &update)?;
+        self.inner
+            .update_one(target, self.scope(target, filter), update)
+            .await
+    }
+
+    async fn update_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.check_update(target, &update)?;
+        self.inner
+            .update_many(target, self.scope(target, filter), update)
+            .await
+    }
+
+    async fn find_one_and_update(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.check_update(target, &update)?;
+        self.inner
+            .find_one_and_update(target, self.scope(target, filter), update)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .delete_one(target, self.scope(target, filter))
+            .await
+    }
+
+    async fn delete_many(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        self.inner
+            .delete_many(target, self.scope(target, filter))
+            .await
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+    ) -> Result<Option<Document>, mongodb::error::Error> {
+        self.inner
+            .find_one_and_delete(target, self.scope(target, filter))
+            .await
+    }
+
+    async fn estimated_document_count(
+        &self,
+        target: &CollectionTarget,
+    ) -> Result<u64, mongodb::error::Error> {
+        // 集合元数据的估算值包含所有租户，限定租户时只能精确计数
+        if target.tenant_field.is_none() {
+            return self.inner.estimated_document_count(target).await;
+        }
+        self.inner
+            .count_documents(target, self.scope(target, Document::new()))
+            .await
+    }
+
+    async fn explain(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Document, mongodb::error::Error> {
+        self.inner
+            .explain(target, self.scope(target, filter), options)
+            .await
+    }
+
+    async fn upsert_one(
+        &self,
+        target: &CollectionTarget,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        // 租户条件是等值条件，插入的新文档从过滤条件得到租户字段
+        let update = without_own_tenant(target, &self.tenant, update)?;
+        self.inner
+            .upsert_one(target, self.scope(target, filter), update)
+            .await
+    }
+
+    async fn aggregate(
+        &self,
+        target: &CollectionTarget,
+        mut pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>, mongodb::error::Error> {
+        if target.tenant_field.is_some() {
+            // 在管道最前面限定租户，后续阶段只能看到本租户的文档
+            pipeline.insert(0, doc! { "$match": self.scope(target, Document::new()) });
+        }
+        self.inner.aggregate(target, pipeline).await
+    }
+
+    async fn insert_batch(
+        &self,
+        target: &CollectionTarget,
+        documents: Vec<Document>,
+        ordered: bool,
+    ) -> Result<Vec<(usize, String)>, mongodb::error::Error> {
+        let mut stamped = Vec::with_capacity(documents.len());
+        let mut indexes = Vec::with_capacity(documents.len());
+        let mut rejected = Vec::new();
+        for (index, document) in documents.into_iter().enumerate() {
+            match self.stamp(target, document) {
+                Ok(document) => {
+                    stamped.push(document);
+                    indexes.push(index);
+                }
+                Err(error) => {
+                    rejected.push((index, error.to_string()));
+                    if ordered {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut failures: Vec<_> = self
+            .inner
+            .insert_batch(target, stamped, ordered)
+            .await?
+            .into_iter()
+            .map(|(index, message)| (indexes[index], message))
+            .collect();
+        // 有序插入在内层失败处已停止，之后被拒绝的文档不再报告
+        if !ordered || failures.is_empty() {
+            failures.extend(rejected);
+            failures.sort_by_key(|(index, _)| *index);
+        }
+        Ok(failures)
+    }
+
+    fn is_tenant_scoped(&self) -> bool {
+        true
+    }
+
+    fn resolve(&self, target: &CollectionTarget) -> Result<ResolvedTarget, mongodb::error::Error> {
+        let mut resolved = self.inner.resolve(target)?;
+        if resolved.target.tenant_field.is_some() {
+            resolved.tenant.get_or_insert_with(|| self.tenant.clone());
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{Backend, BackendExt, CollectionTarget};
+    use crate::{Collection, CollectionRepository, MemoryBackend};
+    use mongodb::bson::doc;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone, PartialEq)]
+    #[collection(tenant_field = "tenant_id")]
+    struct Order {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tenant_id: Option<String>,
+        status: String,
+    }
+
+    fn order(status: &str) -> Order {
+        Order {
+            tenant_id: None,
+            status: status.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scoped_reads_and_writes() {
+        let backend = MemoryBackend::new();
+        let acme = backend.for_tenant("acme");
+        let globex = backend.for_tenant("globex");
+
+        Order::create_many(&acme, vec![order("open"), order("closed")])
+            .await
+            .unwrap();
+        Order::create(&globex, &order("open")).await.unwrap();
+
+        // 插入时写入了租户字段
+        let stored = Order::find_all(&globex).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].tenant_id.as_deref(), Some("globex"));
+
+        assert_eq!(Order::count(&acme, doc! {}).await.unwrap(), 2);
+        // 调用方传入其它租户也无法越权
+        assert_eq!(
+            Order::count(&acme, doc! { "tenant_id": "globex" })
+                .await
+                .unwrap(),
+            0
+        );
+
+        let closed = Order::update_many(&acme, doc! {}, doc! { "$set": { "status": "closed" } })
+            .await
+            .unwrap();
+        assert_eq!(closed, 1);
+        assert!(
+            Order::exists(&globex, doc! { "status": "open" })
+                .await
+                .unwrap()
+        );
+
+        assert_eq!(Order::delete_many(&acme, doc! {}).await.unwrap(), 2);
+        assert_eq!(Order::count(&globex, doc! {}).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_cross_tenant_and_unscoped_writes() {
+        let backend = MemoryBackend::new();
+        let acme = backend.for_tenant("acme");
+
+        let foreign = Order {
+            tenant_id: Some("globex".to_string()),
+            status: "open".to_string(),
+        };
+        assert!(Order::create(&acme, &foreign).await.is_err());
+        assert!(
+            Order::update_many(&acme, doc! {}, doc! { "$set": { "tenant_id": "globex" } })
+                .await
+                .is_err()
+        );
+        assert!(
+            Order::update_many(
+                &acme,
+                doc! {},
+                doc! { "$rename": { "status": "tenant_id" } }
+            )
+            .await
+            .is_err()
+        );
+
+        // 未限定租户的批量操作被拒绝，显式指定租户字段时放行
+        assert!(Order::delete_many(&backend, doc! {}).await.is_err());
+        assert!(
+            Order::update_many(&backend, doc! {}, doc! { "$set": { "status": "x" } })
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            Order::delete_many(&backend, doc! { "tenant_id": "acme" })
+                .await
+                .unwrap(),
+            0
+        );
+
+        // 只有等值条件才算限定了租户
+        for filter in [
+            doc! { "tenant_id": { "$ne": "acme" } },
+            doc! { "tenant_id": { "$in": ["acme", "globex"] } },
+            doc! { "tenant_id": { "$exists": true } },
+            doc! { "tenant_id": { "$eq": "acme", "$ne": "globex" } },
+        ] {
+            assert!(Order::delete_many(&backend, filter).await.is_err());
+        }
+        assert_eq!(
+            Order::delete_many(&backend, doc! { "tenant_id": { "$eq": "acme" } })
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimated_count_is_scoped() {
+        let backend = MemoryBackend::new();
+        let acme = backend.for_tenant("acme");
+        Order::create_many(&acme, vec![order("open"), order("closed")])
+            .await
+            .unwrap();
+        Order::create(&backend.for_tenant("globex"), &order("open"))
+            .await
+            .unwrap();
+
+        let target = CollectionTarget::of::<Order>();
+        assert_eq!(backend.estimated_document_count(&target).await.unwrap(), 3);
+        assert_eq!(acme.estimated_document_count(&target).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_is_scoped() {
+        let backend = MemoryBackend::new();
+        let acme = backend.for_tenant("acme");
+        let globex = backend.for_tenant("globex");
+        let target = CollectionTarget::of::<Order>();
+        Order::create(&globex, &order("open")).await.unwrap();
+
+        // 其它租户的匹配文档不受影响，新文档写入当前租户
+        let update = doc! { "$set": { "tenant_id": "acme", "status": "open", "note": "x" } };
+        let inserted = acme
+            .upsert_one(&target, doc! { "status": "open" }, update.clone())
+            .await
+            .unwrap();
+        assert!(inserted);
+        let stored = acme.find_one(&target, doc! {}).await.unwrap().unwrap();
+        assert_eq!(stored.get_str("tenant_id").unwrap(), "acme");
+        assert!(
+            !globex
+                .find_one(&target, doc! {})
+                .await
+                .unwrap()
+                .unwrap()
+                .contains_key("note")
+        );
+
+        let inserted = acme
+            .upsert_one(&target, doc! { "status": "open" }, update)
+            .await
+            .unwrap();
+        assert!(!inserted);
+        assert_eq!(backend.count_documents(&target, doc! {}).await.unwrap(), 2);
+
+        let moved = doc! { "$set": { "tenant_id": "globex" } };
+        let result = acme.upsert_one(&target, doc! { "status": "open" }, moved);
+        assert!(result.await.is_err());
+    }
+}
@@ -0,0 +1,13 @@
+//! `mongo-collection` 命令
+//!
+//! 注册链接进本二进制的所有模型；应用的模型与迁移只存在于应用代码中，
+//! 需要管理它们时按 [`Cli`] 的文档在应用中构建自己的命令。
+//!
+//! 安装：
+//! cargo install mongo-collection --features cli
+use mongo_collection::cli::Cli;
+
+#[tokio::main]
+async fn main() -> Result<(), mongodb::error::Error> {
+    Cli::new().registered().run().await
+}
@@ -0,0 +1,411 @@
+//! 命令行工具（需要启用 `cli` feature）
+//!
+//! 模型与迁移只存在于应用代码中，因此命令行工具由应用在自己的二进制中构建：
+//! 注册模型与迁移后调用 [`Cli::run`]。[`Cli::registered`] 注册所有派生了
+//! `Collection` 的模型。crate 自带的 `mongo-collection` 二进制只执行
+//! `Cli::new().registered()`，不包含应用的模型与迁移。
+//!
+//! ```ignore
+//! // src/bin/mongo-collection.rs
+//! #[tokio::main]
+//! async fn main() -> Result<(), mongodb::error::Error> {
+//!     Cli::new()
+//!         .model::<User>()
+//!         .model::<Order>()
+//!         .migration(Migration::new(1, "backfill_status").up(...))
+//!         .run()
+//!         .await
+//! }
+//! ```
+//!
+//! ```text
+//! mongo-collection --db app list
+//! mongo-collection --db app diff
+//! mongo-collection --db app apply --drop-extra
+//! mongo-collection --db app migrate status|up|down [steps]
+//! mongo-collection --db app seed fixtures/
+//! ```
+
+use crate::migration::{Migration, Migrator};
+use crate::model::{ModelDiff, ModelInfo, index_name};
+use crate::utils::invalid_argument;
+use crate::{Collection, import, registry};
+use clap::{Parser, Subcommand};
+use mongodb::bson::{Document, doc};
+use mongodb::error::Error;
+use mongodb::{Client, Database, IndexModel};
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Model-driven collection management
+#[derive(Debug, Parser)]
+#[command(name = "mongo-collection")]
+struct Args {
+    /// MongoDB connection string
+    #[arg(long, env = "MONGODB_URI", default_value = "mongodb://localhost:27017")]
+    uri: String,
+    /// Database for models without `#[collection(db = "...")]` and for migrations
+    #[arg(long, env = "MONGODB_DATABASE")]
+    db: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List registered collections and their declared indexes
+    List,
+    /// Compare declared indexes and validators with the database
+    Diff,
+    /// Create missing collections and sync validators and indexes
+    Apply {
+        /// Also drop indexes that are not declared
+        #[arg(long)]
+        drop_extra: bool,
+    },
+    /// Apply, roll back or list migrations
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Load fixtures from `<dir>/<collection>.ndjson`; documents with an `_id` are replaced,
+    /// documents without one are inserted again on every run
+    Seed {
+        /// Directory holding the fixture files
+        dir: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum MigrateCommand {
+    /// Show applied and pending migrations
+    Status,
+    /// Apply pending migrations
+    Up,
+    /// Roll back the most recent migrations
+    Down {
+        /// Number of migrations to roll back
+        #[arg(default_value_t = 1)]
+        steps: usize,
+    },
+}
+
+/// 命令行工具
+#[derive(Debug, Default)]
+pub struct Cli {
+    models: Vec<ModelInfo>,
+    migrations: Vec<Migration>,
+}
+
+impl Cli {
+    /// 创建没有模型与迁移的命令行工具
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册模型
+    pub fn model<T: Collection>(mut self) -> Self {
+        self.models.push(ModelInfo::of::<T>());
+        self
+    }
+
+    /// 注册 [`registry`](crate::registry) 中的所有模型
+    pub fn registered(self) -> Self {
+        self.models(registry::all().into_iter().copied())
+    }
+
+    /// 注册多个模型
+    pub fn models(mut self, models: impl IntoIterator<Item = ModelInfo>) -> Self {
+        self.models.extend(models);
+        self
+    }
+
+    /// 注册迁移
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self
+    }
+
+    /// 解析进程参数并执行命令
+    pub async fn run(self) -> Result<(), Error> {
+        self.run_from(std::env::args_os()).await
+    }
+
+    /// 解析 `args`（第一个元素为程序名）并执行命令
+    ///
+    /// `--help` 与 `--version` 打印后返回 `Ok`；参数错误返回自定义错误，
+    /// 错误信息可通过 `error.get_custom::<String>()` 取回
+    pub async fn run_from<I, T>(self, args: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args = match Args::try_parse_from(args) {
+            Ok(args) => args,
+            Err(error) if !error.use_stderr() => {
+                print!("{error}");
+                return Ok(());
+            }
+            Err(error) => return Err(invalid_argument(error.to_string())),
+        };
+        let client = Client::with_uri_str(&args.uri).await?;
+        let default_db = args.db.as_deref();
+
+        match args.command {
+            Command::List => print!("{}", render_list(&self.models, default_db)),
+            Command::Diff => {
+                for model in &self.models {
+                    let db = database(&client, model, default_db)?;
+                    let diff = model.diff(&db).await?;
+                    print!("{}", render_diff(db.name(), &diff));
+                }
+            }
+            Command::Apply { drop_extra } => {
+                for model in &self.models {
+                    let db = database(&client, model, default_db)?;
+                    let diff = model.apply(&db, drop_extra).await?;
+                    print!("{}", render_diff(db.name(), &diff));
+                }
+            }
+            Command::Migrate { command } => {
+                let db = client.database(default_db.ok_or_else(|| {
+                    invalid_argument(
+                        "migrations need a database: pass --db or set MONGODB_DATABASE",
+                    )
+                })?);
+                let migrator = self
+                    .migrations
+                    .into_iter()
+                    .fold(Migrator::new(&db), Migrator::migration);
+                match command {
+                    MigrateCommand::Status => {
+                        for status in migrator.status().await? {
+                            let state = match (status.applied_at, status.defined) {
+                                (Some(at), true) => format!("applied {at}"),
+                                (Some(at), false) => format!("applied {at} (not defined)"),
+                                (None, _) => "pending".to_string(),
+                            };
+                            println!("{:>14}  {:<32} {state}", status.version, status.name);
+                        }
+                    }
+                    MigrateCommand::Up => {
+                        let applied = migrator.apply().await?;
+                        println!("applied {} migration(s) {applied:?}", applied.len());
+                    }
+                    MigrateCommand::Down { steps } => {
+                        let reverted = migrator.rollback(steps).await?;
+                        println!("rolled back {} migration(s) {reverted:?}", reverted.len());
+                    }
+                }
+            }
+            Command::Seed { dir } => {
+                for model in &self.models {
+                    let Some(path) = fixture_path(&dir, model.name()) else {
+                        continue;
+                    };
+                    let db = database(&client, model, default_db)?;
+                    let (inserted, replaced) = seed(&db, model.name(), &path).await?;
+                    println!(
+                        "{}.{}: {inserted} inserted, {replaced} replaced",
+                        db.name(),
+                        model.name()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 模型所在的数据库：模型绑定的数据库优先，其次是 `--db`
+fn database(client: &Client, model: &ModelInfo, default: Option<&str>) -> Result<Database, Error> {
+    model
+        .database_name()
+        .or(default)
+        .map(|name| client.database(name))
+        .ok_or_else(|| {
+            invalid_argument(format!(
+                "collection `{}` has no database: pass --db or set MONGODB_DATABASE",
+                model.name()
+            ))
+        })
+}
+
+fn render_index(index: &IndexModel) -> String {
+    let unique = index
+        .options
+        .as_ref()
+        .and_then(|o| o.unique)
+        .unwrap_or(false);
+    let mut line = format!("{} {}", index_name(index), index.keys);
+    if unique {
+        line.push_str(" unique");
+    }
+    line
+}
+
+fn render_list(models: &[ModelInfo], default_db: Option<&str>) -> String {
+    let mut out = String::new();
+    for model in models {
+        let db = model.database_name().or(default_db).unwrap_or("?");
+        let _ = writeln!(out, "{db}.{}", model.name());
+        for index in model.indexes() {
+            let _ = writeln!(out, "  {}", render_index(&index));
+        }
+    }
+    out
+}
+
+fn render_diff(db: &str, diff: &ModelDiff) -> String {
+    let mut out = String::new();
+    if diff.is_empty() {
+        let _ = writeln!(out, "{db}.{}: up to date", diff.collection);
+        return out;
+    }
+    let _ = writeln!(out, "{db}.{}:", diff.collection);
+    if !diff.exists {
+        let _ = writeln!(out, "  + collection");
+    }
+    if diff.validator_changed {
+        let _ = writeln!(out, "  ~ validator");
+    }
+    for index in &diff.missing_indexes {
+        let _ = writeln!(out, "  + index {}", render_index(index));
+    }
+    for index in &diff.changed_indexes {
+        let _ = writeln!(out, "  ~ index {}", render_index(index));
+    }
+    for name in &diff.extra_indexes {
+        let _ = writeln!(out, "  - index {name}");
+    }
+    out
+}
+
+/// `<dir>/<collection>.ndjson` 或 `<dir>/<collection>.jsonl`
+fn fixture_path(dir: &Path, collection: &str) -> Option<PathBuf> {
+    ["ndjson", "jsonl"]
+        .iter()
+        .map(|extension| dir.join(format!("{collection}.{extension}")))
+        .find(|path| path.is_file())
+}
+
+/// 写入一个 NDJSON 文件中的文档，返回插入与替换的数量
+///
+/// 带 `_id` 的文档按 `_id` 替换（不存在时插入），重复执行结果相同；
+/// 不带 `_id` 的文档每次执行都会插入，重复执行会产生重复数据
+async fn seed(db: &Database, collection: &str, path: &Path) -> Result<(u64, u64), Error> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let mut inserts = Vec::new();
+    let mut replaces = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let document = import::parse_json_line(line).map_err(|message| {
+            invalid_argument(format!("{}:{}: {message}", path.display(), index + 1))
+        })?;
+        if document.contains_key("_id") {
+            replaces.push(document);
+        } else {
+            inserts.push(document);
+        }
+    }
+
+    let collection = db.collection::<Document>(collection);
+    let inserted = inserts.len() as u64;
+    if !inserts.is_empty() {
+        collection.insert_many(inserts).await?;
+    }
+    let replaced = replaces.len() as u64;
+    for document in replaces {
+        let filter = doc! { "_id": document.get("_id").cloned() };
+        collection
+            .replace_one(filter, document)
+            .upsert(true)
+            .await?;
+    }
+    Ok((inserted, replaced))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[allow(dead_code)]
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[collection(index(keys = "tenant_id, -created_at"))]
+    struct Order {
+        tenant_id: String,
+        #[collection(unique)]
+        number: String,
+        created_at: i64,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[collection(db = "billing")]
+    struct Invoice {
+        amount: i64,
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args =
+            Args::try_parse_from(["mongo-collection", "--db", "app", "migrate", "down"]).unwrap();
+        assert_eq!(args.db.as_deref(), Some("app"));
+        assert!(matches!(
+            args.command,
+            Command::Migrate {
+                command: MigrateCommand::Down { steps: 1 }
+            }
+        ));
+
+        let args = Args::try_parse_from(["mongo-collection", "apply", "--drop-extra"]).unwrap();
+        assert!(matches!(args.command, Command::Apply { drop_extra: true }));
+        assert!(Args::try_parse_from(["mongo-collection", "drop"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_args_error() {
+        let error = Cli::new()
+            .run_from(["mongo-collection", "drop"])
+            .await
+            .unwrap_err();
+        assert!(error.get_custom::<String>().unwrap().contains("drop"));
+    }
+
+    #[test]
+    fn test_render() {
+        let models = [ModelInfo::of::<Order>(), ModelInfo::of::<Invoice>()];
+        assert_eq!(
+            render_list(&models, Some("app")),
+            "app.orders\n  number_1 { \"number\": 1 } unique\n  \
+             tenant_id_1_created_at_-1 { \"tenant_id\": 1, \"created_at\": -1 }\n\
+             billing.invoices\n"
+        );
+
+        let diff = ModelDiff {
+            collection: "orders".to_string(),
+            exists: true,
+            missing_indexes: ModelInfo::of::<Order>().indexes().split_off(1),
+            extra_indexes: vec!["legacy_1".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            render_diff("app", &diff),
+            "app.orders:\n  + index tenant_id_1_created_at_-1 \
+             { \"tenant_id\": 1, \"created_at\": -1 }\n  - index legacy_1\n"
+        );
+        let diff = ModelDiff {
+            collection: "invoices".to_string(),
+            exists: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_diff("billing", &diff),
+            "billing.invoices: up to date\n"
+        );
+    }
+}
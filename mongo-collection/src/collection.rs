@@ -1,4 +1,65 @@
-use mongodb::Database;
+use mongodb::bson::{Document, doc};
+use mongodb::options::CollectionOptions;
+use mongodb::{Database, IndexModel};
+use std::borrow::Cow;
+
+/// Context passed to a dynamic collection name resolver.
+///
+/// Built by the caller and attached to a backend with
+/// `BackendExt::with_name_context`; models without
+/// `#[collection(name_resolver = "...")]` ignore it.
+///
+/// # Examples
+///
+/// ```ignore
+/// let context = NameContext::new().tenant("acme").period("2026_10");
+/// assert_eq!(Order::resolve_name(&context), "tenant_acme_orders_2026_10");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameContext {
+    /// Tenant the collection belongs to, for collection-per-tenant layouts.
+    pub tenant: Option<String>,
+    /// Time bucket such as `"2026_10"`, for date-partitioned collections.
+    pub period: Option<String>,
+}
+
+impl NameContext {
+    /// Creates an empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the tenant.
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Sets the time bucket.
+    pub fn period(mut self, period: impl Into<String>) -> Self {
+        self.period = Some(period.into());
+        self
+    }
+}
+
+/// Metadata of a stored field, as generated by the derive macro.
+///
+/// # Examples
+///
+/// ```ignore
+/// for field in User::fields() {
+///     println!("{} {:?} required={}", field.name, field.bson_types, field.required);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// Name of the field in the stored document, after serde renames.
+    pub name: &'static str,
+    /// BSON types accepted by the generated `$jsonSchema`; empty when unconstrained.
+    pub bson_types: &'static [&'static str],
+    /// Whether the field is listed in the schema's `required` array.
+    pub required: bool,
+}
 
 /// Trait for types that map to MongoDB collections.
 ///
@@ -30,8 +91,57 @@ pub trait Collection: Send + Sync + Sized {
     /// this returns the pluralized snake_case version of the struct name.
     fn name() -> &'static str;
 
+    /// Returns the collection name for the given context.
+    ///
+    /// Defaults to [`Collection::name`]. The derive macro calls the function named by
+    /// `#[collection(name_resolver = "...")]`, which takes `&NameContext` and returns
+    /// a `String` or `&'static str`. Resolution only happens when a name context is
+    /// attached to the backend, so ordinary models keep using the static name.
+    fn resolve_name(context: &NameContext) -> Cow<'static, str> {
+        let _ = context;
+        Cow::Borrowed(Self::name())
+    }
+
+    /// Returns the name of the database this type lives in, if declared.
+    ///
+    /// The derive macro returns the value of `#[collection(db = "...")]`. Backends that
+    /// own a `mongodb::Client` (such as `ClientBackend`) use it to pick the database;
+    /// a `mongodb::Database` passed explicitly always takes precedence.
+    fn database_name() -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the field that holds the tenant key, if this type is multi-tenant.
+    ///
+    /// The derive macro returns the value of `#[collection(tenant_field = "...")]`.
+    /// Repositories refuse `update_many` / `delete_many` on such types unless the
+    /// backend is tenant-scoped or the filter names the tenant field.
+    fn tenant_field() -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the largest page size `find_paginated` accepts for this type.
+    ///
+    /// The derive macro returns the value of `#[collection(max_page_size = ...)]`;
+    /// `None` falls back to the global [`PageSizeLimits`](crate::PageSizeLimits). A value above
+    /// the global maximum is capped at it, so HTTP extractors and `find_paginated` agree.
+    fn max_page_size() -> Option<u64> {
+        None
+    }
+
+    /// Returns the read preference, read concern and write concern declared for this type.
+    ///
+    /// The derive macro fills these in from
+    /// `#[collection(read_preference = "...", read_concern = "...", write_concern = "...")]`;
+    /// unset options are inherited from the database.
+    fn collection_options() -> CollectionOptions {
+        CollectionOptions::default()
+    }
+
     /// Returns a typed MongoDB collection reference for this type.
     ///
+    /// The collection is configured with [`Collection::collection_options`].
+    ///
     /// # Arguments
     ///
     /// * `db` - A reference to the MongoDB database
@@ -47,6 +157,82 @@ pub trait Collection: Send + Sync + Sized {
     /// let user = users.find_one(doc! { "username": "alice" }).await?;
     /// ```
     fn collection(db: &Database) -> mongodb::Collection<Self> {
-        db.collection(Self::name())
+        db.collection_with_options(Self::name(), Self::collection_options())
+    }
+
+    /// Returns a typed MongoDB collection reference resolved for `context`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let orders = Order::collection_for(&db, &NameContext::new().period("2026_10"));
+    /// ```
+    fn collection_for(db: &Database, context: &NameContext) -> mongodb::Collection<Self> {
+        db.collection_with_options(&Self::resolve_name(context), Self::collection_options())
+    }
+
+    /// Returns the `$jsonSchema` describing documents of this collection.
+    ///
+    /// The derive macro generates the schema from the field types: `Option<T>`
+    /// fields are not required, `Vec<T>` maps to `array`, maps map to `object`,
+    /// and serde renames are honored. Nested structs, enums and other types whose
+    /// BSON form depends on their serde attributes are left unconstrained unless
+    /// `#[collection(bson_type = "...")]` is set. Manual implementations default to
+    /// accepting any document.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let schema = User::json_schema();
+    /// assert_eq!(schema.get_str("bsonType")?, "object");
+    /// ```
+    fn json_schema() -> Document {
+        doc! { "bsonType": "object" }
+    }
+
+    /// Returns the columns written by CSV exports, in declaration order.
+    ///
+    /// The derive macro lists every stored field except those marked
+    /// `#[collection(skip_export)]`. Nested documents are expanded into dotted
+    /// columns at export time. An empty list means the columns are taken from the
+    /// exported documents themselves.
+    fn export_columns() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Returns the fields that `q=` filter expressions must not reference.
+    ///
+    /// The derive macro lists the fields marked `#[collection(skip_filter)]`.
+    /// [`FilterLanguage::for_collection`](crate::FilterLanguage::for_collection)
+    /// rejects them, together with their subfields, as unknown fields.
+    fn unfilterable_fields() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Returns the fields that `facets=` may request value counts for.
+    ///
+    /// The derive macro lists the fields marked `#[collection(facet)]`. Facets are
+    /// opt-in because the counts reveal every stored value of a field; requesting
+    /// any other field yields `QueryError::UnknownField`.
+    fn facet_fields() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Returns the indexes declared for this collection.
+    ///
+    /// The derive macro generates them from `#[collection(index)]` and
+    /// `#[collection(unique)]` on fields and from
+    /// `#[collection(index(keys = "a, -b", unique, name = "..."))]` on the struct.
+    /// Indexes without an explicit name use MongoDB's default name, such as `a_1_b_-1`.
+    fn indexes() -> Vec<IndexModel> {
+        Vec::new()
+    }
+
+    /// Returns the stored fields in declaration order.
+    ///
+    /// The derive macro generates this from the same inference as
+    /// [`Collection::json_schema`]; skipped and flattened fields are not listed.
+    fn fields() -> &'static [FieldInfo] {
+        &[]
     }
 }
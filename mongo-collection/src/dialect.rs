@@ -0,0 +1,298 @@
+//! 查询参数方言
+//!
+//! 不同客户端使用不同的分页、排序与筛选参数约定。每种方言把查询字符串解析为同一个
+//! [`PaginatedQuery`]：筛选条件统一保存为 `filters[...]` 格式，排序保存在 `sort` 中，
+//! 因此 `find_paginated`、`parsed_filters` 等无需关心请求来自哪种方言。
+
+use crate::query_string::{self, QueryError};
+use crate::{PaginatedQuery, SortOrder, SortSpec};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+/// 查询参数方言
+///
+/// 实现者只负责解析格式，取值范围由 [`PaginatedQuery::validate`] 检查
+pub trait QueryDialect {
+    /// 把查询字符串（可带前导 `?`）解析为分页查询
+    fn parse(&self, query: &str) -> Result<PaginatedQuery, QueryError>;
+}
+
+/// 默认方言：`page`、`page_size`、`sort_by`、`sort_order`、`sort`、`search`、
+/// `filters[x]` / `filters.x`，即 [`PaginatedQuery::from_query_str`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultDialect;
+
+impl QueryDialect for DefaultDialect {
+    fn parse(&self, query: &str) -> Result<PaginatedQuery, QueryError> {
+        PaginatedQuery::from_query_str(query)
+    }
+}
+
+/// JSON:API 方言：`page[number]`、`page[size]`、`sort=-created_at,name`、`filter[x]`
+///
+/// `filter[author][name]` 对应 `filters[author][name]`；其它参数原样保存在 `filters` 中
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonApiDialect;
+
+impl QueryDialect for JsonApiDialect {
+    fn parse(&self, query: &str) -> Result<PaginatedQuery, QueryError> {
+        let mut parsed = PaginatedQuery::default();
+        let mut extra = HashMap::new();
+        for (key, value) in query_string::pairs(query) {
+            match key.as_ref() {
+                "page[number]" => parsed.page = query_string::parse_u64("page[number]", &value)?,
+                "page[size]" => parsed.page_size = query_string::parse_u64("page[size]", &value)?,
+                "sort" => parsed.sort = SortSpec::parse_list(&value)?,
+                key => {
+                    let key = match key.strip_prefix("filter[") {
+                        Some(path) => format!("filters[{path}"),
+                        None => key.to_string(),
+                    };
+                    extra.insert(key, value.into_owned());
+                }
+            }
+        }
+        parsed.filters = query_string::raw_filters(extra);
+        Ok(parsed)
+    }
+}
+
+/// OData 方言：`$top`、`$skip`、`$orderby=created_at desc,name`、`$search`、
+/// `$filter=status eq 'open' and age eq 18`
+///
+/// `$skip` 必须是每页数量的整数倍；`$filter` 只支持以 `and` 连接的 `eq` 条件，
+/// 嵌套字段使用 `author/name`。其它参数原样保存在 `filters` 中
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ODataDialect;
+
+impl QueryDialect for ODataDialect {
+    fn parse(&self, query: &str) -> Result<PaginatedQuery, QueryError> {
+        let mut parsed = PaginatedQuery::default();
+        let mut extra = HashMap::new();
+        let mut skip = 0;
+        for (key, value) in query_string::pairs(query) {
+            match key.as_ref() {
+                "$top" => parsed.page_size = query_string::parse_u64("$top", &value)?,
+                "$skip" => skip = query_string::parse_u64("$skip", &value)?,
+                "$orderby" => parsed.sort = parse_orderby(&value)?,
+                "$search" => parsed.search = Some(value.into_owned()),
+                "$filter" => {
+                    for (path, value) in parse_odata_filter(&value)? {
+                        extra.insert(format!("filters.{path}"), value);
+                    }
+                }
+                _ => {
+                    extra.insert(key.into_owned(), value.into_owned());
+                }
+            }
+        }
+
+        if parsed.page_size > 0 {
+            if skip % parsed.page_size != 0 {
+                return Err(QueryError::InvalidValue {
+                    field: "$skip".to_string(),
+                    value: skip.to_string(),
+                    expected: "a multiple of $top",
+                });
+            }
+            parsed.page = skip / parsed.page_size + 1;
+        }
+        parsed.filters = query_string::raw_filters(extra);
+        Ok(parsed)
+    }
+}
+
+/// 解析 `$orderby`，如 `created_at desc, author/name`
+fn parse_orderby(value: &str) -> Result<Vec<SortSpec>, QueryError> {
+    value
+        .split(',')
+        .map(|item| {
+            let mut words = item.split_whitespace();
+            let field = words.next().map(|field| field.replace('/', "."));
+            let order = match words.next().map(str::to_ascii_lowercase).as_deref() {
+                None | Some("asc") => Some(SortOrder::Asc),
+                Some("desc") => Some(SortOrder::Desc),
+                Some(_) => None,
+            };
+            match (field, order, words.next()) {
+                (Some(field), Some(order), None) => Ok(SortSpec { field, order }),
+                _ => Err(QueryError::InvalidValue {
+                    field: "$orderby".to_string(),
+                    value: item.trim().to_string(),
+                    expected: "`field [asc|desc]`",
+                }),
+            }
+        })
+        .collect()
+}
+
+/// `$filter` 中的词法单元
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+/// 解析 `$filter`，返回字段路径与值
+fn parse_odata_filter(value: &str) -> Result<Vec<(String, String)>, QueryError> {
+    let invalid = || QueryError::InvalidValue {
+        field: "$filter".to_string(),
+        value: value.to_string(),
+        expected: "`field eq value` clauses joined by `and`",
+    };
+
+    let tokens = tokenize(value).ok_or_else(invalid)?;
+    let mut tokens = tokens.into_iter();
+    let mut clauses = Vec::new();
+    loop {
+        let (Some(Token::Word(field)), Some(Token::Word(op)), Some(value)) =
+            (tokens.next(), tokens.next(), tokens.next())
+        else {
+            return Err(invalid());
+        };
+        if !op.eq_ignore_ascii_case("eq") {
+            return Err(invalid());
+        }
+        let value = match value {
+            Token::Word(word) | Token::Quoted(word) => word,
+        };
+        clauses.push((field.replace('/', "."), value));
+
+        match tokens.next() {
+            None => return Ok(clauses),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("and") => {}
+            Some(_) => return Err(invalid()),
+        }
+    }
+}
+
+/// 按空白拆分，单引号字符串内的 `''` 表示一个单引号；引号未闭合时返回 `None`
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '\'' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next()? {
+                    '\'' if chars.peek() == Some(&'\'') => {
+                        chars.next();
+                        text.push('\'');
+                    }
+                    '\'' => break,
+                    c => text.push(c),
+                }
+            }
+            tokens.push(Token::Quoted(text));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '\'' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    Some(tokens)
+}
+
+/// 按方言 `D` 解析并校验的分页查询，用作 Web 框架提取器
+///
+/// # 示例
+///
+/// ```ignore
+/// async fn list_users(query: DialectQuery<JsonApiDialect>) -> ... {
+///     User::find_paginated(&db, doc! {}, &query).await
+/// }
+/// ```
+pub struct DialectQuery<D> {
+    query: PaginatedQuery,
+    dialect: PhantomData<fn() -> D>,
+}
+
+impl<D: QueryDialect + Default> DialectQuery<D> {
+    /// 使用方言 `D` 解析查询字符串，并按全局限制校验
+    pub fn from_query_str(query: &str) -> Result<Self, QueryError> {
+        Ok(Self {
+            query: D::default().parse(query)?.validate()?,
+            dialect: PhantomData,
+        })
+    }
+}
+
+impl<D> DialectQuery<D> {
+    /// 取出分页查询
+    pub fn into_inner(self) -> PaginatedQuery {
+        self.query
+    }
+}
+
+impl<D> Deref for DialectQuery<D> {
+    type Target = PaginatedQuery;
+
+    fn deref(&self) -> &PaginatedQuery {
+        &self.query
+    }
+}
+
+impl<D> fmt::Debug for DialectQuery<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DialectQuery").field(&self.query).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_api_dialect() {
+        let query = JsonApiDialect
+            .parse("page[number]=3&page[size]=20&sort=-created_at,name&filter[author][name]=alice&include=tags")
+            .unwrap();
+        assert_eq!((query.page, query.page_size), (3, 20));
+        assert_eq!(
+            query.sort,
+            vec![SortSpec::desc("created_at"), SortSpec::asc("name")]
+        );
+        let filters = query.parsed_filters().unwrap();
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters["author.name"], "alice");
+    }
+
+    #[test]
+    fn test_odata_dialect() {
+        let query = ODataDialect
+            .parse("$top=25&$skip=50&$orderby=created_at desc, author/name&$filter=status eq 'it''s open' and author/age eq 18")
+            .unwrap();
+        assert_eq!((query.page, query.page_size), (3, 25));
+        assert_eq!(
+            query.sort,
+            vec![SortSpec::desc("created_at"), SortSpec::asc("author.name")]
+        );
+        let filters = query.parsed_filters().unwrap();
+        assert_eq!(filters["status"], "it's open");
+        assert_eq!(filters["author.age"], "18");
+
+        assert!(ODataDialect.parse("$top=10&$skip=15").is_err());
+        assert!(ODataDialect.parse("$filter=age gt 18").is_err());
+        assert!(ODataDialect.parse("$filter=name eq 'open").is_err());
+        assert!(ODataDialect.parse("$orderby=name sideways").is_err());
+    }
+
+    #[test]
+    fn test_dialect_query_validates() {
+        let query = DialectQuery::<JsonApiDialect>::from_query_str("page[size]=5").unwrap();
+        assert_eq!(query.page_size, 5);
+        assert!(DialectQuery::<ODataDialect>::from_query_str("$top=0").is_err());
+        assert!(DialectQuery::<DefaultDialect>::from_query_str("page=0").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! 执行计划摘要
+
+use mongodb::bson::{Bson, Document};
+
+/// 查询的获胜执行计划
+///
+/// 由 `explain` 命令的输出解析得到，保留原始的 `winningPlan` 以及按深度优先顺序
+/// 展开的阶段名与使用的索引名。分片集群中会合并所有分片的计划。
+///
+/// # 示例
+///
+/// ```ignore
+/// let plan = User::explain(&db, doc! { "email": "a@example.com" }, None).await?;
+/// if plan.is_collscan() {
+///     println!("missing index: {}", plan.summary());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlan {
+    /// 原始的 `queryPlanner.winningPlan`
+    pub winning_plan: Document,
+    /// 阶段名，从根阶段到叶子阶段，如 `["FETCH", "IXSCAN"]`
+    pub stages: Vec<String>,
+    /// 计划使用的索引名
+    pub indexes: Vec<String>,
+}
+
+impl QueryPlan {
+    /// 从 `explain` 命令的输出解析执行计划
+    pub fn from_explain(explain: &Document) -> Result<Self, mongodb::error::Error> {
+        let winning_plan = explain
+            .get_document("queryPlanner")
+            .and_then(|planner| planner.get_document("winningPlan"))
+            .map_err(|_| {
+                crate::utils::invalid_argument("explain output has no queryPlanner.winningPlan")
+            })?
+            .clone();
+
+        let mut stages = Vec::new();
+        let mut indexes = Vec::new();
+        walk(&winning_plan, &mut stages, &mut indexes);
+        Ok(Self {
+            winning_plan,
+            stages,
+            indexes,
+        })
+    }
+
+    /// 计划中是否包含全集合扫描
+    pub fn is_collscan(&self) -> bool {
+        self.stages.iter().any(|stage| stage == "COLLSCAN")
+    }
+
+    /// 单行摘要，如 `FETCH > IXSCAN(email_1)`
+    pub fn summary(&self) -> String {
+        let mut indexes = self.indexes.iter();
+        self.stages
+            .iter()
+            .map(|stage| match stage.as_str() {
+                "IXSCAN" | "COUNT_SCAN" | "DISTINCT_SCAN" => match indexes.next() {
+                    Some(index) => format!("{stage}({index})"),
+                    None => stage.clone(),
+                },
+                _ => stage.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+}
+
+/// 深度优先收集阶段名与索引名
+fn walk(stage: &Document, stages: &mut Vec<String>, indexes: &mut Vec<String>) {
+    if let Ok(name) = stage.get_str("stage") {
+        stages.push(name.to_string());
+    }
+    if let Ok(index) = stage.get_str("indexName") {
+        indexes.push(index.to_string());
+    }
+    for (key, value) in stage {
+        match (key.as_str(), value) {
+            // 经典引擎的 inputStage，SBE 引擎的 queryPlan，分片集群的 winningPlan
+            ("inputStage" | "queryPlan" | "winningPlan", Bson::Document(child)) => {
+                walk(child, stages, indexes);
+            }
+            ("inputStages" | "shards", Bson::Array(children)) => {
+                for child in children.iter().filter_map(Bson::as_document) {
+                    walk(child, stages, indexes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn test_parse_classic_and_sharded_plans() {
+        let explain = doc! {
+            "queryPlanner": {
+                "winningPlan": {
+                    "stage": "FETCH",
+                    "inputStage": { "stage": "IXSCAN", "indexName": "email_1" },
+                },
+            },
+        };
+        let plan = QueryPlan::from_explain(&explain).unwrap();
+        assert_eq!(plan.stages, vec!["FETCH", "IXSCAN"]);
+        assert_eq!(plan.summary(), "FETCH > IXSCAN(email_1)");
+        assert!(!plan.is_collscan());
+
+        let explain = doc! {
+            "queryPlanner": {
+                "winningPlan": {
+                    "stage": "SINGLE_SHARD",
+                    "shards": [{ "winningPlan": { "queryPlan": { "stage": "COLLSCAN" } } }],
+                },
+            },
+        };
+        let plan = QueryPlan::from_explain(&explain).unwrap();
+        assert_eq!(plan.stages, vec!["SINGLE_SHARD", "COLLSCAN"]);
+        assert!(plan.is_collscan());
+
+        assert!(QueryPlan::from_explain(&doc! { "ok": 1 }).is_err());
+    }
+}
@@ -0,0 +1,488 @@
+//! 查询结果导出
+//!
+//! 按批读取筛选结果并逐条编码写入 [`AsyncWrite`]，内存中最多保留一批文档。
+//! 支持 NDJSON、CSV 与 JSON 数组三种格式；JSON 使用与 `mongoexport` 相同的
+//! relaxed Extended JSON，CSV 的列来自 [`Collection::export_columns`]，嵌套文档展开为
+//! `author.name` 形式的列。
+
+use crate::Collection;
+use crate::backend::{Backend, CollectionTarget};
+use futures::io::{AsyncWrite, AsyncWriteExt};
+use mongodb::bson::{Bson, Document, deserialize_from_document, doc, serialize_to_document};
+use mongodb::options::FindOptions;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// 每行一个 JSON 文档
+    #[default]
+    Ndjson,
+    /// 带表头的 CSV（RFC 4180）
+    Csv,
+    /// 单个 JSON 数组
+    JsonArray,
+}
+
+impl ExportFormat {
+    /// 对应的 `Content-Type`
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::JsonArray => "application/json",
+        }
+    }
+}
+
+/// 导出选项
+///
+/// # 示例
+///
+/// ```ignore
+/// let options = ExportOptions::new(ExportFormat::Csv)
+///     .batch_size(500)
+///     .columns(["name", "author.name"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// 导出格式
+    pub format: ExportFormat,
+    /// 每批从后端读取的文档数
+    pub batch_size: u32,
+    /// CSV 列，未设置时使用 [`Collection::export_columns`]
+    pub columns: Option<Vec<String>>,
+}
+
+impl ExportOptions {
+    /// 默认每批读取的文档数
+    pub const DEFAULT_BATCH_SIZE: u32 = 1000;
+
+    /// 以 `format` 导出，每批读取 [`DEFAULT_BATCH_SIZE`](Self::DEFAULT_BATCH_SIZE) 条
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            columns: None,
+        }
+    }
+
+    /// 设置每批读取的文档数，至少为 1
+    pub fn batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// 指定 CSV 列，支持点号路径
+    pub fn columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self::new(ExportFormat::default())
+    }
+}
+
+/// 按 `sort` 分批读取匹配 `filter` 的文档并写入 `writer`，返回导出的文档数
+///
+/// 每个文档先反序列化为 `T` 再序列化，导出内容与模型一致。
+/// `sort` 以 `_id` 结尾（见 [`ListQuery::sort_document`](crate::ListQuery::sort_document)），
+/// 后续批次按上一批最后一个文档的排序值做键集分页，而不是使用不断增长的 `skip`；
+/// 排序字段应为标量，数组字段的排序值与比较结果不一致
+pub(crate) async fn export<T, B, W>(
+    db: &B,
+    target: &CollectionTarget,
+    filter: Document,
+    sort: Document,
+    options: &ExportOptions,
+    writer: &mut W,
+) -> Result<u64, mongodb::error::Error>
+where
+    T: Collection + Serialize + DeserializeOwned,
+    B: Backend + ?Sized,
+    W: AsyncWrite + Unpin + Send + ?Sized,
+{
+    let batch_size = options.batch_size.max(1);
+    let mut encoder = Encoder::new(options.format);
+    let mut exported = 0;
+    let mut batch_filter = filter.clone();
+    loop {
+        let find_options = FindOptions::builder()
+            .sort(sort.clone())
+            .limit(i64::from(batch_size))
+            .build();
+        let documents = db.find(target, batch_filter, Some(find_options)).await?;
+        let fetched = documents.len();
+        let next = documents.last().map(|last| after(last, &sort));
+
+        let mut batch = Vec::with_capacity(fetched);
+        for document in documents {
+            let item: T = deserialize_from_document(document)?;
+            batch.push(serialize_to_document(&item)?);
+        }
+
+        if exported == 0 {
+            let declared = match options.columns {
+                Some(ref columns) => columns.clone(),
+                None => T::export_columns().iter().map(|c| c.to_string()).collect(),
+            };
+            writer.write_all(&encoder.begin(&declared, &batch)).await?;
+        }
+        for document in &batch {
+            writer.write_all(&encoder.row(document)).await?;
+        }
+        exported += fetched as u64;
+
+        match next {
+            Some(next) if fetched == batch_size as usize => {
+                batch_filter = doc! { "$and": [filter.clone(), next] };
+            }
+            _ => break,
+        }
+    }
+    writer.write_all(&encoder.finish()).await?;
+    writer.flush().await?;
+    Ok(exported)
+}
+
+/// 按 `sort` 排在 `last` 之后的文档的筛选条件
+///
+/// 对每个排序字段生成“前面的字段相等且该字段更大（降序时更小）”的分支，
+/// 缺失的字段按 `null` 处理，`null` 排在所有值之前
+fn after(last: &Document, sort: &Document) -> Document {
+    let mut branches = Vec::new();
+    let mut equal = Document::new();
+    for (field, direction) in sort {
+        let value = get_path(last, field).cloned().unwrap_or(Bson::Null);
+        let ascending = !matches!(direction, Bson::Int32(-1) | Bson::Int64(-1));
+        let beyond = match (ascending, &value) {
+            (true, Bson::Null) => Some(doc! { field: { "$ne": Bson::Null } }),
+            (true, _) => Some(doc! { field: { "$gt": value.clone() } }),
+            (false, Bson::Null) => None,
+            (false, _) => Some(doc! { "$or": [
+                { field: { "$lt": value.clone() } },
+                { field: Bson::Null },
+            ] }),
+        };
+        if let Some(beyond) = beyond {
+            let mut branch = equal.clone();
+            branch.extend(beyond);
+            branches.push(branch);
+        }
+        equal.insert(field, value);
+    }
+    doc! { "$or": branches }
+}
+
+/// 按格式把文档编码为字节
+struct Encoder {
+    format: ExportFormat,
+    /// CSV 列
+    columns: Vec<String>,
+    /// 已写入的行数
+    rows: u64,
+}
+
+impl Encoder {
+    fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            columns: Vec::new(),
+            rows: 0,
+        }
+    }
+
+    /// 开始导出：CSV 根据第一批文档确定列并写表头，JSON 数组写 `[`
+    fn begin(&mut self, declared: &[String], first: &[Document]) -> Vec<u8> {
+        match self.format {
+            ExportFormat::Ndjson => Vec::new(),
+            ExportFormat::JsonArray => b"[".to_vec(),
+            ExportFormat::Csv => {
+                self.columns = csv_columns(declared, first);
+                let header: Vec<_> = self.columns.iter().map(|c| csv_escape(c)).collect();
+                format!("{}\r\n", header.join(",")).into_bytes()
+            }
+        }
+    }
+
+    fn row(&mut self, document: &Document) -> Vec<u8> {
+        let first = self.rows == 0;
+        self.rows += 1;
+        match self.format {
+            ExportFormat::Ndjson => format!("{}\n", to_json(document)).into_bytes(),
+            ExportFormat::JsonArray if first => to_json(document).into_bytes(),
+            ExportFormat::JsonArray => format!(",{}", to_json(document)).into_bytes(),
+            ExportFormat::Csv => {
+                let cells: Vec<_> = self
+                    .columns
+                    .iter()
+                    .map(|column| match get_path(document, column) {
+                        // 与缺失字段区分，空字符串写作 `""`
+                        Some(Bson::String(text)) if text.is_empty() => "\"\"".to_string(),
+                        value => csv_escape(&csv_cell(value)),
+                    })
+                    .collect();
+                format!("{}\r\n", cells.join(",")).into_bytes()
+            }
+        }
+    }
+
+    fn finish(&self) -> Vec<u8> {
+        match self.format {
+            ExportFormat::JsonArray => b"]\n".to_vec(),
+            ExportFormat::Ndjson | ExportFormat::Csv => Vec::new(),
+        }
+    }
+}
+
+fn to_json(document: &Document) -> String {
+    Bson::Document(document.clone())
+        .into_relaxed_extjson()
+        .to_string()
+}
+
+/// 按点号路径取值
+fn get_path<'a>(document: &'a Document, path: &str) -> Option<&'a Bson> {
+    match document.get(path) {
+        Some(value) => Some(value),
+        None => {
+            let (head, rest) = path.split_once('.')?;
+            match document.get(head)? {
+                Bson::Document(child) => get_path(child, rest),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// 确定 CSV 列
+///
+/// 未声明列时使用第一批文档的顶层字段；值为嵌套文档的列按第一批文档展开为叶子路径
+fn csv_columns(declared: &[String], first: &[Document]) -> Vec<String> {
+    let mut roots: Vec<String> = declared.to_vec();
+    if roots.is_empty() {
+        for document in first {
+            for key in document.keys() {
+                if !roots.contains(key) {
+                    roots.push(key.clone());
+                }
+            }
+        }
+    }
+
+    let mut columns = Vec::new();
+    for root in roots {
+        let mut leaves = Vec::new();
+        for document in first {
+            if let Some(Bson::Document(nested)) = get_path(document, &root) {
+                leaf_paths(&root, nested, &mut leaves);
+            }
+        }
+        if leaves.is_empty() {
+            leaves.push(root);
+        }
+        for leaf in leaves {
+            if !columns.contains(&leaf) {
+                columns.push(leaf);
+            }
+        }
+    }
+    columns
+}
+
+fn leaf_paths(prefix: &str, document: &Document, paths: &mut Vec<String>) {
+    for (key, value) in document {
+        let path = format!("{prefix}.{key}");
+        match value {
+            Bson::Document(nested) if !nested.is_empty() => leaf_paths(&path, nested, paths),
+            _ => {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+}
+
+/// CSV 单元格的文本：缺失与 `null` 为空，数组与文档使用 JSON
+fn csv_cell(value: Option<&Bson>) -> String {
+    match value {
+        None | Some(Bson::Null) => String::new(),
+        Some(Bson::String(text)) => text.clone(),
+        Some(Bson::Boolean(value)) => value.to_string(),
+        Some(Bson::Int32(value)) => value.to_string(),
+        Some(Bson::Int64(value)) => value.to_string(),
+        Some(Bson::Double(value)) => value.to_string(),
+        Some(Bson::Decimal128(value)) => value.to_string(),
+        Some(Bson::ObjectId(id)) => id.to_hex(),
+        Some(Bson::DateTime(date)) => date
+            .try_to_rfc3339_string()
+            .unwrap_or_else(|_| date.timestamp_millis().to_string()),
+        Some(other) => other.clone().into_relaxed_extjson().to_string(),
+    }
+}
+
+/// 含逗号、引号或换行的单元格用双引号包围，引号写作 `""`
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CollectionRepository, ListQuery, MemoryBackend, SortSpec};
+    use mongodb::bson::doc;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Author {
+        name: String,
+        city: Option<String>,
+    }
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Post {
+        title: String,
+        author: Author,
+        tags: Vec<String>,
+        #[collection(skip_export)]
+        secret: String,
+    }
+
+    fn post(title: &str, author: &str, city: Option<&str>) -> Post {
+        Post {
+            title: title.to_string(),
+            author: Author {
+                name: author.to_string(),
+                city: city.map(str::to_string),
+            },
+            tags: vec!["a".to_string(), "b".to_string()],
+            secret: "hidden".to_string(),
+        }
+    }
+
+    async fn export_string(db: &MemoryBackend, options: &ExportOptions) -> (u64, String) {
+        let query = ListQuery {
+            sort: vec![SortSpec::asc("title")],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let count = Post::export(db, doc! {}, &query, options, &mut output)
+            .await
+            .unwrap();
+        (count, String::from_utf8(output).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_export_formats() {
+        let db = MemoryBackend::new();
+        Post::create_many(
+            &db,
+            vec![
+                post("second, part \"2\"", "bob", None),
+                post("first", "alice", Some("Lyon")),
+                post("third", "carol", None),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(Post::export_columns(), ["title", "author", "tags"]);
+
+        let (count, csv) =
+            export_string(&db, &ExportOptions::new(ExportFormat::Csv).batch_size(2)).await;
+        assert_eq!(count, 3);
+        assert_eq!(
+            csv,
+            "title,author.name,author.city,tags\r\n\
+             first,alice,Lyon,\"[\"\"a\"\",\"\"b\"\"]\"\r\n\
+             \"second, part \"\"2\"\"\",bob,,\"[\"\"a\"\",\"\"b\"\"]\"\r\n\
+             third,carol,,\"[\"\"a\"\",\"\"b\"\"]\"\r\n"
+        );
+
+        let (_, ndjson) = export_string(&db, &ExportOptions::new(ExportFormat::Ndjson)).await;
+        let lines: Vec<_> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("{\"title\":\"first\""));
+
+        let (_, array) = export_string(
+            &db,
+            &ExportOptions::new(ExportFormat::JsonArray).batch_size(1),
+        )
+        .await;
+        assert!(array.starts_with("[{\"title\":\"first\""));
+        assert_eq!(array.matches("},{\"title\"").count(), 2);
+        assert!(array.ends_with("}]\n"));
+
+        db.clear();
+        let (count, array) = export_string(&db, &ExportOptions::new(ExportFormat::JsonArray)).await;
+        assert_eq!((count, array.as_str()), (0, "[]\n"));
+        let (_, csv) = export_string(
+            &db,
+            &ExportOptions::new(ExportFormat::Csv).columns(["title"]),
+        )
+        .await;
+        assert_eq!(csv, "title\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_export_batches_follow_sort_keys() {
+        let db = MemoryBackend::new();
+        Post::create_many(
+            &db,
+            vec![
+                post("a", "bob", Some("Lyon")),
+                post("b", "alice", None),
+                post("c", "bob", None),
+                post("d", "alice", Some("Paris")),
+                post("e", "bob", Some("Lyon")),
+            ],
+        )
+        .await
+        .unwrap();
+
+        // 相同的排序值与缺失字段跨越批次边界时，每个文档恰好导出一次
+        for sort in [
+            vec![SortSpec::desc("author.city"), SortSpec::asc("author.name")],
+            vec![SortSpec::asc("author.city"), SortSpec::desc("author.name")],
+        ] {
+            let query = ListQuery {
+                sort,
+                ..Default::default()
+            };
+            let expected: Vec<_> = Post::find_list(&db, doc! {}, &query)
+                .await
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|post| post.title)
+                .collect();
+            let options = ExportOptions::new(ExportFormat::Csv)
+                .columns(["title"])
+                .batch_size(1);
+            let mut output = Vec::new();
+            let count = Post::export(&db, doc! {}, &query, &options, &mut output)
+                .await
+                .unwrap();
+            let exported: Vec<_> = String::from_utf8(output)
+                .unwrap()
+                .lines()
+                .skip(1)
+                .map(str::to_string)
+                .collect();
+            assert_eq!(count, 5);
+            assert_eq!(exported, expected);
+        }
+    }
+}
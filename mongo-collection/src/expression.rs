@@ -0,0 +1,658 @@
+//! 筛选表达式
+//!
+//! `q=` 参数使用的小型查询语言，例如
+//! `status:active AND (age>=18 OR vip:true) AND name~"ali"`。
+//!
+//! | 语法 | 含义 |
+//! |------|------|
+//! | `field:value` / `field!:value` | 等于 / 不等于 |
+//! | `field>value`、`>=`、`<`、`<=` | 比较 |
+//! | `field~value` | 包含（不区分大小写） |
+//! | `AND`、`OR`、`NOT`、`( )` | 逻辑组合，相邻条件默认为 `AND` |
+//!
+//! 含空白、括号或运算符的值需要用双引号包围，引号内用 `\` 转义。
+
+use crate::Collection;
+use crate::query_string::QueryError;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{Bson, DateTime, Document, doc};
+use std::collections::HashMap;
+
+/// 表达式的最大嵌套深度
+const MAX_DEPTH: usize = 32;
+
+/// 字段的 BSON 类型，来自 `$jsonSchema`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FieldType {
+    /// 允许的类型，为空时不限制
+    types: Vec<String>,
+    /// 数组元素的类型
+    items: Option<Box<FieldType>>,
+}
+
+impl FieldType {
+    fn from_schema(schema: &Document) -> Self {
+        let types = match schema.get("bsonType") {
+            Some(Bson::String(single)) => vec![single.clone()],
+            Some(Bson::Array(types)) => types
+                .iter()
+                .filter_map(|t| t.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let items = schema
+            .get_document("items")
+            .ok()
+            .map(|items| Box::new(Self::from_schema(items)));
+        Self { types, items }
+    }
+
+    fn allows(&self, bson_type: &str) -> bool {
+        self.types.is_empty() || self.types.iter().any(|t| t == bson_type)
+    }
+
+    /// 是否可以按 `a.b` 访问子字段
+    fn has_subfields(&self) -> bool {
+        self.allows("object") || self.allows("array")
+    }
+}
+
+/// 筛选表达式解析器
+///
+/// 通过 [`for_collection`](Self::for_collection) 创建时只允许模型 `$jsonSchema` 中的字段，
+/// 并按字段的 BSON 类型转换值；嵌套文档与数组的子字段按字面量推断类型。
+/// [`deny`](Self::deny) 的字段与标记了 `#[collection(skip_filter)]` 的字段被拒绝。
+///
+/// # 示例
+///
+/// ```ignore
+/// let filter = FilterLanguage::for_collection::<User>()
+///     .deny("password_hash")
+///     .parse(r#"status:active AND (age>=18 OR vip:true) AND name~"ali""#)?;
+/// let users = User::find_many(&db, filter, None).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FilterLanguage {
+    /// 允许的顶层字段，`None` 表示不限制
+    fields: Option<HashMap<String, FieldType>>,
+    /// 禁止的字段，其子字段同样被禁止
+    denied: Vec<String>,
+}
+
+impl FilterLanguage {
+    /// 创建不限制字段的解析器，值的类型按字面量推断
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 只允许模型 `T` 的字段，并按 `T::json_schema()` 转换值
+    ///
+    /// [`Collection::unfilterable_fields`] 中的字段被拒绝。
+    /// 手动实现 `json_schema` 且没有 `properties` 时不允许任何字段，可通过 [`allow`](Self::allow) 添加
+    pub fn for_collection<T: Collection>() -> Self {
+        T::unfilterable_fields()
+            .iter()
+            .fold(Self::for_schema::<T>(), |language, field| {
+                language.deny(field)
+            })
+    }
+
+    /// 允许 `T::json_schema()` 中的全部字段，不排除 `skip_filter` 字段
+    pub(crate) fn for_schema<T: Collection>() -> Self {
+        let schema = T::json_schema();
+        let fields = schema
+            .get_document("properties")
+            .map(|properties| {
+                properties
+                    .iter()
+                    .filter_map(|(name, node)| {
+                        Some((name.clone(), FieldType::from_schema(node.as_document()?)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            fields: Some(fields),
+            denied: Vec::new(),
+        }
+    }
+
+    /// 允许字段 `field`，值的类型按字面量推断
+    ///
+    /// 同时撤销此前对该字段的 [`deny`](Self::deny)
+    pub fn allow(mut self, field: impl Into<String>) -> Self {
+        let field = field.into();
+        self.denied.retain(|denied| *denied != field);
+        self.fields
+            .get_or_insert_with(HashMap::new)
+            .insert(field, FieldType::default());
+        self
+    }
+
+    /// 禁止字段 `field` 及其子字段
+    ///
+    /// 不影响其它字段：对 [`new`](Self::new) 创建的解析器，其余字段仍不受限制
+    pub fn deny(mut self, field: &str) -> Self {
+        if let Some(ref mut fields) = self.fields {
+            fields.remove(field);
+        }
+        self.denied.push(field.to_string());
+        self
+    }
+
+    /// 把表达式解析为 MongoDB 过滤条件，空表达式返回空文档
+    pub fn parse(&self, input: &str) -> Result<Document, QueryError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(Document::new());
+        }
+        let mut parser = Parser {
+            language: self,
+            input,
+            tokens,
+            position: 0,
+            depth: 0,
+        };
+        let filter = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(filter),
+            Some(_) => Err(parser.error("an operator or the end of the expression")),
+        }
+    }
+
+    /// 把 CSV 等文本格式中的单元格转换为字段 `path` 的值
+    ///
+    /// 数组与文档字段接受 JSON（Extended JSON）；数组字段的单个值转换为只有一个元素的数组
+    pub(crate) fn coerce_cell(
+        &self,
+        path: &str,
+        value: String,
+        quoted: bool,
+    ) -> Result<Bson, QueryError> {
+        let field_type = self.field_type(path)?;
+        if field_type.is_none_or(FieldType::has_subfields) && value.starts_with(['[', '{']) {
+            let parsed = serde_json::from_str::<serde_json::Value>(&value)
+                .ok()
+                .and_then(|json| Bson::try_from(json).ok());
+            if let Some(parsed) = parsed {
+                return Ok(parsed);
+            }
+        }
+        let converted = coerce(field_type, path, value, quoted)?;
+        match field_type {
+            Some(FieldType { items: Some(_), .. }) if converted != Bson::Null => {
+                Ok(Bson::Array(vec![converted]))
+            }
+            _ => Ok(converted),
+        }
+    }
+
+    /// 检查字段是否允许，返回用于转换值的类型
+    fn field_type(&self, path: &str) -> Result<Option<&FieldType>, QueryError> {
+        let unknown = || QueryError::UnknownField {
+            field: path.to_string(),
+        };
+        let valid = path.split('.').all(|segment| {
+            segment
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        let denied = self.denied.iter().any(|denied| {
+            path.strip_prefix(denied.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        });
+        if !valid || denied {
+            return Err(unknown());
+        }
+
+        let Some(ref fields) = self.fields else {
+            return Ok(None);
+        };
+        let (root, nested) = match path.split_once('.') {
+            Some((root, _)) => (root, true),
+            None => (path, false),
+        };
+        match fields.get(root) {
+            Some(field) if !nested => Ok(Some(field)),
+            Some(field) if field.has_subfields() => Ok(None),
+            _ => Err(unknown()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+/// 词法分析
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ':' => Token::Op(Op::Eq),
+            '~' => Token::Op(Op::Contains),
+            '!' if chars.next_if_eq(&':').is_some() => Token::Op(Op::Ne),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Gte),
+            '>' => Token::Op(Op::Gt),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Lte),
+            '<' => Token::Op(Op::Lt),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                        None => return Err(invalid(input, "a closing `\"`")),
+                    }
+                }
+                Token::Quoted(text)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|&c| {
+                    !c.is_whitespace()
+                        && !matches!(c, '(' | ')' | ':' | '~' | '!' | '>' | '<' | '"')
+                }) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn invalid(input: &str, expected: &'static str) -> QueryError {
+    QueryError::InvalidValue {
+        field: "q".to_string(),
+        value: input.to_string(),
+        expected,
+    }
+}
+
+/// 递归下降解析器
+struct Parser<'a> {
+    language: &'a FilterLanguage,
+    input: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, expected: &'static str) -> QueryError {
+        invalid(self.input, expected)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matched = matches!(self.peek(), Some(Token::Word(word)) if word == keyword);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn or(&mut self) -> Result<Document, QueryError> {
+        let mut clauses = vec![self.and()?];
+        while self.keyword("OR") {
+            clauses.push(self.and()?);
+        }
+        Ok(combine("$or", clauses))
+    }
+
+    fn and(&mut self) -> Result<Document, QueryError> {
+        let mut clauses = vec![self.unary()?];
+        loop {
+            if self.keyword("AND") {
+                clauses.push(self.unary()?);
+                continue;
+            }
+            // 相邻的条件视为 AND
+            match self.peek() {
+                Some(Token::Open) => clauses.push(self.unary()?),
+                Some(Token::Word(word)) if word != "OR" => clauses.push(self.unary()?),
+                _ => return Ok(combine("$and", clauses)),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Document, QueryError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("an expression nested at most 32 levels deep"));
+        }
+        let result = if self.keyword("NOT") {
+            self.unary().map(|inner| doc! { "$nor": [inner] })
+        } else if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let inner = self.or()?;
+            match self.next() {
+                Some(Token::Close) => Ok(inner),
+                _ => Err(self.error("a closing `)`")),
+            }
+        } else {
+            self.comparison()
+        };
+        self.depth -= 1;
+        result
+    }
+
+    fn comparison(&mut self) -> Result<Document, QueryError> {
+        let Some(Token::Word(field)) = self.next() else {
+            return Err(self.error("a field name"));
+        };
+        let Some(Token::Op(op)) = self.next() else {
+            return Err(self.error("an operator after the field name"));
+        };
+        let (value, quoted) = match self.next() {
+            Some(Token::Word(value)) => (value, false),
+            Some(Token::Quoted(value)) => (value, true),
+            _ => return Err(self.error("a value after the operator")),
+        };
+
+        let field_type = self.language.field_type(&field)?;
+        if op == Op::Contains {
+            if field_type.is_some_and(|t| !t.allows("string") && !t.has_subfields()) {
+                return Err(QueryError::InvalidValue {
+                    field,
+                    value,
+                    expected: "`~` on a string field",
+                });
+            }
+            let pattern = regex::escape(&value);
+            return Ok(doc! { field: { "$regex": pattern, "$options": "i" } });
+        }
+
+        let value = coerce(field_type, &field, value, quoted)?;
+        let condition = match op {
+            Op::Eq => return Ok(doc! { field: value }),
+            Op::Ne => "$ne",
+            Op::Gt => "$gt",
+            Op::Gte => "$gte",
+            Op::Lt => "$lt",
+            Op::Lte => "$lte",
+            Op::Contains => unreachable!("handled above"),
+        };
+        Ok(doc! { field: { condition: value } })
+    }
+}
+
+/// 合并逻辑子句，只有一个子句时直接返回
+fn combine(operator: &str, mut clauses: Vec<Document>) -> Document {
+    if clauses.len() == 1 {
+        clauses.remove(0)
+    } else {
+        doc! { operator: clauses }
+    }
+}
+
+/// 按字段类型转换值；类型未知时按字面量推断，带引号的值视为字符串
+fn coerce(
+    field_type: Option<&FieldType>,
+    field: &str,
+    value: String,
+    quoted: bool,
+) -> Result<Bson, QueryError> {
+    let field_type = match field_type {
+        // 数组字段按元素类型匹配
+        Some(FieldType {
+            items: Some(items), ..
+        }) => Some(items.as_ref()),
+        other => other,
+    };
+    let types = match field_type {
+        Some(field_type) if !field_type.types.is_empty() => &field_type.types,
+        _ => return Ok(infer(value, quoted)),
+    };
+
+    if value == "null" && !quoted && types.iter().any(|t| t == "null") {
+        return Ok(Bson::Null);
+    }
+    let mut expected = "a value of the field's type";
+    for bson_type in types {
+        let (converted, description) = match bson_type.as_str() {
+            "string" => (Some(Bson::String(value.clone())), "a string"),
+            "int" => (value.parse().ok().map(Bson::Int32), "an integer"),
+            "long" => (value.parse().ok().map(Bson::Int64), "an integer"),
+            "double" | "decimal" => (value.parse().ok().map(Bson::Double), "a number"),
+            "bool" => (value.parse().ok().map(Bson::Boolean), "`true` or `false`"),
+            "date" => (parse_date(&value).map(Bson::DateTime), "an RFC 3339 date"),
+            "objectId" => (
+                ObjectId::parse_str(&value).ok().map(Bson::ObjectId),
+                "an ObjectId",
+            ),
+            "object" | "array" => (Some(infer(value.clone(), quoted)), ""),
+            _ => continue,
+        };
+        if let Some(converted) = converted {
+            return Ok(converted);
+        }
+        expected = description;
+    }
+    Err(QueryError::InvalidValue {
+        field: field.to_string(),
+        value,
+        expected,
+    })
+}
+
+/// 解析 RFC 3339 时间或 `YYYY-MM-DD` 日期（UTC 零点）
+fn parse_date(value: &str) -> Option<DateTime> {
+    DateTime::parse_rfc3339_str(value)
+        .or_else(|_| DateTime::parse_rfc3339_str(format!("{value}T00:00:00Z")))
+        .ok()
+}
+
+/// 按字面量推断类型
+fn infer(value: String, quoted: bool) -> Bson {
+    if quoted {
+        return Bson::String(value);
+    }
+    match value.as_str() {
+        "true" => Bson::Boolean(true),
+        "false" => Bson::Boolean(false),
+        "null" => Bson::Null,
+        _ => {
+            if let Ok(int) = value.parse::<i64>() {
+                Bson::Int64(int)
+            } else if let Ok(float) = value.parse::<f64>() {
+                Bson::Double(float)
+            } else {
+                Bson::String(value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collection, CollectionRepository, MemoryBackend, PaginatedQuery};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Member {
+        name: String,
+        status: String,
+        age: i32,
+        vip: Option<bool>,
+        joined: Option<DateTime>,
+        tags: Vec<String>,
+        address: Address,
+        #[collection(skip_filter)]
+        password_hash: String,
+        secret: Option<Document>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    struct Address {
+        city: String,
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        let language = FilterLanguage::for_collection::<Member>();
+        let filter = language
+            .parse(r#"status:active AND (age>=18 OR vip:true) AND name~"a.i""#)
+            .unwrap();
+        assert_eq!(
+            filter,
+            doc! {
+                "$and": [
+                    { "status": "active" },
+                    { "$or": [{ "age": { "$gte": 18 } }, { "vip": true }] },
+                    { "name": { "$regex": "a\\.i", "$options": "i" } },
+                ]
+            }
+        );
+
+        // 相邻条件视为 AND，NOT 转换为 $nor
+        let filter = language
+            .parse("NOT vip:null tags:rust address.city!:\"New York\" joined<2024-01-01")
+            .unwrap();
+        assert_eq!(
+            filter,
+            doc! {
+                "$and": [
+                    { "$nor": [{ "vip": Bson::Null }] },
+                    { "tags": "rust" },
+                    { "address.city": { "$ne": "New York" } },
+                    { "joined": { "$lt": DateTime::parse_rfc3339_str("2024-01-01T00:00:00Z").unwrap() } },
+                ]
+            }
+        );
+
+        assert_eq!(language.parse("  ").unwrap(), doc! {});
+    }
+
+    #[test]
+    fn test_rejects_unknown_fields_and_bad_values() {
+        let language = FilterLanguage::for_collection::<Member>().deny("secret");
+        // skip_filter 字段与 deny 的字段及其子字段都被拒绝
+        for expression in ["password_hash:x", "secret:x", "secret.token:x"] {
+            assert!(matches!(
+                language.parse(expression),
+                Err(QueryError::UnknownField { .. })
+            ));
+        }
+        assert_eq!(
+            language.parse("password_hash:x").unwrap_err(),
+            QueryError::UnknownField {
+                field: "password_hash".to_string()
+            }
+        );
+        assert!(matches!(
+            language.parse("$where:1"),
+            Err(QueryError::UnknownField { .. })
+        ));
+        assert!(matches!(
+            language.parse("name.first:x"),
+            Err(QueryError::UnknownField { .. })
+        ));
+        assert!(matches!(
+            language.parse("age>old"),
+            Err(QueryError::InvalidValue { ref field, expected: "an integer", .. }) if field == "age"
+        ));
+        assert!(language.parse("age~1").is_err());
+        assert!(language.parse("(status:active").is_err());
+        assert!(language.parse("status:\"active").is_err());
+        assert!(language.parse("status:active OR").is_err());
+        assert!(language.parse(&"(".repeat(40)).is_err());
+
+        // 不限制字段时按字面量推断
+        assert_eq!(
+            FilterLanguage::new()
+                .parse("score>1.5 active:true")
+                .unwrap(),
+            doc! { "$and": [{ "score": { "$gt": 1.5 } }, { "active": true }] }
+        );
+        // deny 只排除指定字段，不会把不受限的解析器变成空的允许列表
+        let language = FilterLanguage::new().deny("password_hash");
+        assert!(language.parse("password_hash:x").is_err());
+        assert!(language.parse("password_hash_hint:x").is_ok());
+        assert_eq!(language.parse("age:1").unwrap(), doc! { "age": 1_i64 });
+        assert!(
+            FilterLanguage::new()
+                .deny("age")
+                .allow("age")
+                .parse("age:1")
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_q_filter_on_memory_backend() {
+        let db = MemoryBackend::new();
+        let member = |name: &str, age: i32, vip: bool| Member {
+            name: name.to_string(),
+            status: "active".to_string(),
+            age,
+            vip: Some(vip),
+            joined: None,
+            tags: vec![],
+            address: Address {
+                city: "Paris".to_string(),
+            },
+            password_hash: String::new(),
+            secret: None,
+        };
+        Member::create_many(
+            &db,
+            vec![
+                member("alice", 30, false),
+                member("alina", 16, true),
+                member("bob", 40, true),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let query = PaginatedQuery::from_query_str("q=name~ALI AND (age>=18 OR vip:true)").unwrap();
+        let filter = query.q_filter::<Member>().unwrap();
+        let page = Member::find_paginated(&db, filter, &query).await.unwrap();
+        assert_eq!(page.total_count, 2);
+
+        let query = PaginatedQuery::from_query_str("q=password_hash:abc").unwrap();
+        assert!(matches!(
+            query.q_filter::<Member>(),
+            Err(QueryError::UnknownField { .. })
+        ));
+        assert_eq!(Member::unfilterable_fields(), ["password_hash"]);
+    }
+}
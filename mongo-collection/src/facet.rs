@@ -0,0 +1,251 @@
+//! 分面统计
+//!
+//! `facets=status,category` 在分页结果旁返回每个字段的取值与文档数量。
+//! 当前页数据、总数与各字段的统计在同一个 `$facet` 聚合中计算，
+//! 因此统计结果与分页结果使用同一个筛选条件。
+
+use crate::backend::{Backend, CollectionTarget};
+use crate::{Collection, PaginatedData, PaginatedQuery, QueryError};
+use mongodb::bson::{Bson, Document, deserialize_from_document, doc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// 每个字段最多返回的取值数量
+const MAX_BUCKETS: i32 = 100;
+
+/// 分面统计中的一个取值
+///
+/// 数组字段按元素分别计数；缺少该字段的文档计入 `null`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct FacetBucket {
+    /// 字段取值
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub value: Bson,
+    /// 匹配筛选条件且取该值的文档数
+    pub count: u64,
+}
+
+/// 在一次 `$facet` 聚合中查询当前页、总数与 `query.facets` 的分面统计
+///
+/// `query` 已经过校验；分面字段来自请求参数，只允许 [`Collection::facet_fields`] 中的字段
+pub(crate) async fn find_paginated<T, B>(
+    db: &B,
+    target: &CollectionTarget,
+    filter: Document,
+    query: &PaginatedQuery,
+) -> Result<PaginatedData<T>, mongodb::error::Error>
+where
+    T: Collection + DeserializeOwned,
+    B: Backend + ?Sized,
+{
+    if let Some(field) = query
+        .facets
+        .iter()
+        .find(|field| !T::facet_fields().contains(&field.as_str()))
+    {
+        let error = QueryError::UnknownField {
+            field: field.clone(),
+        };
+        return Err(mongodb::error::Error::custom(error));
+    }
+
+    let output = db.aggregate(target, pipeline(filter, query)).await?;
+    let result = decode(output, &query.facets)?;
+
+    let mut items = Vec::with_capacity(result.items.len());
+    for document in result.items {
+        items.push(deserialize_from_document(document)?);
+    }
+
+    let mut data = PaginatedData::new(items, result.total_count, query);
+    data.facets = result.facets;
+    Ok(data)
+}
+
+/// 一次聚合得到的当前页、总数与分面统计
+struct FacetResult {
+    items: Vec<Document>,
+    total_count: u64,
+    facets: BTreeMap<String, Vec<FacetBucket>>,
+}
+
+/// `$facet` 中第 `index` 个统计字段的输出名
+///
+/// 字段路径可能含 `.`，不能直接用作输出名
+fn output_name(index: usize) -> String {
+    format!("facet_{index}")
+}
+
+/// 生成分页查询与分面统计的聚合管道
+///
+/// 统计按数量降序、取值升序排列，每个字段最多保留 [`MAX_BUCKETS`] 个取值
+fn pipeline(filter: Document, query: &PaginatedQuery) -> Vec<Document> {
+    let skip = i64::try_from(query.skip()).unwrap_or(i64::MAX);
+    let mut facets = doc! {
+        "items": [
+            { "$sort": query.sort_document() },
+            { "$skip": skip },
+            { "$limit": query.limit() },
+        ],
+        "total": [{ "$count": "count" }],
+    };
+    for (index, field) in query.facets.iter().enumerate() {
+        let path = format!("${field}");
+        facets.insert(
+            output_name(index),
+            vec![
+                doc! { "$unwind": { "path": &path, "preserveNullAndEmptyArrays": true } },
+                doc! { "$group": { "_id": &path, "count": { "$sum": 1 } } },
+                doc! { "$sort": { "count": -1, "_id": 1 } },
+                doc! { "$limit": MAX_BUCKETS },
+            ],
+        );
+    }
+    vec![doc! { "$match": filter }, doc! { "$facet": facets }]
+}
+
+/// 解析 [`pipeline`] 的输出
+fn decode(
+    mut output: Vec<Document>,
+    fields: &[String],
+) -> Result<FacetResult, mongodb::error::Error> {
+    let malformed = || crate::utils::invalid_argument("malformed $facet aggregation result");
+    let mut output = match output.pop() {
+        Some(document) if output.is_empty() => document,
+        _ => return Err(malformed()),
+    };
+
+    let mut documents = |name: &str| match output.remove(name) {
+        Some(Bson::Array(values)) => values
+            .into_iter()
+            .map(|value| match value {
+                Bson::Document(document) => Ok(document),
+                _ => Err(malformed()),
+            })
+            .collect::<Result<Vec<_>, _>>(),
+        None => Ok(Vec::new()),
+        Some(_) => Err(malformed()),
+    };
+
+    let items = documents("items")?;
+    // 没有匹配文档时 `$count` 不输出结果
+    let total_count = match documents("total")?.first() {
+        Some(total) => count(total.get("count")).ok_or_else(malformed)?,
+        None => 0,
+    };
+
+    let mut facets = BTreeMap::new();
+    for (index, field) in fields.iter().enumerate() {
+        let buckets = documents(&output_name(index))?
+            .into_iter()
+            .map(|mut bucket| {
+                Some(FacetBucket {
+                    count: count(bucket.get("count"))?,
+                    value: bucket.remove("_id").unwrap_or(Bson::Null),
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(malformed)?;
+        facets.insert(field.clone(), buckets);
+    }
+
+    Ok(FacetResult {
+        items,
+        total_count,
+        facets,
+    })
+}
+
+/// 聚合计数可能是 `Int32` 或 `Int64`
+fn count(value: Option<&Bson>) -> Option<u64> {
+    match value? {
+        Bson::Int32(n) => u64::try_from(*n).ok(),
+        Bson::Int64(n) => u64::try_from(*n).ok(),
+        Bson::Double(n) if *n >= 0.0 => Some(*n as u64),
+        _ => None,
+    }
+}
+
+/// 把逗号分隔的字段列表拆分为字段名，忽略空项
+pub(crate) fn parse_fields(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 反序列化 `facets` 参数：逗号分隔的字符串，或字段名数组
+pub(crate) fn deserialize_facets<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Text(String),
+        Fields(Vec<String>),
+    }
+
+    Ok(match Raw::deserialize(deserializer)? {
+        Raw::Text(text) => parse_fields(&text),
+        Raw::Fields(fields) => fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::query::aggregate;
+
+    #[test]
+    fn test_facet_pipeline_and_decode() {
+        let documents = vec![
+            doc! { "_id": 1, "status": "open", "tags": ["a", "b"] },
+            doc! { "_id": 2, "status": "closed", "tags": ["a"] },
+            doc! { "_id": 3, "status": "open" },
+            doc! { "_id": 4, "status": "open", "tags": [] },
+        ];
+        let query = PaginatedQuery {
+            page_size: 2,
+            facets: vec!["status".to_string(), "tags".to_string()],
+            ..Default::default()
+        };
+
+        let output = aggregate(documents, &pipeline(doc! {}, &query)).unwrap();
+        let result = decode(output, &query.facets).unwrap();
+        assert_eq!(result.total_count, 4);
+        assert_eq!(
+            result.items,
+            vec![
+                doc! { "_id": 4, "status": "open", "tags": [] },
+                doc! { "_id": 3, "status": "open" }
+            ]
+        );
+
+        let bucket = |value: Bson, count| FacetBucket { value, count };
+        assert_eq!(
+            result.facets["status"],
+            vec![bucket("open".into(), 3), bucket("closed".into(), 1)]
+        );
+        assert_eq!(
+            result.facets["tags"],
+            vec![
+                bucket(Bson::Null, 2),
+                bucket("a".into(), 2),
+                bucket("b".into(), 1)
+            ]
+        );
+
+        let output = aggregate(Vec::new(), &pipeline(doc! {}, &query)).unwrap();
+        let result = decode(output, &query.facets).unwrap();
+        assert_eq!(result.total_count, 0);
+        assert!(result.facets["status"].is_empty());
+    }
+}
@@ -0,0 +1,622 @@
+//! 批量导入
+//!
+//! 从 [`AsyncBufRead`] 逐行读取 NDJSON 或 CSV，转换为模型并校验后分批写入集合。
+//! 无效的记录与写入失败按行号记录在 [`ImportReport`] 中，不会中断整个导入，
+//! 有序导入（默认）除外：遇到第一个错误即停止。
+//!
+//! 导入格式与 [`export`](crate::CollectionRepository::export) 的输出一致：
+//! NDJSON 为 Extended JSON，CSV 的 `author.name` 列写入嵌套文档，数组与文档单元格为 JSON。
+
+use crate::backend::{Backend, CollectionTarget};
+use crate::{Collection, FilterLanguage};
+use futures::io::{AsyncBufRead, AsyncBufReadExt};
+use mongodb::bson::{Bson, Document, deserialize_from_document, doc, serialize_to_document};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::Arc;
+
+type Validator<T> = Arc<dyn Fn(&T) -> Result<(), String> + Send + Sync>;
+
+/// 导入格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// 每行一个 JSON 文档，支持 Extended JSON
+    Ndjson,
+    /// 带表头的 CSV（RFC 4180），值按模型的 `$jsonSchema` 转换类型
+    Csv,
+}
+
+/// 导入选项
+///
+/// # 示例
+///
+/// ```ignore
+/// let options = ImportOptions::new()
+///     .batch_size(500)
+///     .ordered(false)
+///     .upsert_by(["email"])
+///     .validate(|user: &User| match user.age {
+///         0..=150 => Ok(()),
+///         _ => Err("age out of range".to_string()),
+///     });
+/// ```
+pub struct ImportOptions<T> {
+    /// 每批插入的文档数
+    pub batch_size: usize,
+    /// 遇到第一个错误时是否停止
+    pub ordered: bool,
+    /// 按这些字段匹配已有文档并更新，为空时只插入
+    ///
+    /// 每条记录通过一次原子的 [`Backend::upsert_one`] 写入；记录中的 `_id` 只在插入新文档时使用
+    pub upsert_by: Vec<String>,
+    validator: Option<Validator<T>>,
+}
+
+impl<T> ImportOptions<T> {
+    /// 默认每批插入的文档数
+    pub const DEFAULT_BATCH_SIZE: usize = 1000;
+
+    /// 有序导入，每批 [`DEFAULT_BATCH_SIZE`](Self::DEFAULT_BATCH_SIZE) 条，只插入
+    pub fn new() -> Self {
+        Self {
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            ordered: true,
+            upsert_by: Vec::new(),
+            validator: None,
+        }
+    }
+
+    /// 设置每批插入的文档数，至少为 1
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// 设置是否有序：`false` 时跳过无效记录与写入失败的文档，继续导入其余记录
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// 按字段（支持点号路径）匹配已有文档：存在时用记录覆盖其字段，否则插入
+    ///
+    /// 每条记录单独查询与写入，不使用批量插入
+    pub fn upsert_by<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.upsert_by = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 在写入前校验每条记录，返回 `Err` 的记录计入错误
+    pub fn validate<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+}
+
+impl<T> Default for ImportOptions<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ImportOptions<T> {
+    fn clone(&self) -> Self {
+        Self {
+            batch_size: self.batch_size,
+            ordered: self.ordered,
+            upsert_by: self.upsert_by.clone(),
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ImportOptions<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportOptions")
+            .field("batch_size", &self.batch_size)
+            .field("ordered", &self.ordered)
+            .field("upsert_by", &self.upsert_by)
+            .field("validator", &self.validator.is_some())
+            .finish()
+    }
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// 新插入的文档数
+    pub inserted: u64,
+    /// 按 `upsert_by` 更新的已有文档数
+    pub updated: u64,
+    /// 失败的记录
+    pub errors: Vec<ImportLineError>,
+}
+
+impl ImportReport {
+    /// 是否所有记录都已写入
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// 单条记录的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportLineError {
+    /// 记录起始的行号（从 1 开始，CSV 表头为第 1 行）
+    pub line: u64,
+    /// 错误信息
+    pub message: String,
+}
+
+impl fmt::Display for ImportLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// 从 `reader` 导入 `T` 的记录
+///
+/// 读取或后端连接失败时返回错误；单条记录的问题记录在报告中
+pub(crate) async fn import<T, B, R>(
+    db: &B,
+    target: &CollectionTarget,
+    reader: &mut R,
+    format: ImportFormat,
+    options: &ImportOptions<T>,
+) -> Result<ImportReport, mongodb::error::Error>
+where
+    T: Collection + Serialize + DeserializeOwned,
+    B: Backend + ?Sized,
+    R: AsyncBufRead + Unpin + Send + ?Sized,
+{
+    let mut importer = Importer {
+        db,
+        target,
+        options,
+        report: ImportReport::default(),
+        pending: Vec::new(),
+        stopped: false,
+    };
+    let mut records = Records {
+        reader,
+        line: 0,
+        format,
+    };
+
+    let columns = match format {
+        ImportFormat::Ndjson => Vec::new(),
+        ImportFormat::Csv => match records.next().await? {
+            Some((_, header)) => parse_csv(&header)
+                .map_err(crate::utils::invalid_argument)?
+                .into_iter()
+                .map(|(column, _)| column)
+                .collect(),
+            None => return Ok(importer.report),
+        },
+    };
+    let language = if T::json_schema().contains_key("properties") {
+        FilterLanguage::for_schema::<T>()
+    } else {
+        FilterLanguage::new()
+    };
+
+    while let Some((line, text)) = records.next().await? {
+        let document = match format {
+            ImportFormat::Ndjson => parse_json_line(&text),
+            ImportFormat::Csv => {
+                parse_csv(&text).and_then(|cells| csv_document(&language, &columns, cells))
+            }
+        };
+        match document.and_then(|document| check::<T>(document, options)) {
+            Ok(document) => importer.write(line, document).await?,
+            Err(message) => {
+                // 有序导入停止前先写入此前的有效记录
+                if options.ordered {
+                    importer.flush().await?;
+                }
+                importer.fail(line, message);
+            }
+        }
+        if importer.stopped {
+            break;
+        }
+    }
+    importer.flush().await?;
+    Ok(importer.report)
+}
+
+/// 反序列化为 `T` 并校验，返回 `T` 序列化后的文档
+///
+/// 模型没有 `_id` 字段时记录中的 `_id` 不会在往返中丢失，原样写入
+fn check<T>(document: Document, options: &ImportOptions<T>) -> Result<Document, String>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let id = document.get("_id").cloned();
+    let item: T = deserialize_from_document(document).map_err(|e| e.to_string())?;
+    if let Some(ref validator) = options.validator {
+        validator(&item)?;
+    }
+    let mut checked = serialize_to_document(&item).map_err(|e| e.to_string())?;
+    if let Some(id) = id
+        && !checked.contains_key("_id")
+    {
+        checked.insert("_id", id);
+    }
+    Ok(checked)
+}
+
+/// 按格式逐条读取记录，跳过空行
+struct Records<'r, R: ?Sized> {
+    reader: &'r mut R,
+    /// 已读取的行数
+    line: u64,
+    format: ImportFormat,
+}
+
+impl<R: AsyncBufRead + Unpin + Send + ?Sized> Records<'_, R> {
+    /// 返回下一条记录的起始行号与文本；CSV 中引号内的换行属于同一条记录
+    async fn next(&mut self) -> Result<Option<(u64, String)>, mongodb::error::Error> {
+        let mut record = String::new();
+        let mut start = 0;
+        loop {
+            let mut buffer = String::new();
+            if self.reader.read_line(&mut buffer).await? == 0 {
+                return Ok((!record.trim().is_empty()).then_some((start, record)));
+            }
+            self.line += 1;
+            if record.is_empty() {
+                if buffer.trim().is_empty() {
+                    continue;
+                }
+                start = self.line;
+            }
+            record.push_str(&buffer);
+
+            let complete = match self.format {
+                ImportFormat::Ndjson => true,
+                ImportFormat::Csv => record.matches('"').count().is_multiple_of(2),
+            };
+            if complete {
+                let trimmed = record.trim_end_matches(['\r', '\n']).len();
+                record.truncate(trimmed);
+                return Ok(Some((start, record)));
+            }
+        }
+    }
+}
+
+/// 收集待插入的文档并写入后端
+struct Importer<'a, T, B: ?Sized> {
+    db: &'a B,
+    target: &'a CollectionTarget,
+    options: &'a ImportOptions<T>,
+    report: ImportReport,
+    /// 待插入的文档及其行号
+    pending: Vec<(u64, Document)>,
+    /// 有序导入遇到错误后停止
+    stopped: bool,
+}
+
+impl<T, B: Backend + ?Sized> Importer<'_, T, B> {
+    fn fail(&mut self, line: u64, message: String) {
+        self.report.errors.push(ImportLineError { line, message });
+        if self.options.ordered {
+            self.stopped = true;
+        }
+    }
+
+    async fn write(&mut self, line: u64, document: Document) -> Result<(), mongodb::error::Error> {
+        if self.options.upsert_by.is_empty() {
+            self.pending.push((line, document));
+            if self.pending.len() >= self.options.batch_size {
+                self.flush().await?;
+            }
+            return Ok(());
+        }
+
+        let mut filter = Document::new();
+        for key in &self.options.upsert_by {
+            match lookup(&document, key) {
+                Some(value) => filter.insert(key, value.clone()),
+                None => {
+                    self.fail(line, format!("missing upsert key `{key}`"));
+                    return Ok(());
+                }
+            };
+        }
+        // `_id` 不可修改，只在插入新文档时写入
+        let mut fields = document;
+        let mut update = doc! {};
+        if let Some(id) = fields.remove("_id") {
+            update.insert("$setOnInsert", doc! { "_id": id });
+        }
+        update.insert("$set", fields);
+        match self.db.upsert_one(self.target, filter, update).await {
+            Ok(true) => self.report.inserted += 1,
+            Ok(false) => self.report.updated += 1,
+            Err(error) => self.fail(line, error.to_string()),
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), mongodb::error::Error> {
+        if self.pending.is_empty() || self.stopped {
+            return Ok(());
+        }
+        let (lines, documents): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.pending).into_iter().unzip();
+        let failures = self
+            .db
+            .insert_batch(self.target, documents, self.options.ordered)
+            .await?;
+
+        let attempted = match failures.first() {
+            // 有序插入在第一个失败的文档处停止
+            Some(&(index, _)) if self.options.ordered => index + 1,
+            _ => lines.len(),
+        };
+        self.report.inserted += (attempted - failures.len()) as u64;
+        for (index, message) in failures {
+            self.fail(lines[index], message);
+        }
+        Ok(())
+    }
+}
+
+/// 按点号路径取值
+fn lookup<'a>(document: &'a Document, path: &str) -> Option<&'a Bson> {
+    match path.split_once('.') {
+        None => document.get(path),
+        Some((head, rest)) => match document.get(head)? {
+            Bson::Document(child) => lookup(child, rest),
+            _ => None,
+        },
+    }
+}
+
+pub(crate) fn parse_json_line(line: &str) -> Result<Document, String> {
+    let json: serde_json::Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    match Bson::try_from(json).map_err(|e| e.to_string())? {
+        Bson::Document(document) => Ok(document),
+        _ => Err("expected a JSON object".to_string()),
+    }
+}
+
+/// 拆分一条 CSV 记录，返回每个单元格及其是否带引号
+fn parse_csv(record: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut cells = Vec::new();
+    let mut chars = record.chars().peekable();
+    loop {
+        let mut cell = String::new();
+        let quoted = chars.peek() == Some(&'"');
+        if quoted {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        cell.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => cell.push(c),
+                    None => return Err("unterminated quoted field".to_string()),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' {
+                    break;
+                }
+                if c == '"' {
+                    return Err("unexpected quote in unquoted field".to_string());
+                }
+                cell.push(c);
+                chars.next();
+            }
+        }
+        cells.push((cell, quoted));
+        match chars.next() {
+            None => return Ok(cells),
+            Some(',') => {}
+            Some(_) => return Err("expected `,` after quoted field".to_string()),
+        }
+    }
+}
+
+/// 按表头把 CSV 单元格转换为文档；不带引号的空单元格视为字段缺失
+fn csv_document(
+    language: &FilterLanguage,
+    columns: &[String],
+    cells: Vec<(String, bool)>,
+) -> Result<Document, String> {
+    if cells.len() != columns.len() {
+        return Err(format!(
+            "expected {} fields, found {}",
+            columns.len(),
+            cells.len()
+        ));
+    }
+    let mut document = Document::new();
+    for (column, (cell, quoted)) in columns.iter().zip(cells) {
+        if cell.is_empty() && !quoted {
+            continue;
+        }
+        let value = language
+            .coerce_cell(column, cell, quoted)
+            .map_err(|e| e.to_string())?;
+        insert_path(&mut document, column, value)?;
+    }
+    Ok(document)
+}
+
+fn insert_path(document: &mut Document, path: &str, value: Bson) -> Result<(), String> {
+    match path.split_once('.') {
+        None => {
+            document.insert(path, value);
+            Ok(())
+        }
+        Some((head, rest)) => {
+            let child = document
+                .entry(head.to_string())
+                .or_insert_with(|| Bson::Document(Document::new()));
+            match child {
+                Bson::Document(child) => insert_path(child, rest, value),
+                _ => Err(format!("column `{path}` conflicts with column `{head}`")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CollectionRepository, ExportFormat, ExportOptions, ListQuery, MemoryBackend};
+    use mongodb::bson::oid::ObjectId;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Address {
+        city: String,
+        zip: Option<String>,
+    }
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Contact {
+        email: String,
+        name: String,
+        age: i32,
+        tags: Vec<String>,
+        address: Address,
+    }
+
+    async fn import(
+        db: &MemoryBackend,
+        input: &str,
+        format: ImportFormat,
+        options: &ImportOptions<Contact>,
+    ) -> ImportReport {
+        let mut reader = input.as_bytes();
+        Contact::import(db, &mut reader, format, options)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        assert_eq!(
+            parse_csv("a,\"b,\"\"c\"\"\",,\"\"").unwrap(),
+            vec![
+                ("a".to_string(), false),
+                ("b,\"c\"".to_string(), true),
+                (String::new(), false),
+                (String::new(), true),
+            ]
+        );
+        assert!(parse_csv("\"open").is_err());
+        assert!(parse_csv("a\"b").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_csv_with_errors() {
+        let db = MemoryBackend::new();
+        let input = "email,name,age,tags,address.city,address.zip\r\n\
+                     a@x.io,Alice,30,\"[\"\"x\"\",\"\"y\"\"]\",Lyon,\"01000\"\r\n\
+                     \r\n\
+                     b@x.io,Bob,old,x,Paris,\r\n\
+                     c@x.io,\"Carol\nSmith\",41,x,Nice,\r\n";
+
+        let options = ImportOptions::new().ordered(false).batch_size(1);
+        let report = import(&db, input, ImportFormat::Csv, &options).await;
+        assert_eq!(report.inserted, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 4);
+
+        let carol = Contact::find_one(&db, doc! { "email": "c@x.io" })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(carol.name, "Carol\nSmith");
+        assert_eq!(carol.tags, ["x"]);
+        let alice = Contact::find_one(&db, doc! { "email": "a@x.io" })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alice.address.zip.as_deref(), Some("01000"));
+
+        // 有序导入在第一个错误处停止
+        db.clear();
+        let report = import(&db, input, ImportFormat::Csv, &ImportOptions::new()).await;
+        assert_eq!((report.inserted, report.errors.len()), (1, 1));
+        assert_eq!(db.documents("contacts").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_ndjson_upsert_and_validate() {
+        let db = MemoryBackend::new();
+        let line = |email: &str, age: i32| {
+            format!(
+                r#"{{"email":"{email}","name":"n","age":{age},"tags":[],"address":{{"city":"Lyon"}}}}"#
+            )
+        };
+        let input = [line("a@x.io", 30), line("b@x.io", 20)].join("\n");
+        let report = import(&db, &input, ImportFormat::Ndjson, &ImportOptions::new()).await;
+        assert_eq!(report.inserted, 2);
+
+        let input = [line("a@x.io", 31), line("c@x.io", 200), line("d@x.io", 5)].join("\n");
+        let options = ImportOptions::new()
+            .ordered(false)
+            .upsert_by(["email"])
+            .validate(|contact: &Contact| match contact.age {
+                0..=150 => Ok(()),
+                _ => Err("age out of range".to_string()),
+            });
+        let report = import(&db, &input, ImportFormat::Ndjson, &options).await;
+        assert_eq!((report.inserted, report.updated), (1, 1));
+        assert_eq!(report.errors[0].to_string(), "line 2: age out of range");
+
+        let a = Contact::find_one(&db, doc! { "email": "a@x.io" })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(a.age, 31);
+        assert_eq!(Contact::count(&db, doc! {}).await.unwrap(), 3);
+
+        // 模型没有 `_id` 字段时保留记录中的 `_id`；更新已有文档时不修改 `_id`
+        let id = ObjectId::new();
+        let with_id = |email: &str| {
+            line(email, 40).replacen('{', &format!(r#"{{"_id":{{"$oid":"{id}"}},"#), 1)
+        };
+        let input = [with_id("e@x.io"), with_id("a@x.io")].join("\n");
+        let report = import(&db, &input, ImportFormat::Ndjson, &options).await;
+        assert_eq!((report.inserted, report.updated), (1, 1));
+        let stored = db.documents("contacts");
+        let id_of = |email: &str| {
+            let document = stored
+                .iter()
+                .find(|d| d.get_str("email").is_ok_and(|e| e == email));
+            document.unwrap().get_object_id("_id").unwrap()
+        };
+        assert_eq!(id_of("e@x.io"), id);
+        assert_ne!(id_of("a@x.io"), id);
+
+        // 导出的 NDJSON 可以原样导入
+        let mut exported = Vec::new();
+        let export = ExportOptions::new(ExportFormat::Ndjson);
+        Contact::export(&db, doc! {}, &ListQuery::default(), &export, &mut exported)
+            .await
+            .unwrap();
+        let copy = MemoryBackend::new();
+        let input = String::from_utf8(exported).unwrap();
+        let report = import(&copy, &input, ImportFormat::Ndjson, &ImportOptions::new()).await;
+        assert_eq!((report.inserted, report.errors.len()), (4, 0));
+    }
+}
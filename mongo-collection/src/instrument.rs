@@ -0,0 +1,338 @@
+//! 仓储操作埋点
+//!
+//! 启用 `tracing` feature 后，每个 `CollectionRepository` 方法都在一个 span 中执行，
+//! 字段遵循 OpenTelemetry 数据库语义约定（`db.system.name`、`db.collection.name`、
+//! `db.operation.name`、`db.query.text` 等）。
+//! 启用 `metrics` feature 后，每次操作都会通过 `metrics` 门面记录调用次数、耗时与错误数。
+//! 两者都未启用时 [`Operation`] 不做任何事。
+
+use crate::backend::CollectionTarget;
+use mongodb::bson::Document;
+use mongodb::options::FindOptions;
+use std::future::IntoFuture;
+
+/// 一次仓储操作的埋点上下文
+pub(crate) struct Operation {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "metrics")]
+    labels: [(&'static str, String); 2],
+}
+
+impl Operation {
+    /// 为目标集合上的操作创建埋点上下文
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(operation: &'static str, target: &CollectionTarget) -> Self {
+        #[cfg(feature = "tracing")]
+        let span = {
+            let span = tracing::info_span!(
+                "mongodb",
+                otel.name = %format_args!("{operation} {}", target.name),
+                otel.kind = "client",
+                otel.status_code = tracing::field::Empty,
+                db.system.name = "mongodb",
+                db.namespace = tracing::field::Empty,
+                db.collection.name = %target.name,
+                db.operation.name = operation,
+                db.query.text = tracing::field::Empty,
+                db.mongodb.sort = tracing::field::Empty,
+                db.mongodb.skip = tracing::field::Empty,
+                db.mongodb.limit = tracing::field::Empty,
+                db.response.returned_rows = tracing::field::Empty,
+                db.client.operation.duration = tracing::field::Empty,
+                error.type = tracing::field::Empty,
+            );
+            if let Some(ref database) = target.database {
+                span.record("db.namespace", database.as_ref());
+            }
+            span
+        };
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span,
+            #[cfg(feature = "metrics")]
+            labels: [
+                ("collection", target.name.to_string()),
+                ("operation", operation.to_string()),
+            ],
+        }
+    }
+
+    /// 记录过滤条件的结构（字段值替换为 `"?"`）
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn filter(self, filter: &Document) -> Self {
+        #[cfg(feature = "tracing")]
+        self.span
+            .record("db.query.text", filter_shape(filter).to_string());
+        self
+    }
+
+    /// 记录排序与 skip/limit
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn find_options(self, options: Option<&FindOptions>) -> Self {
+        #[cfg(feature = "tracing")]
+        if let Some(options) = options {
+            if let Some(ref sort) = options.sort {
+                self.span.record("db.mongodb.sort", sort.to_string());
+            }
+            if let Some(skip) = options.skip {
+                self.span.record("db.mongodb.skip", skip);
+            }
+            if let Some(limit) = options.limit {
+                self.span.record("db.mongodb.limit", limit);
+            }
+        }
+        self
+    }
+
+    /// 执行操作并记录返回文档数、耗时与错误类型
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) async fn run<T, F>(
+        self,
+        returned: fn(&T) -> u64,
+        future: F,
+    ) -> Result<T, mongodb::error::Error>
+    where
+        F: IntoFuture<Output = Result<T, mongodb::error::Error>>,
+    {
+        #[cfg(any(feature = "tracing", feature = "metrics"))]
+        {
+            let start = std::time::Instant::now();
+            #[cfg(feature = "tracing")]
+            let future = tracing::Instrument::instrument(future.into_future(), self.span.clone());
+            let result = future.await;
+            let elapsed = start.elapsed();
+
+            #[cfg(feature = "tracing")]
+            self.record_span(&result, returned, elapsed);
+            #[cfg(feature = "metrics")]
+            self.record_metrics(&result, elapsed);
+            result
+        }
+        #[cfg(not(any(feature = "tracing", feature = "metrics")))]
+        future.await
+    }
+
+    #[cfg(feature = "tracing")]
+    fn record_span<T>(
+        &self,
+        result: &Result<T, mongodb::error::Error>,
+        returned: fn(&T) -> u64,
+        elapsed: std::time::Duration,
+    ) {
+        self.span
+            .record("db.client.operation.duration", elapsed.as_secs_f64());
+        match result {
+            Ok(value) => {
+                self.span
+                    .record("db.response.returned_rows", returned(value));
+            }
+            Err(e) => {
+                self.span.record("otel.status_code", "ERROR");
+                self.span.record("error.type", error_kind(e));
+            }
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_metrics<T>(
+        &self,
+        result: &Result<T, mongodb::error::Error>,
+        elapsed: std::time::Duration,
+    ) {
+        use crate::metrics::{OPERATION_DURATION, OPERATION_ERRORS, OPERATIONS};
+
+        metrics::counter!(OPERATIONS, &self.labels).increment(1);
+        metrics::histogram!(OPERATION_DURATION, &self.labels).record(elapsed.as_secs_f64());
+        if let Err(e) = result {
+            let [collection, operation] = self.labels.clone();
+            metrics::counter!(
+                OPERATION_ERRORS,
+                &[collection, operation, ("kind", error_kind(e).to_string())]
+            )
+            .increment(1);
+        }
+    }
+}
+
+/// 返回文档数：单个文档
+pub(crate) fn one<T>(_: &T) -> u64 {
+    1
+}
+
+/// 返回文档数：文档列表
+// 需要与 `fn(&Vec<T>) -> u64` 匹配，不能改为切片
+#[allow(clippy::ptr_arg)]
+pub(crate) fn len<T>(items: &Vec<T>) -> u64 {
+    items.len() as u64
+}
+
+/// 返回文档数：可能不存在的文档
+pub(crate) fn some<T>(item: &Option<T>) -> u64 {
+    item.is_some() as u64
+}
+
+/// 返回文档数：是否命中
+pub(crate) fn hit(hit: &bool) -> u64 {
+    *hit as u64
+}
+
+/// 返回文档数：受影响的文档数
+pub(crate) fn count(count: &u64) -> u64 {
+    *count
+}
+
+/// 返回文档数：不返回文档
+pub(crate) fn none<T>(_: &T) -> u64 {
+    0
+}
+
+/// 错误类型的低基数名称，对应 `ErrorKind` 的变体
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub(crate) fn error_kind(error: &mongodb::error::Error) -> &'static str {
+    use mongodb::error::ErrorKind;
+
+    match *error.kind {
+        ErrorKind::InvalidArgument { .. } => "InvalidArgument",
+        ErrorKind::Authentication { .. } => "Authentication",
+        ErrorKind::BsonDeserialization(_) => "BsonDeserialization",
+        ErrorKind::BsonSerialization(_) => "BsonSerialization",
+        ErrorKind::Bson(_) => "Bson",
+        ErrorKind::InsertMany(_) => "InsertMany",
+        ErrorKind::BulkWrite(_) => "BulkWrite",
+        ErrorKind::Command(_) => "Command",
+        ErrorKind::DnsResolve { .. } => "DnsResolve",
+        ErrorKind::Internal { .. } => "Internal",
+        ErrorKind::Io(_) => "Io",
+        ErrorKind::ConnectionPoolCleared { .. } => "ConnectionPoolCleared",
+        ErrorKind::InvalidResponse { .. } => "InvalidResponse",
+        ErrorKind::ServerSelection { .. } => "ServerSelection",
+        ErrorKind::SessionsNotSupported => "SessionsNotSupported",
+        ErrorKind::Write(_) => "Write",
+        ErrorKind::Transaction { .. } => "Transaction",
+        ErrorKind::IncompatibleServer { .. } => "IncompatibleServer",
+        ErrorKind::MissingResumeToken => "MissingResumeToken",
+        ErrorKind::Custom(_) => "Custom",
+        ErrorKind::Shutdown => "Shutdown",
+        _ => "Other",
+    }
+}
+
+/// 过滤条件的结构：保留字段名与操作符，字段值替换为 `"?"`
+#[cfg(any(feature = "tracing", test))]
+pub(crate) fn filter_shape(filter: &Document) -> Document {
+    use mongodb::bson::Bson;
+
+    fn shape(value: &Bson) -> Bson {
+        match value {
+            Bson::Document(document) => Bson::Document(filter_shape(document)),
+            // `$and` / `$or` 等逻辑操作符的子条件
+            Bson::Array(items) if items.iter().all(|item| item.as_document().is_some()) => {
+                Bson::Array(items.iter().map(shape).collect())
+            }
+            _ => Bson::String("?".to_string()),
+        }
+    }
+
+    filter
+        .iter()
+        .map(|(key, value)| (key.clone(), shape(value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn test_filter_shape_hides_values() {
+        let filter = doc! {
+            "email": "alice@example.com",
+            "age": { "$gte": 18, "$in": [18, 19] },
+            "$or": [{ "status": "active" }, { "vip": true }],
+        };
+        assert_eq!(
+            filter_shape(&filter),
+            doc! {
+                "email": "?",
+                "age": { "$gte": "?", "$in": "?" },
+                "$or": [{ "status": "?" }, { "vip": "?" }],
+            }
+        );
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod metrics_tests {
+    use crate::metrics::{OPERATION_ERRORS, OPERATIONS};
+    use crate::{Collection, CollectionRepository, MemoryBackend};
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use mongodb::bson::doc;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Event {
+        name: String,
+    }
+
+    #[test]
+    fn test_records_operations_and_errors() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            futures::executor::block_on(async {
+                let backend = MemoryBackend::new();
+                let event = Event {
+                    name: "signup".to_string(),
+                };
+                Event::create(&backend, &event).await.unwrap();
+                Event::count(&backend, doc! {}).await.unwrap();
+                // 未知操作符返回错误
+                let filter = doc! { "name": { "$bogus": 1 } };
+                assert!(Event::count(&backend, filter).await.is_err());
+            })
+        });
+
+        // (指标名, operation 标签, kind 标签) -> 计数
+        let mut counters = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter_map(|(key, _, _, value)| {
+                let DebugValue::Counter(value) = value else {
+                    return None;
+                };
+                let label = |name: &str| {
+                    key.key()
+                        .labels()
+                        .find(|label| label.key() == name)
+                        .map(|label| label.value().to_string())
+                };
+                Some((
+                    key.key().name().to_string(),
+                    label("operation")?,
+                    label("kind"),
+                    value,
+                ))
+            })
+            .collect::<Vec<_>>();
+        counters.sort();
+
+        assert_eq!(
+            counters,
+            vec![
+                (
+                    OPERATION_ERRORS.to_string(),
+                    "count".to_string(),
+                    Some("Custom".to_string()),
+                    1
+                ),
+                (OPERATIONS.to_string(), "count".to_string(), None, 2),
+                (OPERATIONS.to_string(), "create".to_string(), None, 1),
+            ]
+        );
+    }
+}
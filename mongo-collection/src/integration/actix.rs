@@ -0,0 +1,147 @@
+//! actix-web 提取器与响应
+//!
+//! 查询参数无效时返回 `400 Bad Request`；`PaginatedData` 与 `ListData` 以 JSON 响应，
+//! 并附带 `X-Total-Count` 头，分页数据还附带 RFC 8288 `Link` 头；仓储方法的错误经
+//! [`ApiError`] 转换为响应
+
+use crate::{
+    ApiError, DialectQuery, ListData, ListQuery, PaginatedData, PaginatedQuery, PaginationLinks,
+    QueryDialect, QueryError,
+};
+use actix_web::body::BoxBody;
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::http::header::LINK;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, Responder, ResponseError};
+use serde::Serialize;
+use std::future::{Ready, ready};
+
+/// 总记录数响应头
+const TOTAL_COUNT: &str = "X-Total-Count";
+
+impl FromRequest for PaginatedQuery {
+    type Error = QueryError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(PaginatedQuery::from_query_str(req.query_string()).and_then(PaginatedQuery::validate))
+    }
+}
+
+impl FromRequest for ListQuery {
+    type Error = QueryError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(ListQuery::from_query_str(req.query_string()))
+    }
+}
+
+impl<D: QueryDialect + Default> FromRequest for DialectQuery<D> {
+    type Error = QueryError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(DialectQuery::from_query_str(req.query_string()))
+    }
+}
+
+impl ResponseError for QueryError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self.query_error() {
+            Some(_) => StatusCode::BAD_REQUEST,
+            None => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl<T: Serialize> Responder for PaginatedData<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let link = PaginationLinks::new(req.path())
+            .raw_query(req.query_string())
+            .build(&self)
+            .link_header();
+        HttpResponse::Ok()
+            .insert_header((TOTAL_COUNT, self.total_count))
+            .insert_header((LINK, link))
+            .json(self)
+    }
+}
+
+impl<T: Serialize> Responder for ListData<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+        HttpResponse::Ok()
+            .insert_header((TOTAL_COUNT, self.total_count))
+            .json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[tokio::test]
+    async fn test_extract_queries() {
+        let req = TestRequest::with_uri("/users?page=2&filters.role=admin").to_http_request();
+        let query = PaginatedQuery::extract(&req).await.unwrap();
+        assert_eq!(query.page, 2);
+        assert_eq!(query.parsed_filters().unwrap()["role"], "admin");
+
+        let req = TestRequest::with_uri("/users?$top=5&$skip=10").to_http_request();
+        let query = DialectQuery::<crate::ODataDialect>::extract(&req)
+            .await
+            .unwrap();
+        assert_eq!((query.page, query.page_size), (3, 5));
+
+        let req = TestRequest::with_uri("/users?sort_order=sideways").to_http_request();
+        let error = ListQuery::extract(&req).await.unwrap_err();
+        assert_eq!(error.error_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_repository_error_status() {
+        let error = QueryError::OutOfRange {
+            field: "page_size".to_string(),
+            value: 80,
+            min: 1,
+            max: 50,
+        };
+        let error = ApiError::from(mongodb::error::Error::custom(error));
+        assert_eq!(error.error_response().status(), StatusCode::BAD_REQUEST);
+
+        let error = ApiError::from(crate::utils::invalid_argument("connection lost"));
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_paginated_response_headers() {
+        let req = TestRequest::with_uri("/users?page=2&page_size=1").to_http_request();
+        let query = PaginatedQuery::from_query_str(req.query_string()).unwrap();
+        let data = PaginatedData::new(vec!["b"], 3, &query);
+        let response = data.respond_to(&req);
+        let headers = response.headers();
+        assert_eq!(headers.get(TOTAL_COUNT).unwrap(), "3");
+        let link = headers.get(LINK).unwrap().to_str().unwrap();
+        assert!(link.contains("</users?page_size=1&page=3>; rel=\"next\""));
+        assert!(link.contains("</users?page_size=1&page=1>; rel=\"prev\""));
+
+        // 链接沿用请求的方言
+        let req = TestRequest::with_uri("/users?page[number]=2&page[size]=1").to_http_request();
+        let query =
+            DialectQuery::<crate::JsonApiDialect>::from_query_str(req.query_string()).unwrap();
+        let response = PaginatedData::new(vec!["b"], 3, &query).respond_to(&req);
+        let link = response.headers().get(LINK).unwrap().to_str().unwrap();
+        assert!(link.contains("</users?page%5Bsize%5D=1&page%5Bnumber%5D=3>; rel=\"next\""));
+    }
+}
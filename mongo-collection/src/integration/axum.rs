@@ -0,0 +1,102 @@
+//! axum 提取器
+//!
+//! 查询参数无效时返回 `400 Bad Request`，响应体为错误描述；仓储方法的错误经
+//! [`ApiError`] 转换为响应
+
+use crate::{ApiError, DialectQuery, ListQuery, PaginatedQuery, QueryDialect, QueryError};
+use axum::extract::FromRequestParts;
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+
+impl<S: Send + Sync> FromRequestParts<S> for PaginatedQuery {
+    type Rejection = QueryError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        PaginatedQuery::from_query_str(parts.uri.query().unwrap_or_default())?.validate()
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ListQuery {
+    type Rejection = QueryError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        ListQuery::from_query_str(parts.uri.query().unwrap_or_default())
+    }
+}
+
+impl<S, D> FromRequestParts<S> for DialectQuery<D>
+where
+    S: Send + Sync,
+    D: QueryDialect + Default,
+{
+    type Rejection = QueryError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        DialectQuery::from_query_str(parts.uri.query().unwrap_or_default())
+    }
+}
+
+impl IntoResponse for QueryError {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.query_error() {
+            Some(_) => StatusCode::BAD_REQUEST,
+            None => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn extract<T: FromRequestParts<()>>(uri: &str) -> Result<T, T::Rejection> {
+        let (mut parts, _) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+        T::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_extract_queries() {
+        let query: PaginatedQuery = extract("/users?page=3&page_size=5&filters[role]=admin")
+            .await
+            .unwrap();
+        assert_eq!((query.page, query.page_size), (3, 5));
+        assert_eq!(query.parsed_filters().unwrap()["role"], "admin");
+
+        let query: ListQuery = extract("/users").await.unwrap();
+        assert!(query.filters.is_none());
+
+        let query: DialectQuery<crate::JsonApiDialect> =
+            extract("/users?page[number]=2").await.unwrap();
+        assert_eq!(query.page, 2);
+
+        let rejection = extract::<PaginatedQuery>("/users?page_size=0")
+            .await
+            .unwrap_err();
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_repository_error_response() {
+        let error = QueryError::OutOfRange {
+            field: "page_size".to_string(),
+            value: 80,
+            min: 1,
+            max: 50,
+        };
+        let response = ApiError::from(mongodb::error::Error::custom(error)).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let error = crate::utils::invalid_argument("connection lost");
+        let response = ApiError::from(error).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,64 @@
+//! Web 框架集成
+//!
+//! 为 [`PaginatedQuery`](crate::PaginatedQuery) 与 [`ListQuery`](crate::ListQuery)
+//! 实现各框架的提取器，查询字符串解析见 [`query_string`](crate::query_string)
+
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "axum")]
+mod axum;
+
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub use error::ApiError;
+
+#[cfg(any(feature = "axum", feature = "actix"))]
+mod error {
+    use crate::QueryError;
+    use std::fmt;
+
+    /// 仓储方法错误的 HTTP 响应
+    ///
+    /// 由 `mongodb::error::Error` 转换而来：其中携带 [`QueryError`]（如 `find_paginated`
+    /// 按模型限制校验失败）时返回 `400 Bad Request`，其余返回 `500 Internal Server Error`
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// async fn list_users(
+    ///     State(db): State<Database>,
+    ///     query: PaginatedQuery,
+    /// ) -> Result<Json<PaginatedData<User>>, ApiError> {
+    ///     Ok(Json(User::find_paginated(&db, doc! {}, &query).await?))
+    /// }
+    /// ```
+    #[derive(Debug)]
+    pub struct ApiError(pub mongodb::error::Error);
+
+    impl ApiError {
+        /// 错误中携带的查询参数错误
+        pub fn query_error(&self) -> Option<&QueryError> {
+            self.0.get_custom::<QueryError>()
+        }
+    }
+
+    impl From<mongodb::error::Error> for ApiError {
+        fn from(error: mongodb::error::Error) -> Self {
+            Self(error)
+        }
+    }
+
+    impl fmt::Display for ApiError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.query_error() {
+                Some(error) => error.fmt(f),
+                None => self.0.fmt(f),
+            }
+        }
+    }
+
+    impl std::error::Error for ApiError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+}
@@ -1,22 +1,47 @@
+// 让派生宏生成的 `::mongo_collection::...` 路径在本 crate 内部同样可用
+extern crate self as mongo_collection;
+
 pub use mongo_collection_macro::Collection;
 
+pub mod backend;
+#[cfg(feature = "cli")]
+pub mod cli;
 mod collection;
-pub use collection::Collection;
+pub use collection::{Collection, FieldInfo, NameContext};
 
+mod dialect;
+mod explain;
+mod export;
+mod expression;
+mod facet;
+mod import;
+mod instrument;
+mod integration;
+mod links;
 mod list;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod migration;
+pub mod model;
+pub mod outbox;
 mod paginated;
+pub mod query_string;
+pub mod registry;
 pub mod repository;
+mod sort;
 mod utils;
+pub mod validator;
+pub mod watch;
 
-pub use mongo_collection_macro::CollectionRepository;
+pub use mongo_collection_macro::{CollectionRepository, DocumentSchema};
 
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "openapi")]
-use utoipa::{IntoParams, ToSchema};
+use utoipa::ToSchema;
 
 /// 排序方向
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum SortOrder {
@@ -27,6 +52,35 @@ pub enum SortOrder {
     Asc,
 }
 
+pub use crate::backend::{
+    Backend, BackendExt, ClientBackend, MemoryBackend, SlowQuery, SlowQueryOptions, TenantScoped,
+    WithNameContext,
+};
+pub use crate::dialect::{
+    DefaultDialect, DialectQuery, JsonApiDialect, ODataDialect, QueryDialect,
+};
+pub use crate::explain::QueryPlan;
+pub use crate::export::{ExportFormat, ExportOptions};
+pub use crate::expression::FilterLanguage;
+pub use crate::facet::FacetBucket;
+pub use crate::import::{ImportFormat, ImportLineError, ImportOptions, ImportReport};
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub use crate::integration::ApiError;
+pub use crate::links::{HalLinks, PageLinks, PaginationLinks};
 pub use crate::list::{ListData, ListQuery};
-pub use crate::paginated::{PaginatedData, PaginatedQuery};
-pub use crate::repository::CollectionRepository;
+pub use crate::migration::{Migration, MigrationError, MigrationStatus, Migrator};
+pub use crate::model::{ModelDiff, ModelInfo};
+pub use crate::outbox::{Outbox, OutboxEvent, OutboxMessage, OutboxRepository};
+pub use crate::paginated::{PageSizeLimits, PaginatedData, PaginatedQuery};
+pub use crate::query_string::QueryError;
+pub use crate::repository::{CollectionRepository, MongoRepository, Repository};
+pub use crate::sort::SortSpec;
+pub use crate::validator::{DocumentSchema, ValidatorOptions};
+pub use crate::watch::{ChangeEvent, CollectionTokenStore, ResumeTokenStore, WatchOptions};
+
+/// 派生宏生成代码所依赖的内部路径，不属于公开 API
+#[doc(hidden)]
+pub mod __private {
+    pub use inventory;
+    pub use mongodb;
+}
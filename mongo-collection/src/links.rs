@@ -0,0 +1,340 @@
+//! 分页链接
+//!
+//! 根据 [`PaginatedData`] 生成 `self`、`first`、`prev`、`next`、`last` 链接，
+//! 可输出为 RFC 8288 `Link` 头、HAL `_links` 或 JSON:API `links` 对象
+
+use crate::{PaginatedData, PaginatedQuery, SortOrder};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+/// 分页链接构建器
+///
+/// # 示例
+///
+/// ```ignore
+/// let links = PaginationLinks::new("https://api.example.com/users")
+///     .query(&query)
+///     .build(&page);
+///
+/// response.insert_header(("Link", links.link_header()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PaginationLinks {
+    base_url: String,
+    /// 除页码外的已编码查询参数
+    params: String,
+    /// 页码参数的写法
+    page_param: PageParam,
+}
+
+/// 链接中页码参数的写法，与解析请求的方言对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageParam {
+    /// `page=N`，见 [`DefaultDialect`](crate::DefaultDialect)
+    Page,
+    /// `page[number]=N`，见 [`JsonApiDialect`](crate::JsonApiDialect)
+    JsonApi,
+    /// `$skip=(N-1)*page_size`，见 [`ODataDialect`](crate::ODataDialect)
+    OData,
+}
+
+impl PageParam {
+    /// 按请求中的参数名推断方言；没有方言特有的参数时使用默认方言
+    fn detect<'a>(mut keys: impl Iterator<Item = &'a str>) -> Self {
+        keys.find_map(|key| {
+            if key.starts_with("page[") {
+                Some(Self::JsonApi)
+            } else if key.starts_with('$') {
+                Some(Self::OData)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(Self::Page)
+    }
+
+    /// 指定页码的参数名
+    fn key(self) -> &'static str {
+        match self {
+            Self::Page => "page",
+            Self::JsonApi => "page[number]",
+            Self::OData => "$skip",
+        }
+    }
+
+    /// 指定页码的参数值
+    fn value(self, page: u64, page_size: u64) -> u64 {
+        match self {
+            Self::Page | Self::JsonApi => page,
+            Self::OData => page.saturating_sub(1) * page_size,
+        }
+    }
+}
+
+impl PaginationLinks {
+    /// 以 `base_url` 为资源地址创建构建器，地址中已有的查询参数会保留
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            params: String::new(),
+            page_param: PageParam::Page,
+        }
+    }
+
+    /// 在链接中保留 `query` 的每页数量、排序、搜索、筛选与分面参数
+    ///
+    /// 筛选参数按键名排序，保证同一查询生成的链接稳定
+    pub fn query(mut self, query: &PaginatedQuery) -> Self {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        serializer.append_pair("page_size", &query.page_size.to_string());
+        if let Some(ref sort_by) = query.sort_by {
+            serializer.append_pair("sort_by", sort_by);
+            serializer.append_pair(
+                "sort_order",
+                match query.sort_order {
+                    SortOrder::Asc => "asc",
+                    SortOrder::Desc => "desc",
+                },
+            );
+        }
+        if !query.sort.is_empty() {
+            let sort: Vec<_> = query.sort.iter().map(ToString::to_string).collect();
+            serializer.append_pair("sort", &sort.join(","));
+        }
+        if let Some(ref search) = query.search {
+            serializer.append_pair("search", search);
+        }
+        if let Some(ref q) = query.q {
+            serializer.append_pair("q", q);
+        }
+        if !query.facets.is_empty() {
+            serializer.append_pair("facets", &query.facets.join(","));
+        }
+        if let Some(ref filters) = query.filters {
+            let mut filters: Vec<_> = filters.iter().collect();
+            filters.sort();
+            serializer.extend_pairs(filters);
+        }
+        self.params = serializer.finish();
+        self.page_param = PageParam::Page;
+        self
+    }
+
+    /// 在链接中原样保留请求的查询字符串，只替换页码
+    ///
+    /// 按参数名推断请求使用的方言（JSON:API 的 `page[...]`、OData 的 `$...`），
+    /// 页码按同一方言写回；请求中没有方言特有的参数时使用默认方言的 `page`
+    #[cfg_attr(not(feature = "actix"), allow(dead_code))]
+    pub(crate) fn raw_query(mut self, query: &str) -> Self {
+        let pairs: Vec<_> =
+            form_urlencoded::parse(query.trim_start_matches('?').as_bytes()).collect();
+        let page_param = PageParam::detect(pairs.iter().map(|(key, _)| key.as_ref()));
+        self.params = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs.iter().filter(|(key, _)| key != page_param.key()))
+            .finish();
+        self.page_param = page_param;
+        self
+    }
+
+    /// 生成 `data` 对应的链接
+    pub fn build<T>(&self, data: &PaginatedData<T>) -> PageLinks {
+        let url = |page| self.url(page, data.page_size);
+        PageLinks {
+            current: url(data.page),
+            first: url(1),
+            prev: data.prev_page.map(url),
+            next: data.next_page.map(url),
+            last: url(data.total_pages.max(1)),
+        }
+    }
+
+    fn url(&self, page: u64, page_size: u64) -> String {
+        let separator = if self.base_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        let key: String =
+            form_urlencoded::byte_serialize(self.page_param.key().as_bytes()).collect();
+        let value = self.page_param.value(page, page_size);
+        if self.params.is_empty() {
+            format!("{}{separator}{key}={value}", self.base_url)
+        } else {
+            format!("{}{separator}{}&{key}={value}", self.base_url, self.params)
+        }
+    }
+}
+
+/// 一页数据的翻页链接
+///
+/// 序列化结果即 JSON:API 的 `links` 对象，不存在的上一页/下一页为 `null`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageLinks {
+    /// 当前页
+    #[serde(rename = "self")]
+    pub current: String,
+    /// 第一页
+    pub first: String,
+    /// 上一页
+    pub prev: Option<String>,
+    /// 下一页
+    pub next: Option<String>,
+    /// 最后一页
+    pub last: String,
+}
+
+impl PageLinks {
+    /// RFC 8288 `Link` 头的值，如 `<...?page=1>; rel="first", <...?page=3>; rel="next"`
+    pub fn link_header(&self) -> String {
+        self.relations()
+            .filter(|(rel, _)| *rel != "self")
+            .map(|(rel, url)| format!("<{url}>; rel=\"{rel}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// HAL 风格的 `_links` 对象：`{ "self": { "href": ... }, "next": { "href": ... } }`
+    pub fn hal(&self) -> HalLinks<'_> {
+        HalLinks(self)
+    }
+
+    fn relations(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("self", Some(&self.current)),
+            ("first", Some(&self.first)),
+            ("prev", self.prev.as_ref()),
+            ("next", self.next.as_ref()),
+            ("last", Some(&self.last)),
+        ]
+        .into_iter()
+        .filter_map(|(rel, url)| Some((rel, url?.as_str())))
+    }
+}
+
+/// HAL 风格的链接，由 [`PageLinks::hal`] 创建，省略不存在的上一页/下一页
+#[derive(Debug, Clone, Copy)]
+pub struct HalLinks<'a>(&'a PageLinks);
+
+impl Serialize for HalLinks<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Href<'a> {
+            href: &'a str,
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        for (rel, href) in self.0.relations() {
+            map.serialize_entry(rel, &Href { href })?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, serialize_to_document};
+    use std::collections::HashMap;
+
+    fn page(page: u64, total_count: u64, query: &PaginatedQuery) -> PaginatedData<()> {
+        let query = PaginatedQuery {
+            page,
+            ..query.clone()
+        };
+        PaginatedData::new(Vec::new(), total_count, &query)
+    }
+
+    #[test]
+    fn test_navigation_metadata() {
+        let query = PaginatedQuery {
+            page_size: 10,
+            ..Default::default()
+        };
+
+        let data = page(2, 25, &query);
+        assert_eq!(data.total_pages, 3);
+        assert!(data.has_next && data.has_prev);
+        assert_eq!((data.prev_page, data.next_page), (Some(1), Some(3)));
+
+        let data = page(1, 0, &query);
+        assert_eq!(data.total_pages, 0);
+        assert!(!data.has_next && !data.has_prev);
+
+        // 超出末页
+        let data = page(7, 25, &query);
+        assert_eq!((data.prev_page, data.next_page), (Some(3), None));
+    }
+
+    #[test]
+    fn test_links_keep_query_parameters() {
+        let query = PaginatedQuery {
+            page_size: 10,
+            sort_by: Some("name".to_string()),
+            sort_order: SortOrder::Asc,
+            filters: Some(HashMap::from([(
+                "filters[status]".to_string(),
+                "open".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let links = page(2, 25, &query).links("/users", &query);
+
+        let prefix = "/users?page_size=10&sort_by=name&sort_order=asc&filters%5Bstatus%5D=open";
+        assert_eq!(links.current, format!("{prefix}&page=2"));
+        assert_eq!(links.prev, Some(format!("{prefix}&page=1")));
+        assert_eq!(links.next, Some(format!("{prefix}&page=3")));
+        assert_eq!(
+            links.link_header(),
+            format!(
+                "<{prefix}&page=1>; rel=\"first\", <{prefix}&page=1>; rel=\"prev\", \
+                 <{prefix}&page=3>; rel=\"next\", <{prefix}&page=3>; rel=\"last\""
+            )
+        );
+    }
+
+    #[test]
+    fn test_hal_and_json_api_links() {
+        let data = page(1, 5, &PaginatedQuery::default());
+        let links = PaginationLinks::new("/users?lang=en")
+            .raw_query("page=1&filters.role=admin")
+            .build(&data);
+        assert_eq!(links.last, "/users?lang=en&filters.role=admin&page=1");
+
+        let json_api = serialize_to_document(&links).unwrap();
+        assert_eq!(json_api.get("prev"), Some(&mongodb::bson::Bson::Null));
+        assert_eq!(json_api.get_str("self").unwrap(), links.current);
+
+        let hal = serialize_to_document(&links.hal()).unwrap();
+        assert_eq!(hal.keys().collect::<Vec<_>>(), ["self", "first", "last"]);
+        assert_eq!(
+            hal.get_document("first").unwrap(),
+            &doc! { "href": &links.first }
+        );
+    }
+
+    #[test]
+    fn test_raw_query_keeps_dialect() {
+        let query = PaginatedQuery {
+            page_size: 10,
+            ..Default::default()
+        };
+        let data = page(2, 25, &query);
+
+        let links = PaginationLinks::new("/users")
+            .raw_query("page[number]=2&page[size]=10&sort=-name")
+            .build(&data);
+        assert_eq!(
+            links.next.as_deref(),
+            Some("/users?page%5Bsize%5D=10&sort=-name&page%5Bnumber%5D=3")
+        );
+
+        let links = PaginationLinks::new("/users")
+            .raw_query("$top=10&$skip=10&$orderby=name")
+            .build(&data);
+        assert_eq!(links.first, "/users?%24top=10&%24orderby=name&%24skip=0");
+        assert_eq!(
+            links.next.as_deref(),
+            Some("/users?%24top=10&%24orderby=name&%24skip=20")
+        );
+    }
+}
@@ -2,7 +2,10 @@ use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 use std::collections::HashMap;
 
-use crate::SortOrder;
+use crate::query_string::{self, QueryError};
+use crate::sort::{self, SortSpec};
+use crate::{Collection, FilterLanguage, SortOrder};
+use mongodb::bson::Document;
 
 #[cfg(feature = "openapi")]
 use utoipa::{IntoParams, ToSchema};
@@ -17,30 +20,69 @@ pub struct ListQuery {
     /// 排序方向
     #[default(_code = "SortOrder::Desc")]
     pub sort_order: SortOrder,
+    /// 多字段排序，如 `sort=status,-created_at`，指定时优先于 `sort_by`
+    #[serde(deserialize_with = "crate::sort::deserialize_sort")]
+    pub sort: Vec<SortSpec>,
     /// 全局搜索关键词
     pub search: Option<String>,
+    /// 筛选表达式，如 `status:active AND age>=18`，见 [`FilterLanguage`](crate::FilterLanguage)
+    pub q: Option<String>,
     /// 字段级筛选
     #[serde(flatten)]
     pub filters: Option<HashMap<String, String>>,
 }
 
-impl ListQuery{
-    pub fn parsed_filters(&self)->Option<HashMap<String, String>>{
-        if let Some(ref raw_filters) = self.filters{
-            let mut filters = HashMap::new();
-            for (key, value) in raw_filters {
-                // 解析 filters[key] 格式
-                if let Some(k) = key.strip_prefix("filters[").and_then(|s| s.strip_suffix("]")) {
-                    filters.insert(k.to_string(), value.to_string());
+impl ListQuery {
+    /// 解析 `filters[key]`、`filters[a][b]` 与 `filters.key` 格式的筛选参数
+    ///
+    /// 嵌套的键名转换为点号路径，如 `filters[author][name]` 对应 `author.name`
+    pub fn parsed_filters(&self) -> Option<HashMap<String, String>> {
+        query_string::parse_filters(&self.filters)
+    }
+
+    /// 生成排序文档
+    ///
+    /// `sort` 优先于 `sort_by`，未指定时按 `_id` 降序；
+    /// 未包含 `_id` 时追加 `_id` 作为最后的排序字段，保证结果顺序稳定
+    pub fn sort_document(&self) -> Document {
+        sort::sort_document(&self.sort, self.sort_by.as_deref(), &self.sort_order)
+    }
+
+    /// 按模型 `T` 的字段解析 `q` 筛选表达式，未提供时返回空文档
+    ///
+    /// 使用 [`FilterLanguage::for_collection`]：允许 `T::json_schema()` 中的全部字段，
+    /// 标记了 `#[collection(skip_filter)]` 的字段除外。`q` 来自请求参数，
+    /// 密码哈希等不应被探测的字段必须标记 `skip_filter`；
+    /// 需要显式的允许列表时直接使用 [`FilterLanguage`]
+    pub fn q_filter<T: Collection>(&self) -> Result<Document, QueryError> {
+        match self.q {
+            Some(ref q) => FilterLanguage::for_collection::<T>().parse(q),
+            None => Ok(Document::new()),
+        }
+    }
+
+    /// 从 URL 查询字符串解析列表参数
+    ///
+    /// 空值视为未提供，未识别的参数保存在 `filters` 中
+    pub fn from_query_str(query: &str) -> Result<Self, QueryError> {
+        let mut parsed = Self::default();
+        let mut extra = HashMap::new();
+        for (key, value) in query_string::pairs(query) {
+            match key.as_ref() {
+                "sort_by" => parsed.sort_by = Some(value.into_owned()),
+                "sort_order" => {
+                    parsed.sort_order = query_string::parse_sort_order("sort_order", &value)?
                 }
-                // 解析 filters.key 格式
-                else if let Some(k) = key.strip_prefix("filters.") {
-                    filters.insert(k.to_string(), value.to_string());
+                "sort" => parsed.sort = SortSpec::parse_list(&value)?,
+                "search" => parsed.search = Some(value.into_owned()),
+                "q" => parsed.q = Some(value.into_owned()),
+                _ => {
+                    extra.insert(key.into_owned(), value.into_owned());
                 }
             }
-            return Some(filters)
         }
-        None
+        parsed.filters = query_string::raw_filters(extra);
+        Ok(parsed)
     }
 }
 
@@ -0,0 +1,38 @@
+//! 仓储操作指标
+//!
+//! 启用 `metrics` feature 后，每个 `CollectionRepository` 方法都会通过
+//! [`metrics`](https://docs.rs/metrics) 门面记录以下指标，标签为 `collection` 与 `operation`：
+//!
+//! | 指标 | 类型 | 说明 |
+//! |------|------|------|
+//! | [`OPERATIONS`] | counter | 操作次数 |
+//! | [`OPERATION_DURATION`] | histogram | 操作耗时（秒） |
+//! | [`OPERATION_ERRORS`] | counter | 失败次数，额外带有 `kind` 标签（`ErrorKind` 的变体名） |
+//!
+//! 指标由应用安装的 recorder（如 `metrics-exporter-prometheus`）导出。
+//! `find_paginated` 等组合方法还会记录其内部调用的 `count` 与 `find_many`。
+
+/// 操作次数
+pub const OPERATIONS: &str = "mongo_collection_operations_total";
+
+/// 操作耗时（秒）
+pub const OPERATION_DURATION: &str = "mongo_collection_operation_duration_seconds";
+
+/// 失败次数
+pub const OPERATION_ERRORS: &str = "mongo_collection_operation_errors_total";
+
+/// 向当前 recorder 注册指标的单位与说明
+///
+/// 应在安装 recorder 之后调用一次
+pub fn describe() {
+    ::metrics::describe_counter!(OPERATIONS, "Number of repository operations");
+    ::metrics::describe_histogram!(
+        OPERATION_DURATION,
+        ::metrics::Unit::Seconds,
+        "Duration of repository operations"
+    );
+    ::metrics::describe_counter!(
+        OPERATION_ERRORS,
+        "Number of failed repository operations by error kind"
+    );
+}
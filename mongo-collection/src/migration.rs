@@ -0,0 +1,727 @@
+//! 集合迁移
+//!
+//! 按版本号顺序执行的迁移，每个迁移包含 `up` 与可选的 `down` 异步函数。
+//! 已执行的迁移记录在 `migrations` 集合中；执行或回滚期间在同一集合中持有一个
+//! 锁文档，防止多个进程同时迁移。锁是租约：每个迁移开始前与写入记录前都会续期并
+//! 确认仍由当前进程持有。
+//!
+//! 迁移函数不在事务中执行：`up` 失败时已执行的迁移保留记录，失败的迁移不会被记录，
+//! 修复后重新执行即可。
+
+use crate::backend::{Backend, CollectionTarget};
+use futures::future::BoxFuture;
+use mongodb::Database;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{DateTime, Document, doc};
+use mongodb::error::Error;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 锁文档的 `_id`
+const LOCK_ID: &str = "lock";
+
+type MigrationFn = Arc<dyn Fn(Database) -> BoxFuture<'static, Result<(), Error>> + Send + Sync>;
+
+/// 单个迁移
+///
+/// # 示例
+///
+/// ```ignore
+/// let migration = Migration::new(20261018, "rename_user_name")
+///     .up(|db| async move {
+///         db.collection::<Document>("users")
+///             .update_many(doc! {}, doc! { "$rename": { "name": "full_name" } })
+///             .await?;
+///         Ok(())
+///     })
+///     .down(|db| async move {
+///         db.collection::<Document>("users")
+///             .update_many(doc! {}, doc! { "$rename": { "full_name": "name" } })
+///             .await?;
+///         Ok(())
+///     });
+/// ```
+#[derive(Clone)]
+pub struct Migration {
+    version: u64,
+    name: String,
+    up: Option<MigrationFn>,
+    down: Option<MigrationFn>,
+}
+
+impl Migration {
+    /// 创建迁移，版本号决定执行顺序（可以使用日期或时间戳）
+    pub fn new(version: u64, name: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: None,
+            down: None,
+        }
+    }
+
+    /// 设置执行迁移的函数
+    pub fn up<F, Fut>(mut self, up: F) -> Self
+    where
+        F: Fn(Database) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        self.up = Some(Arc::new(move |db| Box::pin(up(db))));
+        self
+    }
+
+    /// 设置回滚迁移的函数，未设置时迁移不可回滚
+    pub fn down<F, Fut>(mut self, down: F) -> Self
+    where
+        F: Fn(Database) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        self.down = Some(Arc::new(move |db| Box::pin(down(db))));
+        self
+    }
+
+    /// 版本号
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// 名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 是否可以回滚
+    pub fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("name", &self.name)
+            .field("reversible", &self.is_reversible())
+            .finish()
+    }
+}
+
+/// 迁移的执行状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationStatus {
+    /// 版本号
+    pub version: u64,
+    /// 名称
+    pub name: String,
+    /// 执行时间，未执行时为 `None`
+    pub applied_at: Option<DateTime>,
+    /// 是否在当前的 [`Migrator`] 中定义；为 `false` 表示数据库中有未知版本的记录
+    pub defined: bool,
+}
+
+/// 迁移错误
+///
+/// 通过 `mongodb::error::Error::custom` 返回，可用 `error.get_custom::<MigrationError>()` 取得
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationError {
+    /// 其它进程正在迁移
+    Locked {
+        /// 锁的到期时间
+        locked_until: DateTime,
+    },
+    /// 迁移期间锁已到期并被其它进程获取，停止后续的写入
+    LockLost,
+    /// 多个迁移使用了同一版本号
+    DuplicateVersion(u64),
+    /// 版本号超出 `i64` 范围，无法记录
+    VersionOutOfRange(u64),
+    /// 迁移没有 `up` 函数
+    MissingUp(u64),
+    /// 需要回滚的迁移没有 `down` 函数
+    Irreversible(u64),
+    /// 需要回滚的迁移不在当前的 [`Migrator`] 中
+    Unknown(u64),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Locked { locked_until } => {
+                write!(
+                    f,
+                    "migrations are locked by another run until {locked_until}"
+                )
+            }
+            MigrationError::LockLost => {
+                write!(f, "the migration lock expired and was taken by another run")
+            }
+            MigrationError::DuplicateVersion(version) => {
+                write!(f, "migration version {version} is defined more than once")
+            }
+            MigrationError::VersionOutOfRange(version) => {
+                write!(f, "migration version {version} does not fit in an i64")
+            }
+            MigrationError::MissingUp(version) => {
+                write!(f, "migration {version} has no up function")
+            }
+            MigrationError::Irreversible(version) => {
+                write!(f, "migration {version} has no down function")
+            }
+            MigrationError::Unknown(version) => {
+                write!(f, "migration {version} is applied but not defined")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// 迁移执行器
+///
+/// # 示例
+///
+/// ```ignore
+/// let migrator = Migrator::new(&db)
+///     .migration(Migration::new(1, "backfill_status").up(|db| async move { ... }))
+///     .migration(Migration::new(2, "rename_user_name").up(...).down(...));
+///
+/// let applied = migrator.apply().await?;
+/// let rolled_back = migrator.rollback(1).await?;
+/// for status in migrator.status().await? {
+///     println!("{} {} {:?}", status.version, status.name, status.applied_at);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Migrator {
+    db: Database,
+    /// 读写迁移记录与锁文档的后端
+    records: Arc<dyn Backend>,
+    collection: CollectionTarget,
+    lock_timeout: Duration,
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// 使用 `db` 中的 `migrations` 集合
+    pub fn new(db: &Database) -> Self {
+        Self {
+            db: db.clone(),
+            records: Arc::new(db.clone()),
+            collection: CollectionTarget::new("migrations"),
+            lock_timeout: Duration::from_secs(600),
+            migrations: Vec::new(),
+        }
+    }
+
+    /// 设置记录迁移的集合名
+    pub fn collection(mut self, name: impl Into<String>) -> Self {
+        self.collection = CollectionTarget::new(name.into());
+        self
+    }
+
+    /// 设置锁的有效期，默认 10 分钟
+    ///
+    /// 持有锁的进程崩溃时，锁在到期后可被其它进程获取。每个迁移开始前锁会续期，
+    /// 因此有效期只需长于最慢的单个迁移
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// 添加迁移
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self
+    }
+
+    /// 已添加的迁移，按版本号排序
+    pub fn migrations(&self) -> Vec<&Migration> {
+        let mut migrations: Vec<_> = self.migrations.iter().collect();
+        migrations.sort_by_key(|migration| migration.version);
+        migrations
+    }
+
+    /// 所有迁移的状态，按版本号排序，包含数据库中有记录但未定义的版本
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>, Error> {
+        self.validate()?;
+        let applied = self.applied().await?;
+        Ok(status(&self.migrations(), &applied))
+    }
+
+    /// 按版本号顺序执行所有未执行的迁移，返回本次执行的版本号
+    ///
+    /// 某个迁移失败时停止并返回错误，此前执行的迁移保留记录
+    pub async fn apply(&self) -> Result<Vec<u64>, Error> {
+        self.validate()?;
+        self.locked(async |owner| {
+            let applied = self.applied().await?;
+            let mut done = Vec::new();
+            for migration in pending(&self.migrations(), &applied) {
+                let up = migration.up.as_ref().expect("validated");
+                self.renew(owner).await?;
+                up(self.db.clone()).await?;
+                self.renew(owner).await?;
+                self.records
+                    .insert_one(&self.collection, record(migration))
+                    .await?;
+                done.push(migration.version);
+            }
+            Ok(done)
+        })
+        .await
+    }
+
+    /// 按执行的逆序回滚最近的 `steps` 个迁移，返回回滚的版本号
+    ///
+    /// 执行顺序按记录的 `applied_at` 确定，同一时刻执行的迁移按版本号；
+    /// 开始前检查所有待回滚的迁移都已定义且可回滚
+    pub async fn rollback(&self, steps: usize) -> Result<Vec<u64>, Error> {
+        self.validate()?;
+        self.locked(async |owner| {
+            let applied = self.applied().await?;
+            let migrations = self.migrations();
+            let targets = rollback_plan(&migrations, &applied, steps)?;
+
+            let mut done = Vec::new();
+            for migration in targets {
+                let down = migration.down.as_ref().expect("checked by rollback_plan");
+                self.renew(owner).await?;
+                down(self.db.clone()).await?;
+                self.renew(owner).await?;
+                self.records
+                    .delete_one(&self.collection, doc! { "_id": migration.version as i64 })
+                    .await?;
+                done.push(migration.version);
+            }
+            Ok(done)
+        })
+        .await
+    }
+
+    /// 检查版本号是否重复、是否可以记录，以及每个迁移是否都有 `up`
+    fn validate(&self) -> Result<(), Error> {
+        let mut seen = HashSet::new();
+        for migration in &self.migrations {
+            let version = migration.version;
+            let error = if !seen.insert(version) {
+                MigrationError::DuplicateVersion(version)
+            } else if i64::try_from(version).is_err() {
+                MigrationError::VersionOutOfRange(version)
+            } else if migration.up.is_none() {
+                MigrationError::MissingUp(version)
+            } else {
+                continue;
+            };
+            return Err(Error::custom(error));
+        }
+        Ok(())
+    }
+
+    /// 已执行的迁移：版本号到记录
+    async fn applied(&self) -> Result<BTreeMap<u64, Document>, Error> {
+        let documents = self
+            .records
+            .find(
+                &self.collection,
+                doc! { "version": { "$exists": true } },
+                None,
+            )
+            .await?;
+        let mut applied = BTreeMap::new();
+        for document in documents {
+            if let Ok(version) = document.get_i64("version") {
+                applied.insert(version as u64, document);
+            }
+        }
+        Ok(applied)
+    }
+
+    /// 持有锁执行 `run`，结束后释放锁
+    async fn locked<R, F>(&self, run: F) -> Result<R, Error>
+    where
+        F: AsyncFnOnce(ObjectId) -> Result<R, Error>,
+    {
+        let owner = ObjectId::new();
+        let now = DateTime::now();
+        let (filter, update) = lock_update(owner, now, self.lock_timeout);
+        let acquired = self.records.upsert_one(&self.collection, filter, update);
+        if let Err(error) = acquired.await {
+            // 锁未到期：更新条件不匹配，插入同一 `_id` 的文档失败
+            let lock = self
+                .records
+                .find_one(&self.collection, doc! { "_id": LOCK_ID })
+                .await?;
+            return match lock.and_then(|lock| lock.get_datetime("locked_until").ok().copied()) {
+                Some(locked_until) if locked_until > now => {
+                    Err(Error::custom(MigrationError::Locked { locked_until }))
+                }
+                _ => Err(error),
+            };
+        }
+
+        let result = run(owner).await;
+        // 释放失败时锁会在到期后自动失效
+        let _ = self
+            .records
+            .delete_one(&self.collection, doc! { "_id": LOCK_ID, "owner": owner })
+            .await;
+        result
+    }
+
+    /// 续期锁；锁已不属于 `owner` 时返回 [`MigrationError::LockLost`]
+    async fn renew(&self, owner: ObjectId) -> Result<(), Error> {
+        let (filter, update) = renew_update(owner, DateTime::now(), self.lock_timeout);
+        match self
+            .records
+            .find_one_and_update(&self.collection, filter, update)
+            .await?
+        {
+            Some(_) => Ok(()),
+            None => Err(Error::custom(MigrationError::LockLost)),
+        }
+    }
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrator")
+            .field("db", &self.db.name())
+            .field("collection", &self.collection.name)
+            .field("lock_timeout", &self.lock_timeout)
+            .field("migrations", &self.migrations)
+            .finish()
+    }
+}
+
+/// 获取锁的条件与更新：锁不存在或已到期时由 `owner` 持有
+fn lock_update(owner: ObjectId, now: DateTime, timeout: Duration) -> (Document, Document) {
+    (
+        doc! { "_id": LOCK_ID, "locked_until": { "$lte": now } },
+        doc! { "$set": {
+            "owner": owner,
+            "locked_at": now,
+            "locked_until": expiry(now, timeout),
+        } },
+    )
+}
+
+/// 续期锁的条件与更新：只匹配仍由 `owner` 持有的锁
+fn renew_update(owner: ObjectId, now: DateTime, timeout: Duration) -> (Document, Document) {
+    (
+        doc! { "_id": LOCK_ID, "owner": owner },
+        doc! { "$set": { "locked_until": expiry(now, timeout) } },
+    )
+}
+
+fn expiry(now: DateTime, timeout: Duration) -> DateTime {
+    let timeout = i64::try_from(timeout.as_millis()).unwrap_or(i64::MAX);
+    DateTime::from_millis(now.timestamp_millis().saturating_add(timeout))
+}
+
+/// 迁移记录
+fn record(migration: &Migration) -> Document {
+    doc! {
+        "_id": migration.version as i64,
+        "version": migration.version as i64,
+        "name": &migration.name,
+        "applied_at": DateTime::now(),
+    }
+}
+
+/// 未执行的迁移，按版本号排序
+fn pending<'a>(
+    migrations: &[&'a Migration],
+    applied: &BTreeMap<u64, Document>,
+) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .filter(|migration| !applied.contains_key(&migration.version))
+        .copied()
+        .collect()
+}
+
+/// 需要回滚的迁移：最近执行的 `steps` 个，按 `applied_at` 与版本号降序
+fn rollback_plan<'a>(
+    migrations: &[&'a Migration],
+    applied: &BTreeMap<u64, Document>,
+    steps: usize,
+) -> Result<Vec<&'a Migration>, Error> {
+    let mut order: Vec<_> = applied
+        .iter()
+        .map(|(&version, record)| (record.get_datetime("applied_at").ok().copied(), version))
+        .collect();
+    order.sort_unstable_by(|a, b| b.cmp(a));
+    order
+        .into_iter()
+        .take(steps)
+        .map(|(_, version)| {
+            let migration = migrations
+                .iter()
+                .find(|migration| migration.version == version)
+                .ok_or(MigrationError::Unknown(version))?;
+            if !migration.is_reversible() {
+                return Err(MigrationError::Irreversible(version));
+            }
+            Ok(*migration)
+        })
+        .collect::<Result<_, _>>()
+        .map_err(Error::custom)
+}
+
+fn status(migrations: &[&Migration], applied: &BTreeMap<u64, Document>) -> Vec<MigrationStatus> {
+    let applied_at = |record: &Document| record.get_datetime("applied_at").ok().copied();
+    let mut statuses: Vec<_> = migrations
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            name: migration.name.clone(),
+            applied_at: applied.get(&migration.version).and_then(applied_at),
+            defined: true,
+        })
+        .collect();
+    for (&version, record) in applied {
+        if !migrations.iter().any(|m| m.version == version) {
+            statuses.push(MigrationStatus {
+                version,
+                name: record.get_str("name").unwrap_or_default().to_string(),
+                applied_at: applied_at(record),
+                defined: false,
+            });
+        }
+    }
+    statuses.sort_by_key(|status| status.version);
+    statuses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+
+    fn noop(version: u64) -> Migration {
+        Migration::new(version, format!("m{version}")).up(|_| async { Ok(()) })
+    }
+
+    fn applied(versions: &[u64]) -> BTreeMap<u64, Document> {
+        versions
+            .iter()
+            .map(|&version| {
+                let migration = noop(version);
+                (version, record(&migration))
+            })
+            .collect()
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    /// 迁移记录与锁保存在 `backend` 中的执行器
+    async fn migrator(backend: &MemoryBackend) -> Migrator {
+        // 创建客户端不会建立连接，迁移函数不使用数据库
+        let client = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        Migrator {
+            records: Arc::new(backend.clone()),
+            ..Migrator::new(&client.database("app"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_futures_are_send() {
+        // 创建客户端不会建立连接
+        let client = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        let migrator = Migrator::new(&client.database("app")).migration(noop(1));
+        assert_send(&migrator.apply());
+        assert_send(&migrator.rollback(1));
+        assert_send(&migrator.status());
+    }
+
+    #[tokio::test]
+    async fn test_validate() {
+        let client = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        let db = client.database("app");
+
+        let error = Migrator::new(&db)
+            .migration(noop(1))
+            .migration(noop(1))
+            .validate();
+        let error = error.unwrap_err();
+        assert_eq!(
+            error.get_custom::<MigrationError>(),
+            Some(&MigrationError::DuplicateVersion(1))
+        );
+
+        let error = Migrator::new(&db)
+            .migration(Migration::new(2, "empty"))
+            .validate();
+        assert_eq!(
+            error.unwrap_err().get_custom::<MigrationError>(),
+            Some(&MigrationError::MissingUp(2))
+        );
+        assert!(
+            Migrator::new(&db)
+                .migration(noop(u64::MAX))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_plans() {
+        let reversible = |version| noop(version).down(|_| async { Ok(()) });
+        let (m1, m2, m3) = (reversible(1), noop(2), reversible(3));
+        let migrations = [&m1, &m2, &m3];
+
+        let versions = |plan: Vec<&Migration>| plan.iter().map(|m| m.version).collect::<Vec<_>>();
+        assert_eq!(versions(pending(&migrations, &applied(&[2]))), [1, 3]);
+
+        let mut applied = applied(&[1, 2, 3]);
+        assert_eq!(
+            versions(rollback_plan(&migrations, &applied, 1).unwrap()),
+            [3]
+        );
+        // 后执行的低版本迁移先回滚
+        let later = DateTime::from_millis(DateTime::now().timestamp_millis() + 1_000);
+        applied.get_mut(&1).unwrap().insert("applied_at", later);
+        assert_eq!(
+            versions(rollback_plan(&migrations, &applied, 1).unwrap()),
+            [1]
+        );
+        applied
+            .get_mut(&1)
+            .unwrap()
+            .insert("applied_at", DateTime::from_millis(0));
+        let error = rollback_plan(&migrations, &applied, 2).unwrap_err();
+        assert_eq!(
+            error.get_custom::<MigrationError>(),
+            Some(&MigrationError::Irreversible(2))
+        );
+
+        let statuses = status(&[&m1, &m3], &applied);
+        assert_eq!(
+            statuses
+                .iter()
+                .map(|s| (s.version, s.defined))
+                .collect::<Vec<_>>(),
+            [(1, true), (2, false), (3, true)]
+        );
+        assert_eq!(statuses[1].name, "m2");
+        assert!(statuses.iter().all(|s| s.applied_at.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_apply_and_rollback() {
+        let backend = MemoryBackend::new();
+        let reversible = |version| noop(version).down(|_| async { Ok(()) });
+        let migrator = migrator(&backend)
+            .await
+            .migration(reversible(2))
+            .migration(reversible(1))
+            .migration(reversible(3));
+
+        assert_eq!(migrator.apply().await.unwrap(), [1, 2, 3]);
+        assert!(migrator.apply().await.unwrap().is_empty());
+        // 结束后释放了锁
+        assert_eq!(backend.documents("migrations").len(), 3);
+
+        // 回滚按执行顺序的逆序，而不是版本号
+        let target = CollectionTarget::new("migrations");
+        let later = DateTime::from_millis(DateTime::now().timestamp_millis() + 1_000);
+        backend
+            .update_one(
+                &target,
+                doc! { "_id": 1_i64 },
+                doc! { "$set": { "applied_at": later } },
+            )
+            .await
+            .unwrap();
+        assert_eq!(migrator.rollback(2).await.unwrap(), [1, 3]);
+        let statuses = migrator.status().await.unwrap();
+        assert_eq!(
+            statuses
+                .iter()
+                .map(|s| s.applied_at.is_some())
+                .collect::<Vec<_>>(),
+            [false, true, false]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lock() {
+        let backend = MemoryBackend::new();
+        let target = CollectionTarget::new("migrations");
+        let now = DateTime::now().timestamp_millis();
+        let lock = |until: i64| doc! { "_id": LOCK_ID, "owner": ObjectId::new(), "locked_until": DateTime::from_millis(until) };
+
+        // 其它进程持有未到期的锁
+        backend
+            .insert_one(&target, lock(now + 60_000))
+            .await
+            .unwrap();
+        let migrator = migrator(&backend).await.migration(noop(1));
+        let error = migrator.apply().await.unwrap_err();
+        assert!(matches!(
+            error.get_custom::<MigrationError>(),
+            Some(MigrationError::Locked { .. })
+        ));
+
+        // 到期的锁可以被获取
+        backend.clear();
+        backend.insert_one(&target, lock(now - 1)).await.unwrap();
+        assert_eq!(migrator.apply().await.unwrap(), [1]);
+        assert!(
+            backend
+                .find_one(&target, doc! { "_id": LOCK_ID })
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // 迁移期间锁被其它进程获取：不再写入记录
+        let thief = backend.clone();
+        let steal = Migration::new(2, "slow").up(move |_| {
+            let backend = thief.clone();
+            async move {
+                let target = CollectionTarget::new("migrations");
+                let update = doc! { "$set": { "owner": ObjectId::new() } };
+                backend
+                    .update_one(&target, doc! { "_id": LOCK_ID }, update)
+                    .await?;
+                Ok(())
+            }
+        });
+        let migrator = migrator.migration(steal).migration(noop(3));
+        let error = migrator.apply().await.unwrap_err();
+        assert_eq!(
+            error.get_custom::<MigrationError>(),
+            Some(&MigrationError::LockLost)
+        );
+        let recorded = backend.count_documents(&target, doc! { "version": { "$exists": true } });
+        assert_eq!(recorded.await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_lock_update() {
+        let owner = ObjectId::new();
+        let now = DateTime::from_millis(1_000);
+        let (filter, update) = lock_update(owner, now, Duration::from_secs(60));
+        assert_eq!(
+            filter,
+            doc! { "_id": "lock", "locked_until": { "$lte": now } }
+        );
+        assert_eq!(
+            update
+                .get_document("$set")
+                .unwrap()
+                .get_datetime("locked_until")
+                .unwrap(),
+            &DateTime::from_millis(61_000)
+        );
+    }
+}
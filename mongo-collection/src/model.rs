@@ -0,0 +1,355 @@
+//! 模型元数据与集合同步
+//!
+//! [`ModelInfo`] 以非泛型的形式保存模型的集合名、数据库、索引与 `$jsonSchema`，
+//! 可以把多个模型放进同一个列表中统一处理。[`ModelInfo::diff`] 比较声明的索引、
+//! 校验器与数据库中的实际状态，[`ModelInfo::apply`] 将差异同步到数据库。
+
+use crate::validator::{self, ValidatorOptions};
+use crate::{Collection, FieldInfo};
+use futures::TryStreamExt;
+use mongodb::bson::{Bson, Document, doc};
+use mongodb::error::Error;
+use mongodb::options::IndexOptions;
+use mongodb::{Database, IndexModel};
+use std::fmt;
+
+/// 默认 `_id` 索引的名称，不参与比较
+const ID_INDEX: &str = "_id_";
+
+/// 模型的元数据
+///
+/// # 示例
+///
+/// ```ignore
+/// let models = [ModelInfo::of::<User>(), ModelInfo::of::<Order>()];
+/// for model in &models {
+///     let diff = model.diff(&db).await?;
+///     if !diff.is_empty() {
+///         model.apply(&db, false).await?;
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ModelInfo {
+    name: fn() -> &'static str,
+    database_name: fn() -> Option<&'static str>,
+    json_schema: fn() -> Document,
+    indexes: fn() -> Vec<IndexModel>,
+    fields: fn() -> &'static [FieldInfo],
+}
+
+impl ModelInfo {
+    /// 读取模型 `T` 的元数据
+    pub const fn of<T: Collection>() -> Self {
+        Self {
+            name: T::name,
+            database_name: T::database_name,
+            json_schema: T::json_schema,
+            indexes: T::indexes,
+            fields: T::fields,
+        }
+    }
+
+    /// 集合名
+    pub fn name(&self) -> &'static str {
+        (self.name)()
+    }
+
+    /// 模型绑定的数据库，见 [`Collection::database_name`]
+    pub fn database_name(&self) -> Option<&'static str> {
+        (self.database_name)()
+    }
+
+    /// 模型的 `$jsonSchema`
+    pub fn json_schema(&self) -> Document {
+        (self.json_schema)()
+    }
+
+    /// 集合的 `validator` 文档
+    pub fn validator(&self) -> Document {
+        doc! { "$jsonSchema": self.json_schema() }
+    }
+
+    /// 声明的索引，未命名的索引补全为 MongoDB 的默认名称
+    pub fn indexes(&self) -> Vec<IndexModel> {
+        (self.indexes)().into_iter().map(named).collect()
+    }
+
+    /// 存储的字段，见 [`Collection::fields`]
+    pub fn fields(&self) -> &'static [FieldInfo] {
+        (self.fields)()
+    }
+
+    /// 比较声明的索引、校验器与 `db` 中的实际状态
+    pub async fn diff(&self, db: &Database) -> Result<ModelDiff, Error> {
+        let name = self.name();
+        let specification = db
+            .list_collections()
+            .filter(doc! { "name": name })
+            .await?
+            .try_next()
+            .await?;
+        let Some(specification) = specification else {
+            return Ok(ModelDiff {
+                collection: name.to_string(),
+                exists: false,
+                missing_indexes: self.indexes(),
+                changed_indexes: Vec::new(),
+                extra_indexes: Vec::new(),
+                validator_changed: true,
+            });
+        };
+
+        let actual: Vec<IndexModel> = db
+            .collection::<Document>(name)
+            .list_indexes()
+            .await?
+            .try_collect()
+            .await?;
+        let (missing_indexes, changed_indexes, extra_indexes) = compare(self.indexes(), actual);
+
+        Ok(ModelDiff {
+            collection: name.to_string(),
+            exists: true,
+            missing_indexes,
+            changed_indexes,
+            extra_indexes,
+            validator_changed: specification.options.validator != Some(self.validator()),
+        })
+    }
+
+    /// 将声明的索引与校验器同步到 `db`，返回同步前的差异
+    ///
+    /// 集合不存在时带校验器创建集合；定义改变的索引先删除再重建。
+    /// `drop_extra` 为 `true` 时删除未声明的索引（`_id` 索引除外）
+    pub async fn apply(&self, db: &Database, drop_extra: bool) -> Result<ModelDiff, Error> {
+        let diff = self.diff(db).await?;
+        let name = self.name();
+
+        if !diff.exists {
+            db.create_collection(name)
+                .validator(self.validator())
+                .await?;
+        } else if diff.validator_changed {
+            let command =
+                validator::coll_mod_command(name, self.validator(), &ValidatorOptions::default())?;
+            db.run_command(command).await?;
+        }
+
+        let collection = db.collection::<Document>(name);
+        for index in &diff.changed_indexes {
+            collection.drop_index(index_name(index)).await?;
+        }
+        let create: Vec<_> = diff
+            .changed_indexes
+            .iter()
+            .chain(&diff.missing_indexes)
+            .cloned()
+            .collect();
+        if !create.is_empty() {
+            collection.create_indexes(create).await?;
+        }
+        if drop_extra {
+            for name in &diff.extra_indexes {
+                collection.drop_index(name).await?;
+            }
+        }
+        Ok(diff)
+    }
+}
+
+impl fmt::Debug for ModelInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModelInfo")
+            .field("name", &self.name())
+            .field("database_name", &self.database_name())
+            .finish()
+    }
+}
+
+/// 声明与实际状态的差异
+#[derive(Debug, Clone, Default)]
+pub struct ModelDiff {
+    /// 集合名
+    pub collection: String,
+    /// 集合是否存在
+    pub exists: bool,
+    /// 声明但不存在的索引
+    pub missing_indexes: Vec<IndexModel>,
+    /// 名称相同但键或 `unique` 不同的索引（声明的定义）
+    pub changed_indexes: Vec<IndexModel>,
+    /// 存在但未声明的索引名
+    pub extra_indexes: Vec<String>,
+    /// 校验器是否与模型的 `$jsonSchema` 不同
+    pub validator_changed: bool,
+}
+
+impl ModelDiff {
+    /// 是否没有差异
+    pub fn is_empty(&self) -> bool {
+        self.exists
+            && self.missing_indexes.is_empty()
+            && self.changed_indexes.is_empty()
+            && self.extra_indexes.is_empty()
+            && !self.validator_changed
+    }
+}
+
+/// 索引名；未命名时按 MongoDB 的规则生成，如 `tenant_id_1_created_at_-1`
+pub(crate) fn index_name(index: &IndexModel) -> String {
+    if let Some(name) = index.options.as_ref().and_then(|o| o.name.clone()) {
+        return name;
+    }
+    index
+        .keys
+        .iter()
+        .map(|(field, value)| match value {
+            Bson::String(kind) => format!("{field}_{kind}"),
+            value => format!("{field}_{value}"),
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// 补全索引名
+fn named(mut index: IndexModel) -> IndexModel {
+    let name = index_name(&index);
+    index.options.get_or_insert_with(IndexOptions::default).name = Some(name);
+    index
+}
+
+fn is_unique(index: &IndexModel) -> bool {
+    index
+        .options
+        .as_ref()
+        .and_then(|o| o.unique)
+        .unwrap_or(false)
+}
+
+/// 比较索引键；方向值按数值比较，`1` 与 `1.0` 视为相同
+fn same_keys(a: &Document, b: &Document) -> bool {
+    let number = |value: &Bson| match value {
+        Bson::Int32(n) => Some(f64::from(*n)),
+        Bson::Int64(n) => Some(*n as f64),
+        Bson::Double(n) => Some(*n),
+        _ => None,
+    };
+    a.len() == b.len()
+        && a.iter().zip(b).all(|((ka, va), (kb, vb))| {
+            ka == kb
+                && match (number(va), number(vb)) {
+                    (Some(x), Some(y)) => x == y,
+                    _ => va == vb,
+                }
+        })
+}
+
+/// 按名称比较声明与实际的索引，返回缺少、改变与多余的索引
+fn compare(
+    declared: Vec<IndexModel>,
+    actual: Vec<IndexModel>,
+) -> (Vec<IndexModel>, Vec<IndexModel>, Vec<String>) {
+    let actual: Vec<_> = actual
+        .into_iter()
+        .map(|index| (index_name(&index), index))
+        .filter(|(name, _)| name != ID_INDEX)
+        .collect();
+
+    let mut missing = Vec::new();
+    let mut changed = Vec::new();
+    for index in &declared {
+        let name = index_name(index);
+        match actual.iter().find(|(actual_name, _)| *actual_name == name) {
+            None => missing.push(index.clone()),
+            Some((_, existing)) => {
+                if !same_keys(&index.keys, &existing.keys)
+                    || is_unique(index) != is_unique(existing)
+                {
+                    changed.push(index.clone());
+                }
+            }
+        }
+    }
+    let extra = actual
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| !declared.iter().any(|index| index_name(index) == *name))
+        .collect();
+    (missing, changed, extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[allow(dead_code)]
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[collection(db = "cms", index(keys = "tenantId, -createdAt"))]
+    #[collection(index(keys = "tenantId, slug", unique, name = "tenant_slug"))]
+    #[serde(rename_all = "camelCase")]
+    struct Article {
+        tenant_id: String,
+        slug: String,
+        #[collection(unique)]
+        external_id: String,
+        #[collection(index)]
+        created_at: i64,
+    }
+
+    fn index(keys: Document, unique: bool, name: &str) -> IndexModel {
+        IndexModel::builder()
+            .keys(keys)
+            .options(
+                IndexOptions::builder()
+                    .unique(unique.then_some(true))
+                    .name(name.to_string())
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_model_info() {
+        let model = ModelInfo::of::<Article>();
+        assert_eq!(model.name(), "articles");
+        assert_eq!(model.database_name(), Some("cms"));
+        assert!(model.validator().contains_key("$jsonSchema"));
+
+        let indexes = model.indexes();
+        let names: Vec<_> = indexes.iter().map(index_name).collect();
+        assert_eq!(
+            names,
+            [
+                "externalId_1",
+                "createdAt_1",
+                "tenantId_1_createdAt_-1",
+                "tenant_slug"
+            ]
+        );
+        assert!(is_unique(&indexes[0]));
+        assert!(!is_unique(&indexes[1]));
+        assert_eq!(indexes[2].keys, doc! { "tenantId": 1, "createdAt": -1 });
+        assert!(is_unique(&indexes[3]));
+    }
+
+    #[test]
+    fn test_compare() {
+        let declared = vec![
+            index(doc! { "a": 1 }, true, "a_1"),
+            index(doc! { "b": 1, "c": -1 }, false, "b_1_c_-1"),
+            index(doc! { "d": 1 }, false, "d_1"),
+        ];
+        let actual = vec![
+            index(doc! { "_id": 1 }, false, "_id_"),
+            index(doc! { "a": 1 }, false, "a_1"),
+            index(doc! { "b": 1.0, "c": -1.0 }, false, "b_1_c_-1"),
+            index(doc! { "legacy": 1 }, false, "legacy_1"),
+        ];
+
+        let (missing, changed, extra) = compare(declared, actual);
+        assert_eq!(missing.iter().map(index_name).collect::<Vec<_>>(), ["d_1"]);
+        assert_eq!(changed.iter().map(index_name).collect::<Vec<_>>(), ["a_1"]);
+        assert_eq!(extra, ["legacy_1"]);
+    }
+}
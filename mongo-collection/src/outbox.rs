@@ -0,0 +1,523 @@
+//! 事务性发件箱
+//!
+//! 通过 [`OutboxRepository`] 执行的写操作会在同一个事务中向发件箱集合插入一条事件文档，
+//! 写入与事件要么同时成功，要么同时失败；[`Outbox::relay`] 逐条取出待投递的事件，
+//! 在消费者请求下一条时把上一条标记为已投递。
+//!
+//! 事务要求 MongoDB 以副本集或分片集群方式部署。
+
+use crate::CollectionRepository;
+use crate::backend::{Backend, CollectionTarget};
+use crate::instrument::{self, Operation};
+use futures::StreamExt;
+use futures::stream::{self, BoxStream};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{
+    Bson, DateTime, Document, deserialize_from_document, doc, serialize_to_document,
+};
+use mongodb::error::UNKNOWN_TRANSACTION_COMMIT_RESULT;
+use mongodb::options::ReturnDocument;
+use mongodb::{ClientSession, Database};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// 提交结果未知时重试提交的次数
+const MAX_COMMIT_RETRIES: u32 = 3;
+
+/// 待写入发件箱的事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboxMessage {
+    event_type: String,
+    payload: Document,
+    aggregate_id: Option<Bson>,
+}
+
+impl OutboxMessage {
+    /// 创建事件，`payload` 会被序列化为 BSON 文档
+    pub fn new(
+        event_type: impl Into<String>,
+        payload: &impl Serialize,
+    ) -> Result<Self, mongodb::error::Error> {
+        Ok(Self {
+            event_type: event_type.into(),
+            payload: serialize_to_document(payload)?,
+            aggregate_id: None,
+        })
+    }
+
+    /// 指定事件所属文档的 `_id`
+    ///
+    /// 未指定时使用本次写入的文档的 `_id`
+    pub fn aggregate_id(mut self, id: impl Into<Bson>) -> Self {
+        self.aggregate_id = Some(id.into());
+        self
+    }
+}
+
+/// 发件箱中的事件文档
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEvent {
+    /// 事件 ID，按写入顺序递增
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// 产生事件的集合名
+    pub aggregate: String,
+    /// 产生事件的文档的 `_id`
+    pub aggregate_id: Option<Bson>,
+    /// 事件类型，如 `"OrderPlaced"`
+    pub event_type: String,
+    /// 事件内容
+    pub payload: Document,
+    /// 写入时间
+    pub created_at: DateTime,
+    /// 投递时间，未投递时为 `None`
+    pub dispatched_at: Option<DateTime>,
+    /// 被中继领取的次数
+    #[serde(default)]
+    pub attempts: i32,
+}
+
+/// 事务性发件箱
+///
+/// # 示例
+///
+/// ```ignore
+/// let outbox = Outbox::new(&db);
+/// let orders = outbox.repository::<Order>();
+/// orders.create(&order, OutboxMessage::new("OrderPlaced", &order)?).await?;
+///
+/// let mut events = outbox.relay();
+/// while let Some(event) = events.try_next().await? {
+///     broker.publish(&event.event_type, &event.payload).await?;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Outbox {
+    db: Database,
+    collection: String,
+    lease: Duration,
+}
+
+impl Outbox {
+    /// 使用 `db` 中的 `outbox` 集合
+    pub fn new(db: &Database) -> Self {
+        Self {
+            db: db.clone(),
+            collection: "outbox".to_string(),
+            lease: Duration::from_secs(30),
+        }
+    }
+
+    /// 设置发件箱集合名
+    pub fn collection(mut self, name: impl Into<String>) -> Self {
+        self.collection = name.into();
+        self
+    }
+
+    /// 设置中继领取事件后的租约时长，默认 30 秒
+    ///
+    /// 领取事件的中继在租约内崩溃时，事件会在租约到期后被重新领取
+    pub fn lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// 返回模型 `T` 的发件箱仓储，写入发件箱所在的数据库
+    pub fn repository<T: CollectionRepository>(&self) -> OutboxRepository<T> {
+        self.repository_on(self.db.clone())
+    }
+
+    /// 返回通过 `backend` 写入模型 `T` 的发件箱仓储
+    ///
+    /// 集合由 [`Backend::resolve`] 解析，租户限定、动态集合名与集合选项与 CRUD 方法一致。
+    /// `backend` 必须与发件箱使用同一个客户端，否则事务无法覆盖两次写入
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let orders = outbox.repository_on::<Order, _>(db.for_tenant("acme"));
+    /// ```
+    pub fn repository_on<T: CollectionRepository, B: Backend>(
+        &self,
+        backend: B,
+    ) -> OutboxRepository<T, B> {
+        OutboxRepository {
+            outbox: self.clone(),
+            backend,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 在调用方已开启的事务中写入事件
+    ///
+    /// 用于 [`OutboxRepository`] 未覆盖的写操作
+    pub async fn enqueue(
+        &self,
+        session: &mut ClientSession,
+        aggregate: &str,
+        message: OutboxMessage,
+    ) -> Result<ObjectId, mongodb::error::Error> {
+        let id = ObjectId::new();
+        let event = event_document(id, aggregate, message, None);
+        self.events().insert_one(event).session(session).await?;
+        Ok(id)
+    }
+
+    /// 返回待投递事件的流
+    ///
+    /// 按写入顺序领取未投递、且不在其它中继租约内的事件；消费者请求下一条事件时，
+    /// 上一条事件被标记为已投递（至少一次语义）。没有待投递事件时流结束，
+    /// 应定期重新调用
+    pub fn relay(&self) -> BoxStream<'static, Result<OutboxEvent, mongodb::error::Error>> {
+        let state = Relay {
+            events: self.events(),
+            lease: self.lease,
+            pending: None,
+        };
+        stream::try_unfold(state, Relay::next).boxed()
+    }
+
+    fn events(&self) -> mongodb::Collection<Document> {
+        self.db.collection(&self.collection)
+    }
+
+    /// 在事务中执行 `write` 并写入 `write` 返回的事件
+    async fn transact<R, F>(&self, write: F) -> Result<R, mongodb::error::Error>
+    where
+        F: AsyncFnOnce(&mut ClientSession) -> Result<(R, Option<Document>), mongodb::error::Error>,
+    {
+        let mut session = self.db.client().start_session().await?;
+        session.start_transaction().await?;
+
+        let result = async {
+            let (value, event) = write(&mut session).await?;
+            if let Some(event) = event {
+                self.events()
+                    .insert_one(event)
+                    .session(&mut session)
+                    .await?;
+            }
+            Ok(value)
+        }
+        .await;
+
+        match result {
+            Ok(value) => {
+                let mut retries = 0;
+                loop {
+                    match session.commit_transaction().await {
+                        Err(e)
+                            if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+                                && retries < MAX_COMMIT_RETRIES =>
+                        {
+                            retries += 1;
+                        }
+                        other => return other.map(|()| value),
+                    }
+                }
+            }
+            Err(e) => {
+                // 回滚失败时保留原始错误，服务器会在事务超时后自动回滚
+                let _ = session.abort_transaction().await;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// 构建 `_id` 为 `id` 的发件箱事件文档
+fn event_document(
+    id: ObjectId,
+    aggregate: &str,
+    message: OutboxMessage,
+    written_id: Option<Bson>,
+) -> Document {
+    doc! {
+        "_id": id,
+        "aggregate": aggregate,
+        "aggregate_id": message.aggregate_id.or(written_id).unwrap_or(Bson::Null),
+        "event_type": message.event_type,
+        "payload": message.payload,
+        "created_at": DateTime::now(),
+        "dispatched_at": Bson::Null,
+        "attempts": 0,
+    }
+}
+
+/// 写操作与事件在同一事务中提交的仓储
+///
+/// 通过 [`Outbox::repository`] 或 [`Outbox::repository_on`] 创建
+#[derive(Debug, Clone)]
+pub struct OutboxRepository<T, B = Database> {
+    outbox: Outbox,
+    backend: B,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: CollectionRepository, B: Backend> OutboxRepository<T, B> {
+    /// 创建文档并写入事件
+    pub async fn create(
+        &self,
+        document: &T,
+        message: OutboxMessage,
+    ) -> Result<T, mongodb::error::Error> {
+        let target = CollectionTarget::of::<T>();
+        Operation::new("outbox_create", &target)
+            .run(instrument::one, async {
+                let resolved = self.backend.resolve(&target)?;
+                let raw = resolved.stamp(serialize_to_document(document)?)?;
+                self.outbox
+                    .transact(async |session| {
+                        let result = resolved
+                            .collection()
+                            .insert_one(raw)
+                            .session(session)
+                            .await?;
+                        let event = event_document(
+                            ObjectId::new(),
+                            resolved.name(),
+                            message,
+                            Some(result.inserted_id),
+                        );
+                        Ok((document.clone(), Some(event)))
+                    })
+                    .await
+            })
+            .await
+    }
+
+    /// 更新单个文档并写入事件
+    ///
+    /// 没有文档匹配时不写入事件，返回 `false`
+    pub async fn update_one(
+        &self,
+        filter: Document,
+        update: Document,
+        message: OutboxMessage,
+    ) -> Result<bool, mongodb::error::Error> {
+        let target = CollectionTarget::of::<T>();
+        Operation::new("outbox_update_one", &target)
+            .filter(&filter)
+            .run(instrument::hit, async {
+                let resolved = self.backend.resolve(&target)?;
+                resolved.check_update(&update)?;
+                let filter = resolved.scope(filter);
+                self.outbox
+                    .transact(async |session| {
+                        let updated = resolved
+                            .collection::<Document>()
+                            .find_one_and_update(filter, update)
+                            .return_document(ReturnDocument::After)
+                            .session(session)
+                            .await?;
+                        Ok(match updated {
+                            Some(document) => {
+                                let id = document.get("_id").cloned();
+                                (
+                                    true,
+                                    Some(event_document(
+                                        ObjectId::new(),
+                                        resolved.name(),
+                                        message,
+                                        id,
+                                    )),
+                                )
+                            }
+                            None => (false, None),
+                        })
+                    })
+                    .await
+            })
+            .await
+    }
+
+    /// 删除单个文档并写入事件
+    ///
+    /// 没有文档匹配时不写入事件，返回 `false`
+    pub async fn delete_one(
+        &self,
+        filter: Document,
+        message: OutboxMessage,
+    ) -> Result<bool, mongodb::error::Error> {
+        let target = CollectionTarget::of::<T>();
+        Operation::new("outbox_delete_one", &target)
+            .filter(&filter)
+            .run(instrument::hit, async {
+                let resolved = self.backend.resolve(&target)?;
+                let filter = resolved.scope(filter);
+                self.outbox
+                    .transact(async |session| {
+                        let deleted = resolved
+                            .collection::<Document>()
+                            .find_one_and_delete(filter)
+                            .session(session)
+                            .await?;
+                        Ok(match deleted {
+                            Some(document) => {
+                                let id = document.get("_id").cloned();
+                                (
+                                    true,
+                                    Some(event_document(
+                                        ObjectId::new(),
+                                        resolved.name(),
+                                        message,
+                                        id,
+                                    )),
+                                )
+                            }
+                            None => (false, None),
+                        })
+                    })
+                    .await
+            })
+            .await
+    }
+}
+
+/// 中继流的状态
+struct Relay {
+    events: mongodb::Collection<Document>,
+    lease: Duration,
+    /// 已交给消费者、尚未标记为已投递的事件
+    pending: Option<ObjectId>,
+}
+
+impl Relay {
+    async fn next(mut self) -> Result<Option<(OutboxEvent, Self)>, mongodb::error::Error> {
+        let now = DateTime::now();
+
+        // 消费者请求下一条事件，说明上一条已投递
+        if let Some(id) = self.pending.take() {
+            self.events
+                .update_one(doc! { "_id": id }, dispatch_update(now))
+                .await?;
+        }
+
+        let claimed = self
+            .events
+            .find_one_and_update(claim_filter(now), claim_update(now, self.lease))
+            .sort(doc! { "_id": 1 })
+            .return_document(ReturnDocument::After)
+            .await?;
+
+        match claimed {
+            Some(document) => {
+                let event: OutboxEvent = deserialize_from_document(document)?;
+                self.pending = Some(event.id);
+                Ok(Some((event, self)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// 可领取的事件：未投递，且未被领取或租约已到期
+fn claim_filter(now: DateTime) -> Document {
+    doc! {
+        "dispatched_at": Bson::Null,
+        "$or": [{ "locked_until": Bson::Null }, { "locked_until": { "$lte": now } }],
+    }
+}
+
+/// 领取事件：租约持续到 `now + lease`
+fn claim_update(now: DateTime, lease: Duration) -> Document {
+    let locked_until = DateTime::from_millis(now.timestamp_millis() + lease.as_millis() as i64);
+    doc! { "$set": { "locked_until": locked_until }, "$inc": { "attempts": 1 } }
+}
+
+/// 标记事件已投递并释放租约
+fn dispatch_update(now: DateTime) -> Document {
+    doc! { "$set": { "dispatched_at": now }, "$unset": { "locked_until": "" } }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Collection;
+    use crate::backend::BackendExt;
+    use crate::backend::query::{apply_update, matches};
+
+    #[derive(Serialize)]
+    struct OrderPlaced {
+        total: i64,
+    }
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Order {
+        total: i64,
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[tokio::test]
+    async fn test_futures_are_send() {
+        // 创建客户端不会建立连接
+        let client = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        let outbox = Outbox::new(&client.database("shop"));
+        let orders = outbox.repository::<Order>();
+        let message = OutboxMessage::new("OrderPlaced", &OrderPlaced { total: 1 }).unwrap();
+
+        assert_send(&orders.create(&Order { total: 1 }, message.clone()));
+        assert_send(&orders.update_one(doc! {}, doc! { "$set": { "total": 2 } }, message.clone()));
+        assert_send(&orders.delete_one(doc! {}, message.clone()));
+        assert_send(&outbox.relay());
+
+        let scoped = outbox.repository_on::<Order, _>(client.database("shop").for_tenant("acme"));
+        assert_send(&scoped.create(&Order { total: 1 }, message));
+    }
+
+    #[test]
+    fn test_event_document() {
+        let message = OutboxMessage::new("OrderPlaced", &OrderPlaced { total: 42 }).unwrap();
+        let document = event_document(
+            ObjectId::new(),
+            "orders",
+            message.clone(),
+            Some(Bson::Int32(7)),
+        );
+
+        let event: OutboxEvent = deserialize_from_document(document).unwrap();
+        assert_eq!(event.aggregate, "orders");
+        assert_eq!(event.aggregate_id, Some(Bson::Int32(7)));
+        assert_eq!(event.event_type, "OrderPlaced");
+        assert_eq!(event.payload, doc! { "total": 42_i64 });
+        assert_eq!(event.dispatched_at, None);
+
+        // 显式指定的 aggregate_id 优先
+        let document = event_document(
+            ObjectId::new(),
+            "orders",
+            message.aggregate_id("o-1"),
+            Some(Bson::Int32(7)),
+        );
+        assert_eq!(document.get_str("aggregate_id").unwrap(), "o-1");
+    }
+
+    #[test]
+    fn test_relay_claims_and_lease_expiry() {
+        let lease = Duration::from_secs(30);
+        let now = DateTime::from_millis(1_000_000);
+        let later = |seconds: i64| DateTime::from_millis(now.timestamp_millis() + seconds * 1000);
+        let message = OutboxMessage::new("OrderPlaced", &OrderPlaced { total: 1 }).unwrap();
+        let mut event = event_document(ObjectId::new(), "orders", message, None);
+
+        // 新事件可以领取，领取后在租约内不能被其它中继再次领取
+        assert!(matches(&event, &claim_filter(now)).unwrap());
+        assert!(apply_update(&mut event, &claim_update(now, lease)).unwrap());
+        assert_eq!(event.get_i32("attempts").unwrap(), 1);
+        assert!(!matches(&event, &claim_filter(later(29))).unwrap());
+
+        // 租约到期后可以重新领取
+        assert!(matches(&event, &claim_filter(later(30))).unwrap());
+        assert!(apply_update(&mut event, &claim_update(later(30), lease)).unwrap());
+        assert_eq!(event.get_i32("attempts").unwrap(), 2);
+
+        // 已投递的事件不再被领取
+        assert!(apply_update(&mut event, &dispatch_update(later(31))).unwrap());
+        assert!(!event.contains_key("locked_until"));
+        assert!(!matches(&event, &claim_filter(later(120))).unwrap());
+    }
+}
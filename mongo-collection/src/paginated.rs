@@ -1,7 +1,13 @@
-use crate::{ListQuery, SortOrder};
+use crate::facet;
+use crate::links::{PageLinks, PaginationLinks};
+use crate::query_string::{self, QueryError};
+use crate::sort::{self, SortSpec};
+use crate::{Collection, FacetBucket, FilterLanguage, SortOrder};
+use mongodb::bson::Document;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{PoisonError, RwLock};
 
 #[cfg(feature = "openapi")]
 use utoipa::{IntoParams, ToSchema};
@@ -24,45 +30,199 @@ pub struct PaginatedQuery {
     /// 排序方向
     #[default(_code = "SortOrder::Desc")]
     pub sort_order: SortOrder,
+    /// 多字段排序，如 `sort=status,-created_at`，指定时优先于 `sort_by`
+    #[serde(deserialize_with = "crate::sort::deserialize_sort")]
+    pub sort: Vec<SortSpec>,
     // 搜索/筛选
     /// 全局搜索关键词
     pub search: Option<String>,
+    /// 筛选表达式，如 `status:active AND age>=18`，见 [`FilterLanguage`](crate::FilterLanguage)
+    pub q: Option<String>,
+    /// 需要分面统计的字段，如 `facets=status,category`
+    ///
+    /// 只能是标记了 `#[collection(facet)]` 的字段，其它字段返回 `QueryError::UnknownField`
+    #[serde(deserialize_with = "crate::facet::deserialize_facets")]
+    pub facets: Vec<String>,
     /// 字段级筛选
     #[serde(flatten)]
     pub filters: Option<HashMap<String, String>>,
 }
 
+impl PaginatedQuery {
+    /// 解析 `filters[key]`、`filters[a][b]` 与 `filters.key` 格式的筛选参数
+    ///
+    /// 嵌套的键名转换为点号路径，如 `filters[author][name]` 对应 `author.name`
+    pub fn parsed_filters(&self) -> Option<HashMap<String, String>> {
+        query_string::parse_filters(&self.filters)
+    }
+
+    /// 生成排序文档
+    ///
+    /// `sort` 优先于 `sort_by`，未指定时按 `_id` 降序；
+    /// 未包含 `_id` 时追加 `_id` 作为最后的排序字段，保证结果顺序稳定
+    pub fn sort_document(&self) -> Document {
+        sort::sort_document(&self.sort, self.sort_by.as_deref(), &self.sort_order)
+    }
 
-impl PaginatedQuery{
-    pub fn parsed_filters(&self)->Option<HashMap<String, String>>{
-        if let Some(ref raw_filters) = self.filters{
-            let mut filters = HashMap::new();
-            for (key, value) in raw_filters {
-                // 解析 filters[key] 格式
-                if let Some(k) = key.strip_prefix("filters[").and_then(|s| s.strip_suffix("]")) {
-                    filters.insert(k.to_string(), value.to_string());
+    /// 按模型 `T` 的字段解析 `q` 筛选表达式，未提供时返回空文档
+    ///
+    /// 使用 [`FilterLanguage::for_collection`]：允许 `T::json_schema()` 中的全部字段，
+    /// 标记了 `#[collection(skip_filter)]` 的字段除外。`q` 来自请求参数，
+    /// 密码哈希等不应被探测的字段必须标记 `skip_filter`；
+    /// 需要显式的允许列表时直接使用 [`FilterLanguage`]
+    pub fn q_filter<T: Collection>(&self) -> Result<Document, QueryError> {
+        match self.q {
+            Some(ref q) => FilterLanguage::for_collection::<T>().parse(q),
+            None => Ok(Document::new()),
+        }
+    }
+
+    /// 从 URL 查询字符串解析分页参数
+    ///
+    /// 空值视为未提供，未识别的参数保存在 `filters` 中；
+    /// 只检查参数格式，取值范围由 [`validate`](Self::validate) 检查
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let query = PaginatedQuery::from_query_str("page=2&sort_by=name&filters[status]=open")?;
+    /// ```
+    pub fn from_query_str(query: &str) -> Result<Self, QueryError> {
+        let mut parsed = Self::default();
+        let mut extra = HashMap::new();
+        for (key, value) in query_string::pairs(query) {
+            match key.as_ref() {
+                "page" => parsed.page = query_string::parse_u64("page", &value)?,
+                "page_size" => parsed.page_size = query_string::parse_u64("page_size", &value)?,
+                "sort_by" => parsed.sort_by = Some(value.into_owned()),
+                "sort_order" => {
+                    parsed.sort_order = query_string::parse_sort_order("sort_order", &value)?
                 }
-                // 解析 filters.key 格式
-                else if let Some(k) = key.strip_prefix("filters.") {
-                    filters.insert(k.to_string(), value.to_string());
+                "sort" => parsed.sort = SortSpec::parse_list(&value)?,
+                "search" => parsed.search = Some(value.into_owned()),
+                "q" => parsed.q = Some(value.into_owned()),
+                "facets" => parsed.facets = facet::parse_fields(&value),
+                _ => {
+                    extra.insert(key.into_owned(), value.into_owned());
                 }
             }
-            return Some(filters)
         }
-        None
+        parsed.filters = query_string::raw_filters(extra);
+        Ok(parsed)
+    }
+
+    /// 使用全局的 [`PageSizeLimits`] 校验分页参数，返回校验后的查询
+    ///
+    /// Web 框架集成在提取参数后自动调用
+    pub fn validate(self) -> Result<Self, QueryError> {
+        self.validate_with(&PageSizeLimits::global())
     }
-}
 
+    /// 使用指定的限制校验分页参数
+    ///
+    /// `page` 必须从 1 开始，`page_size` 必须在 `limits` 范围内；
+    /// `limits.clamp` 为 `true` 时把超出范围的值截断到边界而不是返回错误
+    pub fn validate_with(mut self, limits: &PageSizeLimits) -> Result<Self, QueryError> {
+        if limits.clamp {
+            self.page = self.page.max(1);
+            self.page_size = self.page_size.max(limits.min).min(limits.max);
+            return Ok(self);
+        }
+        query_string::check_range("page", self.page, 1, u64::MAX)?;
+        query_string::check_range("page_size", self.page_size, limits.min, limits.max)?;
+        Ok(self)
+    }
+}
 
 impl PaginatedQuery {
     /// 计算跳过的记录数
+    ///
+    /// `page` 为 0 时按第 1 页计算，溢出时取 `u64::MAX`
     pub fn skip(&self) -> u64 {
-        (self.page - 1) * self.page_size
+        self.page.saturating_sub(1).saturating_mul(self.page_size)
     }
 
-    /// 计算查询限制数
+    /// 计算查询限制数，超出 `i64` 范围时取 `i64::MAX`
     pub fn limit(&self) -> i64 {
-        self.page_size as i64
+        i64::try_from(self.page_size).unwrap_or(i64::MAX)
+    }
+}
+
+/// 全局分页大小限制
+static GLOBAL_LIMITS: RwLock<PageSizeLimits> = RwLock::new(PageSizeLimits::DEFAULT);
+
+/// 每页数量的取值范围
+///
+/// [`PaginatedQuery::validate`] 使用全局限制，可通过 [`set_global`](Self::set_global) 修改；
+/// `find_paginated` 使用 [`for_collection`](Self::for_collection)，模型的
+/// `#[collection(max_page_size = ...)]` 只能收紧全局最大值，因此提取器通过校验的请求
+/// 只会因模型限制更小而在 `find_paginated` 中失败
+///
+/// # 示例
+///
+/// ```ignore
+/// // 超出范围时截断，而不是返回 400
+/// PageSizeLimits::new(1, 200).clamp(true).set_global();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSizeLimits {
+    /// 最小每页数量
+    pub min: u64,
+    /// 最大每页数量
+    pub max: u64,
+    /// 超出范围时截断到边界，而不是返回错误
+    pub clamp: bool,
+}
+
+impl PageSizeLimits {
+    /// 默认限制：每页 1 到 100 条，超出范围时返回错误
+    pub const DEFAULT: Self = Self {
+        min: 1,
+        max: 100,
+        clamp: false,
+    };
+
+    /// 创建限制，`min` 至少为 1，`max` 不小于 `min`
+    pub const fn new(min: u64, max: u64) -> Self {
+        let min = if min == 0 { 1 } else { min };
+        Self {
+            min,
+            max: if max < min { min } else { max },
+            clamp: false,
+        }
+    }
+
+    /// 设置超出范围时是否截断
+    pub const fn clamp(mut self, clamp: bool) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    /// 当前的全局限制
+    pub fn global() -> Self {
+        *GLOBAL_LIMITS.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 设置全局限制
+    pub fn set_global(self) {
+        *GLOBAL_LIMITS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = self;
+    }
+
+    /// 模型 `T` 的限制：全局限制，最大值取全局最大值与模型 `max_page_size` 中较小者
+    pub fn for_collection<T: Collection>() -> Self {
+        let global = Self::global();
+        match T::max_page_size() {
+            Some(max) => Self::new(global.min, max.min(global.max)).clamp(global.clamp),
+            None => global,
+        }
+    }
+}
+
+impl Default for PageSizeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
     }
 }
 
@@ -81,9 +241,53 @@ pub struct PaginatedData<T> {
     pub page_size: u64,
     /// 总页数
     pub total_pages: u64,
+    /// 是否有下一页
+    pub has_next: bool,
+    /// 是否有上一页
+    pub has_prev: bool,
+    /// 下一页页码
+    pub next_page: Option<u64>,
+    /// 上一页页码
+    pub prev_page: Option<u64>,
+    /// 分面统计：字段名到取值与数量的映射，未请求时为空且不序列化
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub facets: BTreeMap<String, Vec<FacetBucket>>,
 }
 
 impl<T> PaginatedData<T> {
+    /// 根据查询参数与总记录数创建分页数据，并计算总页数与翻页信息
+    pub fn new(items: Vec<T>, total_count: u64, query: &PaginatedQuery) -> Self {
+        let page = query.page;
+        let total_pages = match query.page_size {
+            0 => 0,
+            page_size => total_count.div_ceil(page_size),
+        };
+        let next_page = (page < total_pages).then(|| page + 1);
+        // 超出末页时上一页指向末页
+        let prev_page = (page > 1).then(|| (page - 1).min(total_pages.max(1)));
+
+        Self {
+            items,
+            total_count,
+            page,
+            page_size: query.page_size,
+            total_pages,
+            has_next: next_page.is_some(),
+            has_prev: prev_page.is_some(),
+            next_page,
+            prev_page,
+            facets: BTreeMap::new(),
+        }
+    }
+
+    /// 生成翻页链接，`base_url` 为当前资源的地址（如 `https://api.example.com/users`）
+    ///
+    /// 链接保留 `query` 中的每页数量、排序、搜索与筛选参数，只替换页码；
+    /// 需要自定义时使用 [`PaginationLinks`]
+    pub fn links(&self, base_url: &str, query: &PaginatedQuery) -> PageLinks {
+        PaginationLinks::new(base_url).query(query).build(self)
+    }
+
     /// 转换数据类型
     ///
     /// 使用泛型闭包对每个元素进行转换
@@ -97,6 +301,11 @@ impl<T> PaginatedData<T> {
             page: self.page,
             page_size: self.page_size,
             total_pages: self.total_pages,
+            has_next: self.has_next,
+            has_prev: self.has_prev,
+            next_page: self.next_page,
+            prev_page: self.prev_page,
+            facets: self.facets,
         }
     }
 
@@ -117,6 +326,11 @@ impl<T> PaginatedData<T> {
             page: self.page,
             page_size: self.page_size,
             total_pages: self.total_pages,
+            has_next: self.has_next,
+            has_prev: self.has_prev,
+            next_page: self.next_page,
+            prev_page: self.prev_page,
+            facets: self.facets,
         })
     }
 }
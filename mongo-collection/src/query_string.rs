@@ -0,0 +1,248 @@
+//! 查询字符串解析
+//!
+//! `PaginatedQuery` 与 `ListQuery` 的 `from_query_str` 不依赖 serde 解析查询字符串：
+//! 数字参数可以与任意筛选参数共存，空值视为未提供，并支持嵌套的 `filters[a][b]`。
+//! Web 框架集成（`axum`、`actix` feature）都基于这里的解析结果。
+
+use crate::SortOrder;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// 查询参数错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// 参数值无法解析
+    InvalidValue {
+        /// 参数名
+        field: String,
+        /// 原始值
+        value: String,
+        /// 期望的格式
+        expected: &'static str,
+    },
+    /// 参数值超出范围
+    OutOfRange {
+        /// 参数名
+        field: String,
+        /// 实际值
+        value: u64,
+        /// 最小值
+        min: u64,
+        /// 最大值
+        max: u64,
+    },
+    /// 字段不存在或不允许用于筛选
+    UnknownField {
+        /// 字段路径
+        field: String,
+    },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::InvalidValue {
+                field,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value `{value}` for `{field}`: expected {expected}"
+            ),
+            QueryError::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } => write!(f, "`{field}` must be between {min} and {max}, got {value}"),
+            QueryError::UnknownField { field } => write!(f, "unknown filter field `{field}`"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// 解码后的键值对，跳过空值
+pub(crate) fn pairs(query: &str) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+    form_urlencoded::parse(query.trim_start_matches('?').as_bytes())
+        .filter(|(_, value)| !value.is_empty())
+}
+
+/// 解析正整数参数
+pub(crate) fn parse_u64(field: &str, value: &str) -> Result<u64, QueryError> {
+    value.trim().parse().map_err(|_| QueryError::InvalidValue {
+        field: field.to_string(),
+        value: value.to_string(),
+        expected: "a non-negative integer",
+    })
+}
+
+/// 解析排序方向（不区分大小写）
+pub(crate) fn parse_sort_order(field: &str, value: &str) -> Result<SortOrder, QueryError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "asc" => Ok(SortOrder::Asc),
+        "desc" => Ok(SortOrder::Desc),
+        _ => Err(QueryError::InvalidValue {
+            field: field.to_string(),
+            value: value.to_string(),
+            expected: "`asc` or `desc`",
+        }),
+    }
+}
+
+/// 检查参数是否在 `min..=max` 内
+pub(crate) fn check_range(field: &str, value: u64, min: u64, max: u64) -> Result<(), QueryError> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(QueryError::OutOfRange {
+            field: field.to_string(),
+            value,
+            min,
+            max,
+        })
+    }
+}
+
+/// 未识别的参数作为原始筛选参数保存，没有时为 `None`
+pub(crate) fn raw_filters(extra: HashMap<String, String>) -> Option<HashMap<String, String>> {
+    (!extra.is_empty()).then_some(extra)
+}
+
+/// 将筛选参数名转换为点号路径
+///
+/// `filters[status]`、`filters.status` 转换为 `status`，
+/// `filters[author][name]`、`filters.author.name` 转换为 `author.name`；
+/// 其它参数名或格式错误时返回 `None`
+pub(crate) fn filter_path(key: &str) -> Option<String> {
+    if let Some(path) = key.strip_prefix("filters.") {
+        return (!path.is_empty() && path.split('.').all(|s| !s.is_empty()))
+            .then(|| path.to_string());
+    }
+
+    let mut rest = key.strip_prefix("filters")?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        let (segment, tail) = rest.strip_prefix('[')?.split_once(']')?;
+        if segment.is_empty() {
+            return None;
+        }
+        segments.push(segment);
+        rest = tail;
+    }
+    (!segments.is_empty()).then(|| segments.join("."))
+}
+
+/// 从原始筛选参数中提取 `filters[...]` / `filters.` 参数
+pub(crate) fn parse_filters(
+    raw_filters: &Option<HashMap<String, String>>,
+) -> Option<HashMap<String, String>> {
+    raw_filters.as_ref().map(|raw_filters| {
+        raw_filters
+            .iter()
+            .filter_map(|(key, value)| Some((filter_path(key)?, value.clone())))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ListQuery, PageSizeLimits, PaginatedQuery};
+
+    #[test]
+    fn test_filter_path() {
+        assert_eq!(filter_path("filters[status]").as_deref(), Some("status"));
+        assert_eq!(filter_path("filters.status").as_deref(), Some("status"));
+        assert_eq!(
+            filter_path("filters[author][name]").as_deref(),
+            Some("author.name")
+        );
+        assert_eq!(
+            filter_path("filters.author.name").as_deref(),
+            Some("author.name")
+        );
+        assert_eq!(filter_path("filters[status"), None);
+        assert_eq!(filter_path("filters[]"), None);
+        assert_eq!(filter_path("filters"), None);
+        assert_eq!(filter_path("status"), None);
+    }
+
+    #[test]
+    fn test_paginated_query_from_query_str() {
+        let query = PaginatedQuery::from_query_str(
+            "page=2&page_size=20&sort_by=name&sort_order=ASC&search=a%20b\
+             &filters[author][name]=alice&filters.status=open&page_token=",
+        )
+        .unwrap();
+        assert_eq!(query.page, 2);
+        assert_eq!(query.page_size, 20);
+        assert_eq!(query.sort_by.as_deref(), Some("name"));
+        assert!(matches!(query.sort_order, SortOrder::Asc));
+        assert_eq!(query.search.as_deref(), Some("a b"));
+
+        let filters = query.parsed_filters().unwrap();
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters["author.name"], "alice");
+        assert_eq!(filters["status"], "open");
+
+        // 空值视为未提供
+        let query = PaginatedQuery::from_query_str("page=&page_size=").unwrap();
+        assert_eq!((query.page, query.page_size), (1, 10));
+        assert!(query.filters.is_none());
+    }
+
+    #[test]
+    fn test_paginated_query_rejects_bad_input() {
+        assert!(matches!(
+            PaginatedQuery::from_query_str("page=abc"),
+            Err(QueryError::InvalidValue { ref field, .. }) if field == "page"
+        ));
+        assert!(PaginatedQuery::from_query_str("sort_order=up").is_err());
+    }
+
+    #[test]
+    fn test_validate_page_limits() {
+        let query = |page, page_size| PaginatedQuery {
+            page,
+            page_size,
+            ..Default::default()
+        };
+        let limits = PageSizeLimits::new(5, 50);
+
+        assert!(query(1, 50).validate_with(&limits).is_ok());
+        assert!(matches!(
+            query(0, 10).validate_with(&limits),
+            Err(QueryError::OutOfRange { ref field, .. }) if field == "page"
+        ));
+        assert_eq!(
+            query(1, 51).validate_with(&limits).unwrap_err(),
+            QueryError::OutOfRange {
+                field: "page_size".to_string(),
+                value: 51,
+                min: 5,
+                max: 50,
+            }
+        );
+
+        let clamped = query(0, 1000).validate_with(&limits.clamp(true)).unwrap();
+        assert_eq!((clamped.page, clamped.page_size), (1, 50));
+        let clamped = query(2, 1).validate_with(&limits.clamp(true)).unwrap();
+        assert_eq!(clamped.page_size, 5);
+
+        // page 为 0 或数值过大时不会溢出
+        assert_eq!(query(0, 10).skip(), 0);
+        assert_eq!(query(u64::MAX, 10).skip(), u64::MAX);
+        assert_eq!(query(1, u64::MAX).limit(), i64::MAX);
+    }
+
+    #[test]
+    fn test_list_query_from_query_str() {
+        let query = ListQuery::from_query_str("sort_by=name&page=x&filters[tag]=rust").unwrap();
+        assert_eq!(query.sort_by.as_deref(), Some("name"));
+        // 列表查询没有分页参数，page 作为普通参数保留
+        assert_eq!(query.filters.as_ref().unwrap()["page"], "x");
+        assert_eq!(query.parsed_filters().unwrap()["tag"], "rust");
+    }
+}
@@ -0,0 +1,110 @@
+//! 模型注册表
+//!
+//! `#[derive(Collection)]` 会把每个模型的 [`ModelInfo`] 注册到全局列表中，
+//! 启动时可以通过 [`all`] 遍历所有模型，用于同步索引、校验器或执行迁移。
+//!
+//! 注册在程序启动时由链接器完成，只包含最终二进制中链接进来的模型。
+//! 手动实现 `Collection` 的类型不会自动注册，可以使用 [`register!`](crate::register) 注册。
+
+use crate::ModelInfo;
+
+inventory::collect!(ModelInfo);
+
+/// 所有已注册的模型，按数据库与集合名排序
+///
+/// # 示例
+///
+/// ```ignore
+/// for model in mongo_collection::registry::all() {
+///     let db = client.database(model.database_name().unwrap_or("app"));
+///     model.apply(&db, false).await?;
+/// }
+/// ```
+pub fn all() -> Vec<&'static ModelInfo> {
+    let mut models: Vec<_> = inventory::iter::<ModelInfo>.into_iter().collect();
+    models.sort_by_key(|model| (model.database_name(), model.name()));
+    models
+}
+
+/// 按集合名查找已注册的模型
+pub fn find(name: &str) -> Option<&'static ModelInfo> {
+    inventory::iter::<ModelInfo>
+        .into_iter()
+        .find(|model| model.name() == name)
+}
+
+/// 注册手动实现 `Collection` 的类型
+///
+/// ```ignore
+/// impl Collection for AuditLog { ... }
+/// mongo_collection::register!(AuditLog);
+/// ```
+#[macro_export]
+macro_rules! register {
+    ($model:ty) => {
+        $crate::__private::inventory::submit! {
+            $crate::ModelInfo::of::<$model>()
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collection, FieldInfo};
+    use serde::{Deserialize, Serialize};
+
+    #[allow(dead_code)]
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[collection(name = "registry_widgets", db = "registry_test")]
+    struct Widget {
+        #[serde(rename = "_id")]
+        id: String,
+        #[collection(index)]
+        label: Option<String>,
+    }
+
+    struct Manual;
+
+    impl Collection for Manual {
+        fn name() -> &'static str {
+            "registry_manual"
+        }
+    }
+
+    crate::register!(Manual);
+
+    #[test]
+    fn test_registry() {
+        let widget = find("registry_widgets").unwrap();
+        assert_eq!(widget.database_name(), Some("registry_test"));
+        assert_eq!(widget.indexes().len(), 1);
+        assert_eq!(
+            widget.fields(),
+            [
+                FieldInfo {
+                    name: "_id",
+                    bson_types: &["string"],
+                    required: true,
+                },
+                FieldInfo {
+                    name: "label",
+                    bson_types: &["string", "null"],
+                    required: false,
+                },
+            ]
+        );
+
+        assert!(find("registry_manual").unwrap().fields().is_empty());
+        assert!(find("registry_missing").is_none());
+
+        let models = all();
+        let names: Vec<_> = models.iter().map(|m| m.name()).collect();
+        assert!(names.contains(&"registry_widgets") && names.contains(&"registry_manual"));
+        let keys: Vec<_> = models
+            .iter()
+            .map(|m| (m.database_name(), m.name()))
+            .collect();
+        assert!(keys.is_sorted());
+    }
+}
@@ -1,14 +1,32 @@
+mod instance;
+
+#[cfg(feature = "mock")]
+pub use instance::MockRepository;
+pub use instance::{MongoRepository, Repository};
+
+use crate::Collection;
+use crate::backend::{Backend, CollectionTarget, ensure_scoped};
+use crate::explain::QueryPlan;
+use crate::export::{self, ExportOptions};
+use crate::facet;
+use crate::import::{self, ImportFormat, ImportOptions, ImportReport};
+use crate::instrument::{self, Operation};
 use crate::list::{ListData, ListQuery};
-use crate::paginated::{PaginatedData, PaginatedQuery};
+use crate::paginated::{PageSizeLimits, PaginatedData, PaginatedQuery};
 use crate::utils::parse_object_id;
-use crate::{Collection, SortOrder};
+use crate::validator::{self, ValidatorOptions};
+use crate::watch::{self, ChangeEvent, WatchOptions};
 use async_trait::async_trait;
-use mongodb::bson::{Document, doc};
-use mongodb::options::FindOptions;
+use futures::io::{AsyncBufRead, AsyncWrite};
+use futures::stream::BoxStream;
+use mongodb::bson::{Document, deserialize_from_document, doc, serialize_to_document};
+use mongodb::options::{CreateCollectionOptions, FindOptions};
 
 /// 通用集合仓储 Trait
 ///
 /// 提供基础的 CRUD 操作接口
+/// 所有方法对存储后端泛型化：既可以传入 `mongodb::Database`，
+/// 也可以传入 [`MemoryBackend`](crate::backend::MemoryBackend) 等其它 [`Backend`] 实现
 /// 注意：所有错误使用 MongoDB 原生错误类型 `mongodb::error::Error`
 #[async_trait]
 pub trait CollectionRepository: Collection + Clone + Send + Sync + Unpin
@@ -18,247 +36,514 @@ where
     // ========== 创建操作 ==========
 
     /// 创建单个文档
-    async fn create(
-        db: &mongodb::Database,
+    async fn create<B: Backend + ?Sized>(
+        db: &B,
         document: &Self,
     ) -> Result<Self, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        collection.insert_one(document).await?;
-        Ok(document.clone())
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("create", &target)
+            .run(instrument::one, async {
+                db.insert_one(&target, serialize_to_document(document)?)
+                    .await?;
+                Ok(document.clone())
+            })
+            .await
     }
 
     /// 批量创建文档
-    async fn create_many(
-        db: &mongodb::Database,
+    async fn create_many<B: Backend + ?Sized>(
+        db: &B,
         documents: Vec<Self>,
     ) -> Result<Vec<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        collection.insert_many(&documents).await?;
-        Ok(documents)
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("create_many", &target)
+            .run(instrument::len, async {
+                let raw = documents
+                    .iter()
+                    .map(serialize_to_document)
+                    .collect::<Result<Vec<_>, _>>()?;
+                db.insert_many(&target, raw).await?;
+                Ok(documents)
+            })
+            .await
     }
 
     // ========== 查询操作 ==========
 
     /// 根据 ID 查找文档
-    async fn find_by_id(
-        db: &mongodb::Database,
+    async fn find_by_id<B: Backend + ?Sized>(
+        db: &B,
         id: &str,
     ) -> Result<Option<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
         let oid = parse_object_id(id)?;
-
-        collection.find_one(doc! { "_id": oid }).await
+        Self::find_one(db, doc! { "_id": oid }).await
     }
 
     /// 根据条件查找单个文档
-    async fn find_one(
-        db: &mongodb::Database,
+    async fn find_one<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
     ) -> Result<Option<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        collection.find_one(filter).await
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("find_one", &target)
+            .filter(&filter)
+            .run(instrument::some, async {
+                db.find_one(&target, filter)
+                    .await?
+                    .map(deserialize_from_document)
+                    .transpose()
+                    .map_err(Into::into)
+            })
+            .await
     }
 
     /// 根据条件查找多个文档
-    async fn find_many(
-        db: &mongodb::Database,
+    async fn find_many<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
         options: Option<FindOptions>,
     ) -> Result<Vec<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        let mut cursor = match options {
-            None => collection.find(filter).await?,
-            Some(options) => collection.find(filter).with_options(options).await?,
-        };
-
-        let mut results = Vec::new();
-        use futures::TryStreamExt;
-        while let Some(doc) = cursor.try_next().await? {
-            results.push(doc);
-        }
-
-        Ok(results)
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("find_many", &target)
+            .filter(&filter)
+            .find_options(options.as_ref())
+            .run(instrument::len, async {
+                let documents = db.find(&target, filter, options).await?;
+
+                let mut results = Vec::with_capacity(documents.len());
+                for document in documents {
+                    results.push(deserialize_from_document(document)?);
+                }
+
+                Ok(results)
+            })
+            .await
     }
 
     /// 查找所有文档
-    async fn find_all(db: &mongodb::Database) -> Result<Vec<Self>, mongodb::error::Error> {
+    async fn find_all<B: Backend + ?Sized>(db: &B) -> Result<Vec<Self>, mongodb::error::Error> {
         Self::find_many(db, doc! {}, None).await
     }
 
     /// 分页查询
-    async fn find_paginated(
-        db: &mongodb::Database,
+    ///
+    /// 先按 [`PageSizeLimits::for_collection`] 校验 `query`，参数无效时返回的错误
+    /// 可通过 `error.get_custom::<QueryError>()` 取得 [`QueryError`](crate::QueryError)。
+    ///
+    /// `query.facets` 非空时，当前页、总数与各字段的分面统计在同一个 `$facet` 聚合中计算，
+    /// 统计结果保存在 [`PaginatedData::facets`]；分面字段必须标记了 `#[collection(facet)]`
+    async fn find_paginated<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
         query: &PaginatedQuery,
     ) -> Result<PaginatedData<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
-
-        // 获取总数
-        let total_count = collection.count_documents(filter.clone()).await?;
-
-        // 构建排序选项
-        let sort_doc = if let Some(ref sort_by) = query.sort_by {
-            let sort_value = match query.sort_order {
-                SortOrder::Asc => 1,
-                SortOrder::Desc => -1,
-            };
-            doc! { sort_by: sort_value }
-        } else {
-            doc! { "_id": -1 } // 默认按 ID 降序
-        };
-
-        // 构建查询选项
-        let find_options = FindOptions::builder()
-            .skip(query.skip())
-            .limit(query.limit())
-            .sort(sort_doc)
-            .build();
-
-        // 执行查询
-        let items = Self::find_many(db, filter, Some(find_options)).await?;
-
-        // 计算总页数
-        let total_pages = (total_count as f64 / query.page_size as f64).ceil() as u64;
-
-        Ok(PaginatedData {
-            items,
-            total_count,
-            page: query.page,
-            page_size: query.page_size,
-            total_pages,
-        })
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("find_paginated", &target)
+            .filter(&filter)
+            .run(
+                |data: &PaginatedData<Self>| data.items.len() as u64,
+                async {
+                    // 校验分页参数
+                    let query = &query
+                        .clone()
+                        .validate_with(&PageSizeLimits::for_collection::<Self>())
+                        .map_err(mongodb::error::Error::custom)?;
+
+                    if !query.facets.is_empty() {
+                        return facet::find_paginated(db, &target, filter, query).await;
+                    }
+
+                    // 获取总数
+                    let total_count = Self::count(db, filter.clone()).await?;
+
+                    // 构建查询选项
+                    let find_options = FindOptions::builder()
+                        .skip(query.skip())
+                        .limit(query.limit())
+                        .sort(query.sort_document())
+                        .build();
+
+                    // 执行查询
+                    let items = Self::find_many(db, filter, Some(find_options)).await?;
+
+                    Ok(PaginatedData::new(items, total_count, query))
+                },
+            )
+            .await
     }
 
     /// 列表查询（不分页）
-    async fn find_list(
-        db: &mongodb::Database,
+    async fn find_list<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
         query: &ListQuery,
     ) -> Result<ListData<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
-
-        // 获取总数
-        let total_count = collection.count_documents(filter.clone()).await?;
-
-        // 构建排序选项
-        let sort_doc = if let Some(ref sort_by) = query.sort_by {
-            let sort_value = match query.sort_order {
-                SortOrder::Asc => 1,
-                SortOrder::Desc => -1,
-            };
-            doc! { sort_by: sort_value }
-        } else {
-            doc! { "_id": -1 } // 默认按 ID 降序
-        };
-
-        // 构建查询选项
-        let find_options = FindOptions::builder().sort(sort_doc).build();
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("find_list", &target)
+            .filter(&filter)
+            .run(|data: &ListData<Self>| data.items.len() as u64, async {
+                // 获取总数
+                let total_count = Self::count(db, filter.clone()).await?;
+
+                // 构建查询选项
+                let find_options = FindOptions::builder().sort(query.sort_document()).build();
+
+                // 执行查询
+                let items = Self::find_many(db, filter, Some(find_options)).await?;
+
+                Ok(ListData { items, total_count })
+            })
+            .await
+    }
 
-        // 执行查询
-        let items = Self::find_many(db, filter, Some(find_options)).await?;
+    /// 导出匹配 `filter` 的全部文档，排序与 [`find_list`](Self::find_list) 相同
+    ///
+    /// 按 `options.batch_size` 分批读取并写入 `writer`，不会一次性加载整个集合；
+    /// 返回导出的文档数
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let options = ExportOptions::new(ExportFormat::Csv);
+    /// User::export(&db, doc! { "active": true }, &query, &options, &mut writer).await?;
+    /// ```
+    async fn export<B, W>(
+        db: &B,
+        filter: Document,
+        query: &ListQuery,
+        options: &ExportOptions,
+        writer: &mut W,
+    ) -> Result<u64, mongodb::error::Error>
+    where
+        B: Backend + ?Sized,
+        W: AsyncWrite + Unpin + Send + ?Sized,
+    {
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("export", &target)
+            .filter(&filter)
+            .run(instrument::count, async {
+                export::export::<Self, _, _>(
+                    db,
+                    &target,
+                    filter,
+                    query.sort_document(),
+                    options,
+                    writer,
+                )
+                .await
+            })
+            .await
+    }
 
-        Ok(ListData { items, total_count })
+    /// 从 NDJSON 或 CSV 批量导入文档
+    ///
+    /// 每条记录反序列化为 `Self` 并经 `options` 的校验函数检查后，按 `options.batch_size`
+    /// 分批插入；设置 `upsert_by` 时按键更新已有文档。无效记录与写入失败按行号记录在
+    /// 报告中，读取或连接失败时返回错误
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let mut reader = futures::io::BufReader::new(file);
+    /// let report = User::import(&db, &mut reader, ImportFormat::Csv, &ImportOptions::new()).await?;
+    /// for error in &report.errors {
+    ///     eprintln!("{error}");
+    /// }
+    /// ```
+    async fn import<B, R>(
+        db: &B,
+        reader: &mut R,
+        format: ImportFormat,
+        options: &ImportOptions<Self>,
+    ) -> Result<ImportReport, mongodb::error::Error>
+    where
+        B: Backend + ?Sized,
+        R: AsyncBufRead + Unpin + Send + ?Sized,
+    {
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("import", &target)
+            .run(
+                |report: &ImportReport| report.inserted + report.updated,
+                import::import(db, &target, reader, format, options),
+            )
+            .await
     }
 
     /// 统计文档数量
-    async fn count(db: &mongodb::Database, filter: Document) -> Result<u64, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        collection.count_documents(filter).await
+    async fn count<B: Backend + ?Sized>(
+        db: &B,
+        filter: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("count", &target)
+            .filter(&filter)
+            .run(instrument::none, db.count_documents(&target, filter))
+            .await
     }
 
     /// 检查文档是否存在
-    async fn exists(
-        db: &mongodb::Database,
+    async fn exists<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
     ) -> Result<bool, mongodb::error::Error> {
-        Ok(Self::find_one(db, filter).await?.is_some())
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("exists", &target)
+            .filter(&filter)
+            .run(instrument::none, async {
+                Ok(db.find_one(&target, filter).await?.is_some())
+            })
+            .await
+    }
+
+    /// 返回查询的获胜执行计划
+    ///
+    /// 参数与 [`find_many`](Self::find_many) 相同，查询本身不会执行
+    async fn explain<B: Backend + ?Sized>(
+        db: &B,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<QueryPlan, mongodb::error::Error> {
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("explain", &target)
+            .filter(&filter)
+            .find_options(options.as_ref())
+            .run(instrument::none, async {
+                QueryPlan::from_explain(&db.explain(&target, filter, options).await?)
+            })
+            .await
     }
 
     // ========== 更新操作 ==========
 
     /// 根据 ID 更新文档
-    async fn update_by_id(
-        db: &mongodb::Database,
+    async fn update_by_id<B: Backend + ?Sized>(
+        db: &B,
         id: &str,
         update: Document,
     ) -> Result<bool, mongodb::error::Error> {
-        let collection = Self::collection(db);
         let oid = parse_object_id(id)?;
-
-        let result = collection.update_one(doc! { "_id": oid }, update).await?;
-
-        Ok(result.modified_count > 0)
+        Self::update_one(db, doc! { "_id": oid }, update).await
     }
 
     /// 根据条件更新单个文档
-    async fn update_one(
-        db: &mongodb::Database,
+    async fn update_one<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
         update: Document,
     ) -> Result<bool, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        let result = collection.update_one(filter, update).await?;
-        Ok(result.modified_count > 0)
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("update_one", &target)
+            .filter(&filter)
+            .run(instrument::hit, async {
+                let modified_count = db.update_one(&target, filter, update).await?;
+                Ok(modified_count > 0)
+            })
+            .await
     }
 
     /// 根据条件更新多个文档
-    async fn update_many(
-        db: &mongodb::Database,
+    ///
+    /// 多租户模型必须使用租户限定的后端，或在过滤条件中指定租户字段
+    async fn update_many<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
         update: Document,
     ) -> Result<u64, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        let result = collection.update_many(filter, update).await?;
-        Ok(result.modified_count)
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("update_many", &target)
+            .filter(&filter)
+            .run(instrument::count, async {
+                ensure_scoped(db, &target, &filter, "update_many")?;
+                db.update_many(&target, filter, update).await
+            })
+            .await
     }
 
     /// 查找并更新文档（返回更新后的文档）
-    async fn find_one_and_update(
-        db: &mongodb::Database,
+    async fn find_one_and_update<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
         update: Document,
     ) -> Result<Option<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        collection.find_one_and_update(filter, update).await
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("find_one_and_update", &target)
+            .filter(&filter)
+            .run(instrument::some, async {
+                db.find_one_and_update(&target, filter, update)
+                    .await?
+                    .map(deserialize_from_document)
+                    .transpose()
+                    .map_err(Into::into)
+            })
+            .await
     }
 
     // ========== 删除操作 ==========
 
     /// 根据 ID 删除文档
-    async fn delete_by_id(db: &mongodb::Database, id: &str) -> Result<bool, mongodb::error::Error> {
-        let collection = Self::collection(db);
+    async fn delete_by_id<B: Backend + ?Sized>(
+        db: &B,
+        id: &str,
+    ) -> Result<bool, mongodb::error::Error> {
         let oid = parse_object_id(id)?;
-
-        let result = collection.delete_one(doc! { "_id": oid }).await?;
-        Ok(result.deleted_count > 0)
+        Self::delete_one(db, doc! { "_id": oid }).await
     }
 
     /// 根据条件删除单个文档
-    async fn delete_one(
-        db: &mongodb::Database,
+    async fn delete_one<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
     ) -> Result<bool, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        let result = collection.delete_one(filter).await?;
-        Ok(result.deleted_count > 0)
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("delete_one", &target)
+            .filter(&filter)
+            .run(instrument::hit, async {
+                let deleted_count = db.delete_one(&target, filter).await?;
+                Ok(deleted_count > 0)
+            })
+            .await
     }
 
     /// 根据条件删除多个文档
-    async fn delete_many(
-        db: &mongodb::Database,
+    ///
+    /// 多租户模型必须使用租户限定的后端，或在过滤条件中指定租户字段
+    async fn delete_many<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
     ) -> Result<u64, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        let result = collection.delete_many(filter).await?;
-        Ok(result.deleted_count)
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("delete_many", &target)
+            .filter(&filter)
+            .run(instrument::count, async {
+                ensure_scoped(db, &target, &filter, "delete_many")?;
+                db.delete_many(&target, filter).await
+            })
+            .await
     }
 
     /// 查找并删除文档（返回被删除的文档）
-    async fn find_one_and_delete(
-        db: &mongodb::Database,
+    async fn find_one_and_delete<B: Backend + ?Sized>(
+        db: &B,
         filter: Document,
     ) -> Result<Option<Self>, mongodb::error::Error> {
-        let collection = Self::collection(db);
-        collection.find_one_and_delete(filter).await
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("find_one_and_delete", &target)
+            .filter(&filter)
+            .run(instrument::some, async {
+                db.find_one_and_delete(&target, filter)
+                    .await?
+                    .map(deserialize_from_document)
+                    .transpose()
+                    .map_err(Into::into)
+            })
+            .await
+    }
+
+    // ========== 变更流 ==========
+
+    /// 监听集合变更，返回类型化的事件流
+    ///
+    /// `pipeline` 为追加在变更流上的聚合阶段（如 `$match`），可以为空。
+    /// 集合由 [`Backend::resolve`] 解析；后端限定了租户时，变更流先按
+    /// `fullDocument.<tenant_field>` 过滤，并为更新事件查询完整文档，
+    /// 因此只会收到该租户的插入、更新与替换事件，删除事件不会投递
+    ///
+    /// # 示例
+    ///
+    /// ```ignore
+    /// let mut events = Order::watch(&db, vec![doc! { "$match": { "operationType": "insert" } }]).await?;
+    /// while let Some(event) = events.try_next().await? {
+    ///     if let ChangeEvent::Insert(order) = event { /* ... */ }
+    /// }
+    /// ```
+    async fn watch<B: Backend + ?Sized>(
+        db: &B,
+        pipeline: Vec<Document>,
+    ) -> Result<
+        BoxStream<'static, Result<ChangeEvent<Self>, mongodb::error::Error>>,
+        mongodb::error::Error,
+    >
+    where
+        Self: 'static,
+    {
+        Self::watch_with(db, pipeline, WatchOptions::default()).await
+    }
+
+    /// 按选项监听集合变更，可通过 [`WatchOptions::resume_with`] 持久化恢复令牌
+    async fn watch_with<B: Backend + ?Sized>(
+        db: &B,
+        pipeline: Vec<Document>,
+        options: WatchOptions,
+    ) -> Result<
+        BoxStream<'static, Result<ChangeEvent<Self>, mongodb::error::Error>>,
+        mongodb::error::Error,
+    >
+    where
+        Self: 'static,
+    {
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("watch", &target)
+            .run(instrument::none, async {
+                let resolved = db.resolve(&target)?;
+                let (pipeline, lookup) = watch::scope_pipeline(&resolved, pipeline);
+                watch::open(resolved.collection(), pipeline, lookup, options).await
+            })
+            .await
+    }
+
+    // ========== 集合管理 ==========
+
+    /// 创建集合并应用 `$jsonSchema` 校验器
+    ///
+    /// 校验器由 `Collection::json_schema()` 生成；集合已存在时返回服务器错误，
+    /// 此时应使用 [`sync_validator`](Self::sync_validator)。
+    /// 集合名与数据库由 [`Backend::resolve`] 解析，与 CRUD 方法访问同一个集合
+    async fn create_collection_with_validator<B: Backend + ?Sized>(
+        db: &B,
+        options: &ValidatorOptions,
+    ) -> Result<(), mongodb::error::Error> {
+        let create_options = CreateCollectionOptions::builder()
+            .validator(validator::validator_document::<Self>())
+            .validation_level(options.validation_level.clone())
+            .validation_action(options.validation_action.clone())
+            .build();
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("create_collection_with_validator", &target)
+            .run(instrument::none, async {
+                let resolved = db.resolve(&target)?;
+                resolved
+                    .database
+                    .create_collection(resolved.name())
+                    .with_options(create_options)
+                    .await
+            })
+            .await
+    }
+
+    /// 通过 `collMod` 将校验器同步为当前模型的 `$jsonSchema`
+    ///
+    /// 集合不存在时会直接创建集合
+    async fn sync_validator<B: Backend + ?Sized>(
+        db: &B,
+        options: &ValidatorOptions,
+    ) -> Result<(), mongodb::error::Error> {
+        let target = CollectionTarget::of::<Self>();
+        Operation::new("sync_validator", &target)
+            .run(instrument::none, async {
+                let resolved = db.resolve(&target)?;
+                let command = validator::coll_mod_command(
+                    resolved.name(),
+                    validator::validator_document::<Self>(),
+                    options,
+                )?;
+                match resolved.database.run_command(command).await {
+                    Ok(_) => Ok(()),
+                    Err(e) if validator::is_namespace_not_found(&e) => {
+                        Self::create_collection_with_validator(db, options).await
+                    }
+                    Err(e) => Err(e),
+                }
+            })
+            .await
     }
 }
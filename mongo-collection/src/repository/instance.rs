@@ -0,0 +1,329 @@
+use crate::backend::Backend;
+use crate::list::{ListData, ListQuery};
+use crate::paginated::{PaginatedData, PaginatedQuery};
+use crate::repository::CollectionRepository;
+use async_trait::async_trait;
+use mongodb::bson::Document;
+use mongodb::options::FindOptions;
+use std::marker::PhantomData;
+
+/// 基于实例的仓储 Trait
+///
+/// 与 `CollectionRepository` 的静态方法一一对应，但通过 `&self` 调用，
+/// 因此可以作为 `Arc<dyn Repository<User>>` 注入到服务中，并在测试中替换为 Mock。
+/// 启用 `mock` feature 后会生成 `MockRepository<T>`（基于 `mockall`）。
+///
+/// # 示例
+///
+/// ```ignore
+/// struct UserService {
+///     users: Arc<dyn Repository<User>>,
+/// }
+///
+/// let service = UserService {
+///     users: Arc::new(MongoRepository::<User>::new(db)),
+/// };
+/// ```
+#[cfg_attr(feature = "mock", mockall::automock)]
+#[async_trait]
+pub trait Repository<T: Send + Sync>: Send + Sync {
+    // ========== 创建操作 ==========
+
+    /// 创建单个文档
+    async fn create(&self, document: &T) -> Result<T, mongodb::error::Error>;
+
+    /// 批量创建文档
+    async fn create_many(&self, documents: Vec<T>) -> Result<Vec<T>, mongodb::error::Error>;
+
+    // ========== 查询操作 ==========
+
+    /// 根据 ID 查找文档
+    async fn find_by_id(&self, id: &str) -> Result<Option<T>, mongodb::error::Error>;
+
+    /// 根据条件查找单个文档
+    async fn find_one(&self, filter: Document) -> Result<Option<T>, mongodb::error::Error>;
+
+    /// 根据条件查找多个文档
+    async fn find_many(
+        &self,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<T>, mongodb::error::Error>;
+
+    /// 查找所有文档
+    async fn find_all(&self) -> Result<Vec<T>, mongodb::error::Error>;
+
+    /// 分页查询
+    async fn find_paginated(
+        &self,
+        filter: Document,
+        query: &PaginatedQuery,
+    ) -> Result<PaginatedData<T>, mongodb::error::Error>;
+
+    /// 列表查询（不分页）
+    async fn find_list(
+        &self,
+        filter: Document,
+        query: &ListQuery,
+    ) -> Result<ListData<T>, mongodb::error::Error>;
+
+    /// 统计文档数量
+    async fn count(&self, filter: Document) -> Result<u64, mongodb::error::Error>;
+
+    /// 检查文档是否存在
+    async fn exists(&self, filter: Document) -> Result<bool, mongodb::error::Error>;
+
+    // ========== 更新操作 ==========
+
+    /// 根据 ID 更新文档
+    async fn update_by_id(&self, id: &str, update: Document)
+    -> Result<bool, mongodb::error::Error>;
+
+    /// 根据条件更新单个文档
+    async fn update_one(
+        &self,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error>;
+
+    /// 根据条件更新多个文档
+    async fn update_many(
+        &self,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error>;
+
+    /// 查找并更新文档
+    async fn find_one_and_update(
+        &self,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<T>, mongodb::error::Error>;
+
+    // ========== 删除操作 ==========
+
+    /// 根据 ID 删除文档
+    async fn delete_by_id(&self, id: &str) -> Result<bool, mongodb::error::Error>;
+
+    /// 根据条件删除单个文档
+    async fn delete_one(&self, filter: Document) -> Result<bool, mongodb::error::Error>;
+
+    /// 根据条件删除多个文档
+    async fn delete_many(&self, filter: Document) -> Result<u64, mongodb::error::Error>;
+
+    /// 查找并删除文档（返回被删除的文档）
+    async fn find_one_and_delete(
+        &self,
+        filter: Document,
+    ) -> Result<Option<T>, mongodb::error::Error>;
+}
+
+/// 持有存储后端的仓储实例
+///
+/// 所有操作委托给 `T` 的 `CollectionRepository` 实现；
+/// 后端默认为 `mongodb::Database`，也可以使用 `MemoryBackend` 等其它实现
+pub struct MongoRepository<T, B = mongodb::Database> {
+    backend: B,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T, B> MongoRepository<T, B> {
+    /// 创建仓储实例
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 返回底层存储后端
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+}
+
+impl<T, B: Clone> Clone for MongoRepository<T, B> {
+    fn clone(&self) -> Self {
+        Self::new(self.backend.clone())
+    }
+}
+
+impl<T, B: std::fmt::Debug> std::fmt::Debug for MongoRepository<T, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MongoRepository")
+            .field("collection", &std::any::type_name::<T>())
+            .field("backend", &self.backend)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<T, B> Repository<T> for MongoRepository<T, B>
+where
+    T: CollectionRepository + serde::Serialize + for<'de> serde::Deserialize<'de>,
+    B: Backend,
+{
+    async fn create(&self, document: &T) -> Result<T, mongodb::error::Error> {
+        T::create(&self.backend, document).await
+    }
+
+    async fn create_many(&self, documents: Vec<T>) -> Result<Vec<T>, mongodb::error::Error> {
+        T::create_many(&self.backend, documents).await
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<T>, mongodb::error::Error> {
+        T::find_by_id(&self.backend, id).await
+    }
+
+    async fn find_one(&self, filter: Document) -> Result<Option<T>, mongodb::error::Error> {
+        T::find_one(&self.backend, filter).await
+    }
+
+    async fn find_many(
+        &self,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<T>, mongodb::error::Error> {
+        T::find_many(&self.backend, filter, options).await
+    }
+
+    async fn find_all(&self) -> Result<Vec<T>, mongodb::error::Error> {
+        T::find_all(&self.backend).await
+    }
+
+    async fn find_paginated(
+        &self,
+        filter: Document,
+        query: &PaginatedQuery,
+    ) -> Result<PaginatedData<T>, mongodb::error::Error> {
+        T::find_paginated(&self.backend, filter, query).await
+    }
+
+    async fn find_list(
+        &self,
+        filter: Document,
+        query: &ListQuery,
+    ) -> Result<ListData<T>, mongodb::error::Error> {
+        T::find_list(&self.backend, filter, query).await
+    }
+
+    async fn count(&self, filter: Document) -> Result<u64, mongodb::error::Error> {
+        T::count(&self.backend, filter).await
+    }
+
+    async fn exists(&self, filter: Document) -> Result<bool, mongodb::error::Error> {
+        T::exists(&self.backend, filter).await
+    }
+
+    async fn update_by_id(
+        &self,
+        id: &str,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        T::update_by_id(&self.backend, id, update).await
+    }
+
+    async fn update_one(
+        &self,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, mongodb::error::Error> {
+        T::update_one(&self.backend, filter, update).await
+    }
+
+    async fn update_many(
+        &self,
+        filter: Document,
+        update: Document,
+    ) -> Result<u64, mongodb::error::Error> {
+        T::update_many(&self.backend, filter, update).await
+    }
+
+    async fn find_one_and_update(
+        &self,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<T>, mongodb::error::Error> {
+        T::find_one_and_update(&self.backend, filter, update).await
+    }
+
+    async fn delete_by_id(&self, id: &str) -> Result<bool, mongodb::error::Error> {
+        T::delete_by_id(&self.backend, id).await
+    }
+
+    async fn delete_one(&self, filter: Document) -> Result<bool, mongodb::error::Error> {
+        T::delete_one(&self.backend, filter).await
+    }
+
+    async fn delete_many(&self, filter: Document) -> Result<u64, mongodb::error::Error> {
+        T::delete_many(&self.backend, filter).await
+    }
+
+    async fn find_one_and_delete(
+        &self,
+        filter: Document,
+    ) -> Result<Option<T>, mongodb::error::Error> {
+        T::find_one_and_delete(&self.backend, filter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collection, CollectionRepository, MemoryBackend};
+    use mongodb::bson::doc;
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+
+    #[derive(Collection, CollectionRepository, Serialize, Deserialize, Debug, Clone)]
+    struct Account {
+        name: String,
+        active: bool,
+    }
+
+    /// 依赖注入的业务服务
+    struct AccountService {
+        accounts: Arc<dyn Repository<Account>>,
+    }
+
+    impl AccountService {
+        async fn deactivate_all(&self) -> Result<u64, mongodb::error::Error> {
+            self.accounts
+                .update_many(
+                    doc! { "active": true },
+                    doc! { "$set": { "active": false } },
+                )
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_service_with_injected_repository() {
+        let repository = MongoRepository::<Account, _>::new(MemoryBackend::new());
+        repository
+            .create(&Account {
+                name: "alice".to_string(),
+                active: true,
+            })
+            .await
+            .unwrap();
+
+        let service = AccountService {
+            accounts: Arc::new(repository.clone()),
+        };
+        assert_eq!(service.deactivate_all().await.unwrap(), 1);
+        assert!(!repository.exists(doc! { "active": true }).await.unwrap());
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_service_with_mock_repository() {
+        let mut mock = MockRepository::<Account>::new();
+        mock.expect_update_many().times(1).returning(|_, _| Ok(3));
+
+        let service = AccountService {
+            accounts: Arc::new(mock),
+        };
+        assert_eq!(service.deactivate_all().await.unwrap(), 3);
+    }
+}
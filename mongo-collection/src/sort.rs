@@ -0,0 +1,194 @@
+//! 多字段排序
+//!
+//! `sort=status,-created_at` 表示先按 `status` 升序、再按 `created_at` 降序；
+//! 生成的排序文档总会以 `_id` 结尾，保证分页结果稳定
+
+use crate::SortOrder;
+use crate::query_string::QueryError;
+use mongodb::bson::{Document, doc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// 单个排序字段
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct SortSpec {
+    /// 字段名，支持点号路径
+    pub field: String,
+    /// 排序方向
+    #[serde(default = "ascending")]
+    pub order: SortOrder,
+}
+
+fn ascending() -> SortOrder {
+    SortOrder::Asc
+}
+
+impl SortSpec {
+    /// 按 `field` 升序
+    pub fn asc(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            order: SortOrder::Asc,
+        }
+    }
+
+    /// 按 `field` 降序
+    pub fn desc(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            order: SortOrder::Desc,
+        }
+    }
+
+    /// 解析逗号分隔的排序参数，如 `status,-created_at`
+    ///
+    /// 字段名前的 `-` 表示降序，`+` 或无前缀表示升序
+    pub fn parse_list(value: &str) -> Result<Vec<Self>, QueryError> {
+        value.split(',').map(|spec| spec.trim().parse()).collect()
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = QueryError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (order, field) = match spec.strip_prefix('-') {
+            Some(field) => (SortOrder::Desc, field),
+            None => (SortOrder::Asc, spec.strip_prefix('+').unwrap_or(spec)),
+        };
+        if field.is_empty() || field.starts_with(['-', '+']) {
+            return Err(QueryError::InvalidValue {
+                field: "sort".to_string(),
+                value: spec.to_string(),
+                expected: "a field name optionally prefixed with `-` or `+`",
+            });
+        }
+        Ok(Self {
+            field: field.to_string(),
+            order,
+        })
+    }
+}
+
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.order {
+            SortOrder::Asc => f.write_str(&self.field),
+            SortOrder::Desc => write!(f, "-{}", self.field),
+        }
+    }
+}
+
+fn direction(order: &SortOrder) -> i32 {
+    match order {
+        SortOrder::Asc => 1,
+        SortOrder::Desc => -1,
+    }
+}
+
+/// 生成排序文档
+///
+/// `sort` 非空时优先于 `sort_by`；两者都未指定时按 `_id` 降序。
+/// 未包含 `_id` 时追加 `_id`，方向与最后一个字段相同
+pub(crate) fn sort_document(
+    sort: &[SortSpec],
+    sort_by: Option<&str>,
+    sort_order: &SortOrder,
+) -> Document {
+    let mut document = Document::new();
+    let mut last = -1;
+    if !sort.is_empty() {
+        for spec in sort {
+            last = direction(&spec.order);
+            document.insert(spec.field.clone(), last);
+        }
+    } else if let Some(sort_by) = sort_by {
+        last = direction(sort_order);
+        document.insert(sort_by, last);
+    }
+    if !document.contains_key("_id") {
+        document.extend(doc! { "_id": last });
+    }
+    document
+}
+
+/// 反序列化 `sort` 参数：逗号分隔的字符串，或 [`SortSpec`] 数组
+pub(crate) fn deserialize_sort<'de, D>(deserializer: D) -> Result<Vec<SortSpec>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Text(String),
+        Specs(Vec<SortSpec>),
+    }
+
+    match Raw::deserialize(deserializer)? {
+        Raw::Text(text) if text.is_empty() => Ok(Vec::new()),
+        Raw::Text(text) => SortSpec::parse_list(&text).map_err(serde::de::Error::custom),
+        Raw::Specs(specs) => Ok(specs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PaginatedQuery;
+    use mongodb::bson::deserialize_from_document;
+
+    #[test]
+    fn test_parse_and_build_sort_document() {
+        let sort = SortSpec::parse_list("status, -created_at").unwrap();
+        assert_eq!(
+            sort,
+            vec![SortSpec::asc("status"), SortSpec::desc("created_at")]
+        );
+        assert_eq!(
+            sort.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["status", "-created_at"]
+        );
+        assert_eq!(
+            sort_document(&sort, Some("ignored"), &SortOrder::Asc),
+            doc! { "status": 1, "created_at": -1, "_id": -1 }
+        );
+
+        assert_eq!(
+            sort_document(&[], Some("name"), &SortOrder::Asc),
+            doc! { "name": 1, "_id": 1 }
+        );
+        assert_eq!(
+            sort_document(&[], None, &SortOrder::Asc),
+            doc! { "_id": -1 }
+        );
+        assert_eq!(
+            sort_document(&[SortSpec::asc("_id")], None, &SortOrder::Desc),
+            doc! { "_id": 1 }
+        );
+
+        assert!(SortSpec::parse_list("status,,name").is_err());
+        assert!(SortSpec::parse_list("--name").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_sort() {
+        let query: PaginatedQuery =
+            deserialize_from_document(doc! { "sort": "-created_at" }).unwrap();
+        assert_eq!(query.sort, vec![SortSpec::desc("created_at")]);
+
+        let query: PaginatedQuery = deserialize_from_document(doc! {
+            "sort": [{ "field": "status" }, { "field": "created_at", "order": "desc" }],
+        })
+        .unwrap();
+        assert_eq!(
+            query.sort,
+            vec![SortSpec::asc("status"), SortSpec::desc("created_at")]
+        );
+        assert!(query.filters.is_none_or(|filters| filters.is_empty()));
+    }
+}
@@ -11,7 +11,14 @@ use mongodb::bson::oid::ObjectId;
 /// * `Ok(ObjectId)` - 解析成功
 /// * `Err(mongodb::error::Error)` - 解析失败
 pub(crate) fn parse_object_id(id: &str) -> Result<ObjectId, mongodb::error::Error> {
-    ObjectId::parse_str(id).map_err(|e| mongodb::error::Error::custom(e))
+    ObjectId::parse_str(id).map_err(mongodb::error::Error::custom)
+}
+
+/// 构造参数错误
+///
+/// 错误信息以 `String` 形式保存，可通过 `error.get_custom::<String>()` 取回
+pub(crate) fn invalid_argument(message: impl Into<String>) -> mongodb::error::Error {
+    mongodb::error::Error::custom(message.into())
 }
 
 #[cfg(test)]
@@ -0,0 +1,194 @@
+use crate::Collection;
+use mongodb::bson::{Document, doc, serialize_to_bson};
+use mongodb::error::{Error, ErrorKind};
+
+pub use mongodb::options::{ValidationAction, ValidationLevel};
+
+/// MongoDB 中 "集合不存在" 的错误码
+const NAMESPACE_NOT_FOUND: i32 = 26;
+
+/// `$jsonSchema` 校验器选项
+///
+/// 未设置的选项沿用服务器默认值（`strict` / `error`）
+#[derive(Debug, Clone, Default)]
+pub struct ValidatorOptions {
+    /// 校验级别
+    pub validation_level: Option<ValidationLevel>,
+    /// 校验失败时的处理方式
+    pub validation_action: Option<ValidationAction>,
+}
+
+impl ValidatorOptions {
+    /// 设置校验级别
+    pub fn validation_level(mut self, level: ValidationLevel) -> Self {
+        self.validation_level = Some(level);
+        self
+    }
+
+    /// 设置校验失败时的处理方式
+    pub fn validation_action(mut self, action: ValidationAction) -> Self {
+        self.validation_action = Some(action);
+        self
+    }
+}
+
+/// 嵌入文档的 `$jsonSchema`
+///
+/// 由 `#[derive(DocumentSchema)]` 生成；模型中标记了 `#[collection(nested)]` 的字段
+/// 用它描述子文档的字段
+pub trait DocumentSchema {
+    /// `{ "bsonType": "object", "properties": ... }` 形式的 schema
+    fn document_schema() -> Document;
+
+    /// 同时允许 `null` 的 schema，用于 `Option` 字段
+    fn nullable_document_schema() -> Document {
+        let mut schema = Self::document_schema();
+        schema.insert("bsonType", vec!["object", "null"]);
+        schema
+    }
+}
+
+/// 构建集合的 `validator` 文档
+pub fn validator_document<T: Collection>() -> Document {
+    doc! { "$jsonSchema": T::json_schema() }
+}
+
+/// 构建将集合 `name` 的校验器更新为 `validator` 的 `collMod` 命令
+pub(crate) fn coll_mod_command(
+    name: &str,
+    validator: Document,
+    options: &ValidatorOptions,
+) -> Result<Document, Error> {
+    let mut command = doc! {
+        "collMod": name,
+        "validator": validator,
+    };
+    if let Some(ref level) = options.validation_level {
+        command.insert("validationLevel", serialize_to_bson(level)?);
+    }
+    if let Some(ref action) = options.validation_action {
+        command.insert("validationAction", serialize_to_bson(action)?);
+    }
+    Ok(command)
+}
+
+/// 判断错误是否为集合不存在
+pub(crate) fn is_namespace_not_found(error: &Error) -> bool {
+    matches!(error.kind.as_ref(), ErrorKind::Command(e) if e.code == NAMESPACE_NOT_FOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DocumentSchema;
+    use mongodb::bson::Bson;
+    use serde::{Deserialize, Serialize};
+
+    #[allow(dead_code)]
+    #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    struct Profile {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        display_name: String,
+        age: i64,
+        tags: Vec<String>,
+        address: Option<Address>,
+        #[collection(nested)]
+        home: Option<Address>,
+        #[collection(nested)]
+        offices: Vec<Address>,
+        #[collection(bson_type = "string")]
+        status: Status,
+        #[serde(skip)]
+        cached: bool,
+    }
+
+    #[derive(DocumentSchema, Serialize, Deserialize, Debug, Clone)]
+    struct Address {
+        city: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    enum Status {
+        Active,
+    }
+
+    #[test]
+    fn test_derived_json_schema() {
+        let schema = Profile::json_schema();
+        assert_eq!(
+            schema,
+            doc! {
+                "bsonType": "object",
+                "required": ["displayName", "age", "tags", "offices", "status"],
+                "properties": {
+                    "_id": { "bsonType": ["string", "null", "objectId"] },
+                    "displayName": { "bsonType": "string" },
+                    "age": { "bsonType": ["long", "int"] },
+                    "tags": { "bsonType": "array", "items": { "bsonType": "string" } },
+                    "address": {},
+                    "home": {
+                        "bsonType": ["object", "null"],
+                        "required": ["city"],
+                        "properties": { "city": { "bsonType": "string" } },
+                    },
+                    "offices": {
+                        "bsonType": "array",
+                        "items": {
+                            "bsonType": "object",
+                            "required": ["city"],
+                            "properties": { "city": { "bsonType": "string" } },
+                        },
+                    },
+                    "status": { "bsonType": "string" },
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_optional_id_accepts_generated_object_id() {
+        #[allow(dead_code)]
+        #[derive(Collection, Serialize, Deserialize, Debug, Clone)]
+        struct Tag {
+            #[serde(rename = "_id", skip_serializing_if = "String::is_empty")]
+            id: String,
+            name: String,
+        }
+
+        // 省略 `_id` 插入时驱动生成 `ObjectId`，校验器必须允许
+        for schema in [Profile::json_schema(), Tag::json_schema()] {
+            let id_types = schema
+                .get_document("properties")
+                .and_then(|properties| properties.get_document("_id"))
+                .and_then(|id| id.get_array("bsonType"))
+                .unwrap();
+            assert!(id_types.contains(&Bson::String("objectId".to_string())));
+        }
+        assert!(
+            !Tag::json_schema()
+                .get_array("required")
+                .unwrap()
+                .contains(&Bson::String("_id".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_coll_mod_command() {
+        let options = ValidatorOptions::default()
+            .validation_level(ValidationLevel::Moderate)
+            .validation_action(ValidationAction::Warn);
+        let command =
+            coll_mod_command(Profile::name(), validator_document::<Profile>(), &options).unwrap();
+        assert_eq!(command.get_str("collMod").unwrap(), "profiles");
+        assert_eq!(command.get_str("validationLevel").unwrap(), "moderate");
+        assert_eq!(command.get_str("validationAction").unwrap(), "warn");
+        assert!(
+            command
+                .get_document("validator")
+                .unwrap()
+                .contains_key("$jsonSchema")
+        );
+    }
+}
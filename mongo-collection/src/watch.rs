@@ -0,0 +1,338 @@
+//! 模型变更流
+//!
+//! [`CollectionRepository::watch`](crate::CollectionRepository::watch) 把驱动的
+//! `ChangeStreamEvent<Document>` 解码为类型化的 [`ChangeEvent`]，
+//! 并通过 [`ResumeTokenStore`] 持久化恢复令牌，使消费者重启后从中断处继续。
+
+use crate::backend::ResolvedTarget;
+use crate::utils::invalid_argument;
+use async_trait::async_trait;
+use futures::StreamExt;
+use futures::stream::{self, BoxStream};
+use mongodb::Database;
+use mongodb::bson::{
+    Bson, DateTime, Document, deserialize_from_bson, deserialize_from_document, doc,
+    serialize_to_bson,
+};
+use mongodb::change_stream::ChangeStream;
+use mongodb::change_stream::event::{ChangeStreamEvent, OperationType, ResumeToken};
+use mongodb::options::FullDocumentType;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::Arc;
+
+/// 类型化的变更事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeEvent<T> {
+    /// 插入的文档
+    Insert(T),
+    /// 更新：被修改的字段与被删除的字段
+    Update {
+        /// 文档的 `_id`
+        id: Bson,
+        /// 被设置的字段及新值
+        updated_fields: Document,
+        /// 被删除的字段
+        removed_fields: Vec<String>,
+    },
+    /// 整体替换后的文档
+    Replace(T),
+    /// 被删除文档的 `_id`
+    Delete(Bson),
+}
+
+/// 恢复令牌的持久化接口
+///
+/// `key` 标识一个消费者；变更流会在消费者请求下一条事件时保存上一条事件的令牌，
+/// 因此重启后最多重复投递最后一条未处理完的事件（至少一次语义）
+#[async_trait]
+pub trait ResumeTokenStore: Send + Sync {
+    /// 读取消费者上次保存的令牌
+    async fn load(&self, key: &str) -> Result<Option<ResumeToken>, mongodb::error::Error>;
+
+    /// 保存消费者最新的令牌
+    async fn save(&self, key: &str, token: &ResumeToken) -> Result<(), mongodb::error::Error>;
+}
+
+/// 把恢复令牌保存在 MongoDB 集合中的 [`ResumeTokenStore`]
+///
+/// 每个消费者一条文档：`{ _id: key, token, updated_at }`
+#[derive(Debug, Clone)]
+pub struct CollectionTokenStore {
+    collection: mongodb::Collection<Document>,
+}
+
+impl CollectionTokenStore {
+    /// 使用 `db` 中名为 `collection` 的集合保存令牌
+    pub fn new(db: &Database, collection: &str) -> Self {
+        Self {
+            collection: db.collection(collection),
+        }
+    }
+}
+
+#[async_trait]
+impl ResumeTokenStore for CollectionTokenStore {
+    async fn load(&self, key: &str) -> Result<Option<ResumeToken>, mongodb::error::Error> {
+        let Some(document) = self.collection.find_one(doc! { "_id": key }).await? else {
+            return Ok(None);
+        };
+        match document.get("token") {
+            Some(token) => Ok(Some(deserialize_from_bson(token.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save(&self, key: &str, token: &ResumeToken) -> Result<(), mongodb::error::Error> {
+        let update = doc! {
+            "$set": { "token": serialize_to_bson(token)?, "updated_at": DateTime::now() }
+        };
+        self.collection
+            .update_one(doc! { "_id": key }, update)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+}
+
+/// 变更流选项
+///
+/// # 示例
+///
+/// ```ignore
+/// let store = Arc::new(CollectionTokenStore::new(&db, "resume_tokens"));
+/// let options = WatchOptions::new().resume_with("order-projector", store);
+/// let mut events = Order::watch_with(&db, vec![], options).await?;
+/// ```
+#[derive(Clone, Default)]
+pub struct WatchOptions {
+    resume: Option<(String, Arc<dyn ResumeTokenStore>)>,
+}
+
+impl WatchOptions {
+    /// 创建默认选项：从当前时刻开始监听，不保存令牌
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用 `store` 以 `key` 为消费者保存令牌，并从上次保存的位置继续
+    pub fn resume_with(mut self, key: impl Into<String>, store: Arc<dyn ResumeTokenStore>) -> Self {
+        self.resume = Some((key.into(), store));
+        self
+    }
+}
+
+impl fmt::Debug for WatchOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchOptions")
+            .field("resume", &self.resume.as_ref().map(|(key, _)| key))
+            .finish()
+    }
+}
+
+/// 为限定了租户的集合在管道前加上租户条件
+///
+/// 返回的布尔值表示是否需要为更新事件查询完整文档
+pub(crate) fn scope_pipeline(
+    resolved: &ResolvedTarget,
+    mut pipeline: Vec<Document>,
+) -> (Vec<Document>, bool) {
+    match (resolved.target.tenant_field.as_deref(), &resolved.tenant) {
+        (Some(field), Some(tenant)) => {
+            let stage = doc! { "$match": { format!("fullDocument.{field}"): tenant.clone() } };
+            pipeline.insert(0, stage);
+            (pipeline, true)
+        }
+        _ => (pipeline, false),
+    }
+}
+
+/// 打开集合上的变更流
+pub(crate) async fn open<T>(
+    collection: mongodb::Collection<Document>,
+    pipeline: Vec<Document>,
+    update_lookup: bool,
+    options: WatchOptions,
+) -> Result<BoxStream<'static, Result<ChangeEvent<T>, mongodb::error::Error>>, mongodb::error::Error>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let resume_after = match options.resume {
+        Some((ref key, ref store)) => store.load(key).await?,
+        None => None,
+    };
+    let mut watch = collection
+        .watch()
+        .pipeline(pipeline)
+        .resume_after(resume_after);
+    if update_lookup {
+        watch = watch.full_document(FullDocumentType::UpdateLookup);
+    }
+    let events = watch.await?;
+
+    let state = State {
+        events,
+        resume: options.resume,
+        pending: None,
+    };
+    Ok(stream::try_unfold(state, next).boxed())
+}
+
+struct State {
+    events: ChangeStream<ChangeStreamEvent<Document>>,
+    resume: Option<(String, Arc<dyn ResumeTokenStore>)>,
+    /// 已交给消费者、尚未保存的令牌
+    pending: Option<ResumeToken>,
+}
+
+async fn next<T: DeserializeOwned>(
+    mut state: State,
+) -> Result<Option<(ChangeEvent<T>, State)>, mongodb::error::Error> {
+    // 消费者请求下一条事件，说明上一条已处理完
+    if let (Some((key, store)), Some(token)) = (&state.resume, state.pending.take()) {
+        store.save(key, &token).await?;
+    }
+
+    while let Some(event) = state.events.next().await {
+        let event = event?;
+        state.pending = Some(event.id.clone());
+        if let Some(change) = decode(event)? {
+            return Ok(Some((change, state)));
+        }
+    }
+    Ok(None)
+}
+
+/// 解码驱动事件；集合删除、重命名等非文档事件返回 `None`
+pub(crate) fn decode<T: DeserializeOwned>(
+    event: ChangeStreamEvent<Document>,
+) -> Result<Option<ChangeEvent<T>>, mongodb::error::Error> {
+    let document_id = |event: &ChangeStreamEvent<Document>| {
+        event
+            .document_key
+            .as_ref()
+            .and_then(|key| key.get("_id"))
+            .cloned()
+            .ok_or_else(|| invalid_argument("change event has no documentKey._id"))
+    };
+    let full_document = |event: ChangeStreamEvent<Document>| {
+        let document = event
+            .full_document
+            .ok_or_else(|| invalid_argument("change event has no fullDocument"))?;
+        deserialize_from_document::<T>(document).map_err(mongodb::error::Error::from)
+    };
+
+    let change = match event.operation_type {
+        OperationType::Insert => ChangeEvent::Insert(full_document(event)?),
+        OperationType::Replace => ChangeEvent::Replace(full_document(event)?),
+        OperationType::Delete => ChangeEvent::Delete(document_id(&event)?),
+        OperationType::Update => {
+            let id = document_id(&event)?;
+            let description = event
+                .update_description
+                .ok_or_else(|| invalid_argument("update event has no updateDescription"))?;
+            ChangeEvent::Update {
+                id,
+                updated_fields: description.updated_fields,
+                removed_fields: description.removed_fields,
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(change))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Order {
+        _id: i32,
+        status: String,
+    }
+
+    fn event(operation_type: &str, extra: Document) -> ChangeStreamEvent<Document> {
+        let mut event = doc! {
+            "_id": { "_data": "8263" },
+            "operationType": operation_type,
+            "ns": { "db": "shop", "coll": "orders" },
+        };
+        event.extend(extra);
+        deserialize_from_document(event).unwrap()
+    }
+
+    #[test]
+    fn test_decode_document_events() {
+        let full = doc! { "_id": 1, "status": "open" };
+        let order = Order {
+            _id: 1,
+            status: "open".to_string(),
+        };
+
+        let insert = event(
+            "insert",
+            doc! { "documentKey": { "_id": 1 }, "fullDocument": full.clone() },
+        );
+        assert_eq!(
+            decode::<Order>(insert).unwrap(),
+            Some(ChangeEvent::Insert(order))
+        );
+
+        let update = event(
+            "update",
+            doc! {
+                "documentKey": { "_id": 1 },
+                "updateDescription": { "updatedFields": { "status": "paid" }, "removedFields": ["note"] },
+            },
+        );
+        assert_eq!(
+            decode::<Order>(update).unwrap(),
+            Some(ChangeEvent::Update {
+                id: Bson::Int32(1),
+                updated_fields: doc! { "status": "paid" },
+                removed_fields: vec!["note".to_string()],
+            })
+        );
+
+        let delete = event("delete", doc! { "documentKey": { "_id": 1 } });
+        assert_eq!(
+            decode::<Order>(delete).unwrap(),
+            Some(ChangeEvent::Delete(Bson::Int32(1)))
+        );
+
+        assert_eq!(decode::<Order>(event("drop", doc! {})).unwrap(), None);
+        // 缺少 fullDocument 的插入事件无法解码
+        assert!(decode::<Order>(event("insert", doc! {})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scope_pipeline_matches_tenant_first() {
+        use crate::backend::{Backend, BackendExt, CollectionTarget};
+
+        let client = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        let db = client.database("shop");
+        let mut target = CollectionTarget::new("orders");
+        target.tenant_field = Some("tenant_id".into());
+        let user_stage = doc! { "$match": { "operationType": "insert" } };
+
+        let resolved = db.for_tenant("acme").resolve(&target).unwrap();
+        let (pipeline, lookup) = scope_pipeline(&resolved, vec![user_stage.clone()]);
+        assert_eq!(
+            pipeline,
+            vec![
+                doc! { "$match": { "fullDocument.tenant_id": "acme" } },
+                user_stage.clone()
+            ]
+        );
+        assert!(lookup);
+
+        let resolved = db.resolve(&target).unwrap();
+        let (pipeline, lookup) = scope_pipeline(&resolved, vec![user_stage.clone()]);
+        assert_eq!(pipeline, vec![user_stage]);
+        assert!(!lookup);
+    }
+}